@@ -0,0 +1,55 @@
+//! Collecting every engine's results into a set pre-sized from the number of engines,
+//! against one that starts empty and rehashes as it grows
+//!
+//! Run with `cargo bench --bench presize`.
+
+use std::collections::HashSet;
+use std::hint::black_box;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use strum::VariantArray;
+use sublist3r_rs::enumerate::{EXPECTED_PER_ENGINE, EngineChoice};
+
+/// Results of a large run, each engine finding `per_engine` names, half of them shared
+fn results(per_engine: usize) -> Vec<Vec<String>> {
+    (0..EngineChoice::VARIANTS.len())
+        .map(|engine| {
+            (0..per_engine)
+                .map(|i| {
+                    let owner = if i % 2 == 0 { 0 } else { engine };
+                    format!("host{owner}-{i}.example.com")
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn collect(results: &[Vec<String>], capacity: usize) -> HashSet<String> {
+    let mut found = HashSet::with_capacity(capacity);
+    for names in results {
+        found.extend(names.iter().cloned());
+    }
+    found
+}
+
+fn presize(c: &mut Criterion) {
+    let engines = EngineChoice::VARIANTS.len();
+    let mut group = c.benchmark_group("collect");
+    for per_engine in [EXPECTED_PER_ENGINE, 10 * EXPECTED_PER_ENGINE] {
+        let results = results(per_engine);
+        group.bench_with_input(BenchmarkId::new("empty", per_engine), &results, |b, r| {
+            b.iter(|| collect(black_box(r), 0));
+        });
+        group.bench_with_input(
+            BenchmarkId::new("presized", per_engine),
+            &results,
+            |b, r| {
+                b.iter(|| collect(black_box(r), engines * EXPECTED_PER_ENGINE));
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, presize);
+criterion_main!(benches);
@@ -6,7 +6,9 @@ use syn::DeriveInput;
 /// Derives the `Extract` trait for a struct.
 ///
 /// This macro generates an implementation of the `Extract` trait that uses regex pattern matching
-/// to extract values from an input string.
+/// to extract values from an input string. Inputs are capped at `MAX_REGEX_INPUT_LEN` bytes,
+/// which must be in scope alongside `Extract`. The regex is compiled once per domain, and shared
+/// by every value of the struct enumerating that domain.
 ///
 /// # Attributes
 ///
@@ -93,20 +95,172 @@ fn impl_extract_trait(item: TokenStream) -> darling::Result<TokenStream> {
 
     // generate impl
     Ok(quote! {
-        static __RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+        static __RE: std::sync::LazyLock<
+            std::sync::Mutex<std::collections::HashMap<std::string::String, regex::Regex>>,
+        > = std::sync::LazyLock::new(Default::default);
 
         impl #impl_generics Extract for #ident #type_generics #where_clause {
             fn extract(&mut self, input: &str) -> std::collections::HashSet<std::string::String> {
-                let re = __RE.get_or_init(|| {
-                    let domain = self.#domain_ident.replace(".", r"\.");
-                    let pat = format!(#pattern);
-                    regex::Regex::new(&pat).expect("failed to compile regex")
-                });
+                // cloning a compiled regex is cheap, it's shared behind the scenes
+                let re = __RE
+                    .lock()
+                    .unwrap()
+                    .entry(self.#domain_ident.clone())
+                    .or_insert_with(|| {
+                        let domain = self.#domain_ident.replace(".", r"\.");
+                        let pat = format!(#pattern);
+                        regex::Regex::new(&pat).expect("failed to compile regex")
+                    })
+                    .clone();
 
                 re.captures_iter(input)
                     .map(|c| c[#group_name].to_owned())
                     .collect()
             }
+
+            fn max_input_len(&self) -> Option<usize> {
+                Some(MAX_REGEX_INPUT_LEN)
+            }
+
+            fn domain(&self) -> Option<&str> {
+                Some(&self.#domain_ident)
+            }
+        }
+    }
+    .into())
+}
+
+/// Derives the `Extract` trait for a struct, reading subdomains from a JSON response.
+///
+/// The generated implementation walks the response along `path` and collects every string
+/// found there. Entries may hold several names separated by whitespace. Only the domain and
+/// its subdomains are kept.
+///
+/// # Attributes
+///
+/// - `#[api_extract(path = "...")]` (required): Dot-separated keys leading to the names, where
+///   `*` stands for every element of an array, e.g. `"passive_dns.*.hostname"`. An empty path
+///   is the response itself, and a path ending at an array takes each of its elements.
+/// - `#[api_extract(labels)]` (optional): Entries are bare labels such as `www` rather than
+///   fully qualified names, the domain is appended to each.
+/// - `#[api_extract(domain)]` (field attribute, required): Marks a field as the domain field.
+///   This field must be a `String`.
+///
+/// # Example
+///
+/// ```ignore
+/// use enumerate_derive::ApiExtract;
+///
+/// // {"subdomains": ["www", "api"]}
+/// #[derive(ApiExtract)]
+/// #[api_extract(path = "subdomains", labels)]
+/// struct SubdomainList {
+///     #[api_extract(domain)]
+///     domain: String,
+/// }
+/// ```
+#[proc_macro_derive(ApiExtract, attributes(api_extract))]
+pub fn api_extract_derive_macro(item: TokenStream) -> TokenStream {
+    impl_api_extract_trait(item).unwrap_or_else(|e| e.write_errors().into())
+}
+
+#[derive(darling::FromDeriveInput)]
+#[darling(attributes(api_extract), supports(struct_named))]
+struct ApiExtractDeriveInput {
+    ident: syn::Ident,
+
+    data: darling::ast::Data<(), ApiExtractFieldReceiver>,
+
+    path: String,
+
+    #[darling(default)]
+    labels: bool,
+}
+
+#[derive(FromField)]
+#[darling(attributes(api_extract))]
+struct ApiExtractFieldReceiver {
+    ident: Option<syn::Ident>,
+
+    ty: syn::Type,
+
+    #[darling(default)]
+    domain: bool,
+}
+
+fn impl_api_extract_trait(item: TokenStream) -> darling::Result<TokenStream> {
+    // parse & extract attributes
+    let ast: DeriveInput = syn::parse(item).unwrap();
+    let ApiExtractDeriveInput {
+        ident,
+        data,
+        path,
+        labels,
+    } = ApiExtractDeriveInput::from_derive_input(&ast)?;
+    let keys: Vec<&str> = path.split('.').filter(|k| !k.is_empty()).collect();
+
+    // extract fields
+    let ApiExtractFieldReceiver {
+        ident: domain_ident,
+        ty: domain_ty,
+        ..
+    } = data
+        .take_struct()
+        .expect("should only be named structs")
+        .into_iter()
+        .find(|f| f.domain)
+        .ok_or_else(|| darling::Error::custom("no fields marked as domain"))?;
+
+    if !is_string_type(&domain_ty) {
+        return Err(darling::Error::unexpected_type(
+            "domain field must be a String",
+        ));
+    }
+
+    // define impl variables
+    let (impl_generics, type_generics, where_clause) = ast.generics.split_for_impl();
+
+    // generate impl
+    Ok(quote! {
+        impl #impl_generics Extract for #ident #type_generics #where_clause {
+            fn extract(&mut self, input: &str) -> std::collections::HashSet<std::string::String> {
+                let Ok(root) = serde_json::from_str::<serde_json::Value>(input) else {
+                    return std::collections::HashSet::new();
+                };
+
+                let mut nodes = vec![&root];
+                for key in [#(#keys),*] {
+                    nodes = nodes
+                        .into_iter()
+                        .flat_map(|n| match (key, n) {
+                            ("*", serde_json::Value::Array(items)) => items.iter().collect(),
+                            (k, n) => n.get(k).into_iter().collect::<Vec<_>>(),
+                        })
+                        .collect();
+                }
+
+                let domain = &self.#domain_ident;
+                nodes
+                    .into_iter()
+                    // a path ending at a list of names, rather than at each name
+                    .flat_map(|n| match n {
+                        serde_json::Value::Array(items) => items.iter().collect(),
+                        n => vec![n],
+                    })
+                    .filter_map(serde_json::Value::as_str)
+                    .flat_map(str::split_whitespace)
+                    .map(|s| if #labels { format!("{s}.{domain}") } else { s.to_owned() })
+                    .filter(|s| {
+                        s == domain
+                            || s.strip_suffix(domain.as_str())
+                                .is_some_and(|rest| rest.ends_with('.'))
+                    })
+                    .collect()
+            }
+
+            fn domain(&self) -> Option<&str> {
+                Some(&self.#domain_ident)
+            }
         }
     }
     .into())
@@ -0,0 +1,95 @@
+use std::collections::{BTreeMap, HashSet};
+
+/// Registrable domain of a host according to the public suffix list
+///
+/// For example, `app.example.co.uk` belongs to `example.co.uk`, not `co.uk`.
+#[must_use]
+pub fn registrable(host: &str) -> Option<&str> {
+    psl::domain_str(host.trim_end_matches('.'))
+}
+
+/// Whether `host` has the same registrable domain as `domain`
+///
+/// Catches results for unrelated domains, e.g. other names on a shared certificate.
+#[must_use]
+pub fn same_apex(host: &str, domain: &str) -> bool {
+    match (registrable(host), registrable(domain)) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        _ => false,
+    }
+}
+
+/// Group hosts by registrable domain, each group sorted alphabetically
+///
+/// Hosts without a registrable domain (e.g. a bare public suffix) are dropped.
+pub fn group<I>(hosts: I) -> BTreeMap<String, Vec<String>>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut groups: BTreeMap<String, HashSet<String>> = BTreeMap::new();
+    for host in hosts {
+        if let Some(apex) = registrable(&host) {
+            let apex = apex.to_ascii_lowercase();
+            groups.entry(apex).or_default().insert(host);
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(apex, hosts)| {
+            let mut hosts: Vec<_> = hosts.into_iter().collect();
+            hosts.sort_unstable();
+            (apex, hosts)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::apex("example.com", Some("example.com"))]
+    #[case::subdomain("app.example.com", Some("example.com"))]
+    #[case::multi_level_suffix("app.example.co.uk", Some("example.co.uk"))]
+    #[case::deep("a.b.example.co.uk", Some("example.co.uk"))]
+    #[case::trailing_dot("app.example.com.", Some("example.com"))]
+    #[case::wildcard("*.example.com", Some("example.com"))]
+    #[case::suffix_only("co.uk", None)]
+    fn test_registrable(#[case] host: &str, #[case] expected: Option<&str>) {
+        assert_eq!(registrable(host), expected);
+    }
+
+    #[rstest]
+    #[case::same("app.example.com", "example.com", true)]
+    #[case::queried_subdomain("api.example.com", "dev.example.com", true)]
+    #[case::other_domain("app.example.net", "example.com", false)]
+    #[case::shared_suffix("other.co.uk", "example.co.uk", false)]
+    #[case::lookalike("example.com.evil.net", "example.com", false)]
+    fn test_same_apex(#[case] host: &str, #[case] domain: &str, #[case] expected: bool) {
+        assert_eq!(same_apex(host, domain), expected);
+    }
+
+    #[test]
+    fn test_group() {
+        let hosts = [
+            "www.example.co.uk",
+            "app.example.com",
+            "api.example.co.uk",
+            "example.com",
+            "co.uk",
+        ]
+        .map(String::from);
+
+        let groups = group(hosts);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups["example.com"], ["app.example.com", "example.com"]);
+        assert_eq!(
+            groups["example.co.uk"],
+            ["api.example.co.uk", "www.example.co.uk"]
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::enumerate::EngineChoice;
+use crate::recurse::Frontier;
+use crate::stats::RunStats;
+
+/// Progress of a run across its targets, saved with `--checkpoint` and picked back up with `--resume`
+///
+/// It's saved each time an engine is done with a target and once the target is done,
+/// so an interrupted run resumes from the target it was on, without the engines that were done.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Targets left to enumerate
+    pub frontier: Frontier,
+    /// Targets already enumerated, with what they found and how many each engine found
+    pub runs: Vec<RunStats>,
+    /// Target being enumerated, and what the engines done with it found
+    #[serde(default)]
+    pub current: Option<InProgress>,
+}
+
+/// A target some engines may already be done with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InProgress {
+    pub domain: String,
+    pub depth: usize,
+    /// What each engine that's done found, before post-processing
+    pub engines: BTreeMap<EngineChoice, Vec<String>>,
+}
+
+impl Checkpoint {
+    #[must_use]
+    pub fn new(frontier: Frontier) -> Self {
+        Self {
+            frontier,
+            runs: Vec::new(),
+            current: None,
+        }
+    }
+
+    /// Load the checkpoint saved at `path`
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read or doesn't hold a checkpoint
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read checkpoint {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("failed to parse checkpoint {}", path.display()))
+    }
+
+    /// Save to a temporary file first, so an interrupted save doesn't lose the last checkpoint
+    ///
+    /// # Errors
+    ///
+    /// If the checkpoint can't be written or moved into place
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        let content = serde_json::to_string(self)?;
+        std::fs::write(&tmp, content)
+            .and_then(|()| std::fs::rename(&tmp, path))
+            .with_context(|| format!("failed to write checkpoint {}", path.display()))
+    }
+
+    /// Next target to enumerate and its depth, the one interrupted first if any
+    pub fn pop(&mut self) -> Option<(String, usize)> {
+        if let Some(current) = &self.current {
+            return Some((current.domain.clone(), current.depth));
+        }
+        let (domain, depth) = self.frontier.pop()?;
+        self.current = Some(InProgress {
+            domain: domain.clone(),
+            depth,
+            engines: BTreeMap::new(),
+        });
+        Some((domain, depth))
+    }
+
+    /// Record what `engine` found for the current target
+    pub fn engine_done(&mut self, engine: EngineChoice, found: &HashSet<String>) {
+        if let Some(current) = &mut self.current {
+            let mut names: Vec<_> = found.iter().cloned().collect();
+            names.sort_unstable();
+            current.engines.insert(engine, names);
+        }
+    }
+
+    /// What the engines already done with the current target found
+    #[must_use]
+    pub fn finished(&self) -> HashMap<EngineChoice, Vec<String>> {
+        self.current
+            .iter()
+            .flat_map(|c| c.engines.clone())
+            .collect()
+    }
+
+    /// Record what a target at `depth` found, queueing its subdomains one level deeper
+    pub fn complete(&mut self, depth: usize, stats: RunStats) {
+        self.current = None;
+        self.frontier
+            .push_found(&stats.domain, depth, &stats.subdomains);
+        self.runs.push(stats);
+    }
+
+    /// Move on from the current target without recording anything for it
+    pub fn skip(&mut self) {
+        self.current = None;
+    }
+
+    /// Every subdomain found so far, across targets
+    #[must_use]
+    pub fn found(&self) -> BTreeSet<String> {
+        self.runs
+            .iter()
+            .flat_map(|r| r.subdomains.iter().cloned())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::recurse::MAX_RECURSIVE_TARGETS;
+
+    fn results() -> HashMap<&'static str, Vec<&'static str>> {
+        HashMap::from([
+            ("example.com", vec!["a.example.com", "b.example.com"]),
+            ("a.example.com", vec!["x.a.example.com", "y.a.example.com"]),
+            ("b.example.com", vec!["x.b.example.com"]),
+            ("x.a.example.com", vec!["deep.x.a.example.com"]),
+        ])
+    }
+
+    fn start() -> Checkpoint {
+        let mut frontier = Frontier::new(2, MAX_RECURSIVE_TARGETS);
+        frontier.push("example.com", 0);
+        Checkpoint::new(frontier)
+    }
+
+    /// Enumerate up to `limit` targets with `results` standing in for the engines,
+    /// saving after each like the CLI does
+    fn drive(checkpoint: &mut Checkpoint, path: &Path, limit: usize) {
+        let results = results();
+        for _ in 0..limit {
+            let Some((domain, depth)) = checkpoint.pop() else {
+                break;
+            };
+            let subdomains = results
+                .get(domain.as_str())
+                .into_iter()
+                .flatten()
+                .map(|s| (*s).to_owned())
+                .collect();
+            let stats = RunStats {
+                domain,
+                subdomains,
+                ..Default::default()
+            };
+            checkpoint.complete(depth, stats);
+            checkpoint.save(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_resume_finds_the_same() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let uninterrupted_path = dir.path().join("uninterrupted.json");
+        let mut uninterrupted = start();
+        drive(&mut uninterrupted, &uninterrupted_path, usize::MAX);
+
+        // interrupted in the middle of the second target, after the first was saved
+        let path = dir.path().join("checkpoint.json");
+        let mut interrupted = start();
+        drive(&mut interrupted, &path, 1);
+        assert!(interrupted.pop().is_some());
+        drop(interrupted);
+
+        let mut resumed = Checkpoint::load(&path).unwrap();
+        drive(&mut resumed, &path, usize::MAX);
+
+        assert_eq!(resumed.found(), uninterrupted.found());
+        assert_eq!(resumed.runs, uninterrupted.runs);
+        // every target down to the max depth was enumerated once
+        assert_eq!(resumed.runs.len(), 6);
+    }
+
+    #[test]
+    fn test_resume_mid_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("checkpoint.json");
+        let found = HashSet::from(["app.example.com".to_owned()]);
+
+        // interrupted after one engine was done with the first target
+        let mut interrupted = start();
+        assert_eq!(interrupted.pop(), Some(("example.com".to_owned(), 0)));
+        interrupted.engine_done(EngineChoice::CrtSh, &found);
+        interrupted.save(&path).unwrap();
+        drop(interrupted);
+
+        let mut resumed = Checkpoint::load(&path).unwrap();
+        assert_eq!(resumed.pop(), Some(("example.com".to_owned(), 0)));
+        assert_eq!(
+            resumed.finished(),
+            HashMap::from([(EngineChoice::CrtSh, vec!["app.example.com".to_owned()])])
+        );
+
+        resumed.complete(
+            0,
+            RunStats {
+                domain: "example.com".to_owned(),
+                subdomains: vec!["app.example.com".to_owned()],
+                ..Default::default()
+            },
+        );
+        assert!(resumed.finished().is_empty());
+        assert_eq!(resumed.pop(), Some(("app.example.com".to_owned(), 1)));
+    }
+
+    #[test]
+    fn test_load_missing() {
+        let err = Checkpoint::load(Path::new("does-not-exist.json")).unwrap_err();
+        assert!(err.to_string().contains("failed to read checkpoint"));
+    }
+}
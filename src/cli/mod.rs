@@ -1,11 +1,22 @@
+use std::fmt;
+use std::path::PathBuf;
 use std::str::FromStr;
 
-use clap::{Command, Parser};
+use clap::builder::RangedU64ValueParser;
+use clap::{Command, Parser, Subcommand, ValueEnum};
 use clap_complete::{Generator, Shell, generate};
-use owo_colors::OwoColorize;
+use owo_colors::{OwoColorize, Stream};
+use regex::Regex;
+use reqwest::header::{HeaderName, HeaderValue};
 use url::{Host, Url};
 
-use crate::enumerate::EngineChoice;
+use crate::config::Profile;
+use crate::enumerate::delay::DelayKind;
+use crate::enumerate::hosts::DEFAULT_PER_HOST_CONCURRENCY;
+use crate::enumerate::profile::DEFAULT_ITERATIONS;
+use crate::enumerate::{DEFAULT_CIRCUIT_BREAKER, EngineChoice, MAX_BACKOFF};
+use crate::output::Format;
+use crate::{PivotScope, SortBy};
 
 pub const BANNER: &str = r"
             _____    
@@ -24,8 +35,56 @@ WARNING:
 [!] Please respect the terms of use of all data sources used by this tool.
 ";
 
+#[must_use]
 pub fn banner() -> String {
-    format!("{}\n{}", BANNER.purple(), WARNINGS.yellow())
+    format!("{}\n{}", art(), warnings())
+}
+
+/// The ASCII art of the banner, without the warnings
+#[must_use]
+pub fn art() -> String {
+    BANNER
+        .if_supports_color(Stream::Stdout, |t| t.purple())
+        .to_string()
+}
+
+/// The warnings of the banner, without the ASCII art
+#[must_use]
+pub fn warnings() -> String {
+    WARNINGS
+        .if_supports_color(Stream::Stdout, |t| t.yellow())
+        .to_string()
+}
+
+/// When to color the output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Color {
+    /// Only when writing to a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    /// Whether to color output written to a stream, given if that stream is a terminal
+    #[must_use]
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            Color::Auto => is_terminal && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+            Color::Always => true,
+            Color::Never => false,
+        }
+    }
+
+    /// Apply the choice to every colored print, `Auto` is left to terminal detection
+    pub fn apply(self) {
+        match self {
+            Color::Auto => owo_colors::unset_override(),
+            Color::Always => owo_colors::set_override(true),
+            Color::Never => owo_colors::set_override(false),
+        }
+    }
 }
 
 /// A Rust rewrite of Sublist3r
@@ -35,41 +94,409 @@ pub fn banner() -> String {
 #[command(before_help = banner(), before_long_help = banner())]
 #[command(arg_required_else_help = true)]
 #[command(verbatim_doc_comment, propagate_version = true)]
+#[command(subcommand_negates_reqs = true)]
+// flags switch independent behaviors on and off, they don't form a state machine
+#[allow(clippy::struct_excessive_bools)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
     /// Domain name to enumerate it's subdomains
-    #[arg(short, long, required_unless_present = "completion")]
+    #[arg(short, long, required_unless_present_any = ["completion", "version_json", "selftest", "tld_expand", "ndjson", "resume"])]
     pub domain: Option<Domain>,
 
+    /// Enumerate a brand across common TLDs, e.g. "example" for example.com, example.net, ...
+    #[arg(long, value_name = "BRAND", conflicts_with = "domain", value_parser = parse_brand)]
+    pub tld_expand: Option<String>,
+
+    /// File with the TLDs to expand the brand with, one per line [default: built-in list]
+    #[arg(long, value_name = "FILE", requires = "tld_expand")]
+    pub tld_list: Option<PathBuf>,
+
     /// Specify a comma-separated list of search engines
     #[arg(short, long, value_delimiter = ',')]
     pub engines: Vec<EngineChoice>,
 
+    /// Also run active engines, which contact the target's own servers (e.g. axfr zone transfers)
+    #[arg(long)]
+    pub active: bool,
+
+    /// Only use the engines that found subdomains in the last run saved with --save-last-run
+    #[arg(long, conflicts_with = "engines")]
+    pub engines_from_last_run: bool,
+
+    /// Record how many subdomains each engine found, for a later --engines-from-last-run
+    #[arg(long)]
+    pub save_last_run: bool,
+
     /// Enable Verbosity and display results in realtime
     #[arg(short, long)]
     pub verbose: bool,
 
+    /// When to color the output
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = Color::Auto)]
+    pub color: Color,
+
+    /// Don't print the ASCII art banner, the warnings are still printed
+    #[arg(long)]
+    pub no_banner: bool,
+
+    /// Only keep subdomains reported by at least N engines
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    pub min_sources: usize,
+
+    /// Only list the subdomains this engine found, to compare engines within one run
+    #[arg(long, value_name = "ENGINE", conflicts_with = "pivot_sameip")]
+    pub list_found_by: Option<EngineChoice>,
+
+    /// Report how many subdomains each engine found that no other engine did
+    #[arg(long)]
+    pub sources_report: bool,
+
+    /// Cap the number of rounds of every engine, never raises an engine's own limit
+    #[arg(long, value_name = "N")]
+    pub max_rounds: Option<usize>,
+
+    /// Seconds to back off after an engine's first failed search, below 16 [default: per engine]
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..MAX_BACKOFF))]
+    pub backoff_base: Option<u64>,
+
+    /// Multiplier applied to the backoff after each failed search [default: per engine]
+    #[arg(long, value_name = "FACTOR", value_parser = clap::value_parser!(u64).range(1..))]
+    pub backoff_factor: Option<u64>,
+
+    /// Maximum number of engines running at once [default: all]
+    #[arg(long, value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub concurrency: Option<usize>,
+
+    /// Maximum number of requests in flight to the same host, across engines
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_PER_HOST_CONCURRENCY, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub per_host_concurrency: usize,
+
+    /// Minimum milliseconds between any two requests, across engines, on top of their own delays
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub min_interval: Option<u64>,
+
+    /// Run engines one at a time in a fixed order, for reproducible logs when debugging
+    #[arg(long, conflicts_with = "concurrency")]
+    pub sequential: bool,
+
+    /// Start engines that usually find the most first, e.g. crt.sh before Yahoo, when not all run at once
+    #[arg(long, conflicts_with = "sequential")]
+    pub engines_weighted: bool,
+
+    /// Per-engine priority for --engines-weighted, higher starts first, e.g. "yahoo=95,crtsh=10"
+    #[arg(long, value_name = "ENGINE=N", value_delimiter = ',', value_parser = parse_engine_priority, requires = "engines_weighted")]
+    pub engine_priority: Vec<(EngineChoice, u8)>,
+
+    /// Seconds after which engines that haven't started yet are skipped, running ones still finish
+    #[arg(long, value_name = "SECS")]
+    pub engines_timeout_budget: Option<u64>,
+
+    /// Per-engine delay between pages in milliseconds, e.g. "google=2000,bing=500"
+    #[arg(long, value_name = "ENGINE=MS", value_delimiter = ',', value_parser = parse_engine_delay)]
+    pub engine_delay: Vec<(EngineChoice, u64)>,
+
+    /// Per-engine number of results asked for per page, e.g. "google=50", clamped to what each engine serves
+    #[arg(long, value_name = "ENGINE=N", value_delimiter = ',', value_parser = parse_engine_per_page)]
+    pub per_page: Vec<(EngineChoice, usize)>,
+
+    /// Report wildcard certificate names from crt.sh, e.g. "*.example.com", besides the host they cover
+    #[arg(long)]
+    pub keep_wildcards: bool,
+
+    /// How the delay between pages changes from page to page [default: fixed]
+    #[arg(long, value_enum, value_name = "STRATEGY")]
+    pub delay_strategy: Option<DelayKind>,
+
+    /// Per-engine delay strategy, e.g. "google=adaptive,crtsh=fixed", overrides --delay-strategy
+    #[arg(long, value_name = "ENGINE=STRATEGY", value_delimiter = ',', value_parser = parse_engine_delay_strategy)]
+    pub engine_delay_strategy: Vec<(EngineChoice, DelayKind)>,
+
+    /// Extra header sent with one engine's searches, e.g. "google:Cookie=CONSENT=YES+" (repeatable)
+    #[arg(long, value_name = "ENGINE:KEY=VALUE", value_parser = parse_engine_header)]
+    pub engine_header: Vec<(EngineChoice, HeaderName, HeaderValue)>,
+
+    /// Seed the random jitter of the jittered delay strategy, for reproducible timing
+    #[arg(long, value_name = "N")]
+    pub retry_jitter_seed: Option<u64>,
+
+    /// Largest response body to read in bytes, larger ones count as failed searches [default: 50 MiB]
+    #[arg(long, value_name = "BYTES", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub max_body_size: Option<usize>,
+
+    /// Give up on an engine after this many failed searches in a row without a single success
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_CIRCUIT_BREAKER, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub circuit_breaker: usize,
+
+    /// Keep ports, paths and query strings in results as extracted
+    #[arg(long)]
+    pub no_clean: bool,
+
+    /// Only keep subdomains that resolve to an IP address
+    #[arg(long)]
+    pub resolve: bool,
+
+    /// Reverse resolve IP addresses to discover more subdomains
+    #[arg(long, requires = "resolve")]
+    pub resolve_ptr: bool,
+
+    /// Milliseconds a single DNS lookup may take before the name counts as not resolving
+    #[arg(long, value_name = "MS", default_value_t = 2000, requires = "resolve")]
+    pub resolve_timeout: u64,
+
+    /// Keep subdomains that only resolve to private or loopback addresses
+    #[arg(long, requires = "resolve")]
+    pub allow_private: bool,
+
+    /// Only keep subdomains that answer over HTTPS or HTTP, using HEAD requests to skip bodies
+    #[arg(long)]
+    pub probe: bool,
+
+    /// After probing, only keep subdomains that answer over HTTPS
+    #[arg(long, requires = "probe")]
+    pub https_only: bool,
+
+    /// Treat www.sub.example.com and sub.example.com as the same host, keeping the latter
+    #[arg(long)]
+    pub collapse_www: bool,
+
+    /// Only keep subdomains matching this regex (repeatable)
+    #[arg(long, value_name = "REGEX")]
+    pub include_pattern: Vec<Regex>,
+
+    /// Drop subdomains matching this regex (repeatable)
+    #[arg(long, value_name = "REGEX")]
+    pub exclude_pattern: Vec<Regex>,
+
+    /// Write results to a file instead of the terminal, "-" for stdout
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Print results to the terminal as well as writing them to the output file
+    #[arg(long, requires = "output")]
+    pub also_stdout: bool,
+
+    /// How results are printed and saved, "none" to only write the output file or get the exit code,
+    /// "dnsx" for "host [A] ip" lines of the resolved addresses (needs --resolve)
+    #[arg(short, long, value_enum, default_value_t = Format::Text, requires_if("dnsx", "resolve"))]
+    pub format: Format,
+
+    /// Gzip the output file, implied when its name ends in ".gz"
+    #[arg(long, requires = "output")]
+    pub compress: bool,
+
+    /// Append subdomains to the output file as they're found, so an interrupted run keeps them
+    #[arg(long, requires = "output", conflicts_with_all = ["output_template_file", "compress"])]
+    pub output_incremental: bool,
+
+    /// Append the subdomains found so far to the output file every SECS seconds, for long runs
+    #[arg(long, value_name = "SECS", requires = "output", conflicts_with_all = ["output_incremental", "output_template_file", "compress"], value_parser = clap::value_parser!(u64).range(1..))]
+    pub flush_interval: Option<u64>,
+
+    /// Start the saved results with a "#" comment naming the domain, time, engines and count
+    #[arg(long, requires = "output", conflicts_with = "output_template_file")]
+    pub metadata_header: bool,
+
+    /// Print and save only the labels in front of the domain, e.g. "v2.api" for v2.api.example.com
+    #[arg(long, conflicts_with = "output_template_file")]
+    pub strip_domain: bool,
+
+    /// Sort results by name, lowest resolved IP (with --resolve) or probe status (with --probe)
+    #[arg(long, value_enum, value_name = "KEY", default_value_t = SortBy::Name)]
+    pub output_sort_by: SortBy,
+
+    /// Mark subdomains whose first crt.sh certificate was issued in the last DAYS days with "[new]"
+    #[arg(long, value_name = "DAYS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub new_within: Option<u64>,
+
+    /// Render results once through a Jinja-style template file instead of one per line
+    #[arg(long, value_name = "PATH")]
+    pub output_template_file: Option<PathBuf>,
+
+    /// POST the results as a JSON report to this URL once the run finishes
+    #[arg(long, value_name = "URL")]
+    pub webhook: Option<Url>,
+
+    /// Also POST each subdomain to the webhook as soon as an engine finds it
+    #[arg(long, requires = "webhook")]
+    pub webhook_stream: bool,
+
+    /// Write run metrics to this file in Prometheus text format, e.g. for `node_exporter`
+    #[arg(long, value_name = "PATH")]
+    pub stats_prometheus: Option<PathBuf>,
+
+    /// Add known subdomains from a file, one per line; enumeration is skipped unless engines are given
+    #[arg(long, value_name = "FILE")]
+    pub input_subdomains: Option<PathBuf>,
+
+    /// Only use HTTP/1.1, useful for engines that stall over HTTP/2 (e.g. Bing)
+    #[arg(long)]
+    pub http1_only: bool,
+
+    /// Send every request through this proxy, e.g. `http://127.0.0.1:8080`, instead of `HTTPS_PROXY` and the like
+    #[arg(long, value_name = "URL")]
+    pub proxy: Option<Url>,
+
+    /// Ignore the proxies set in the environment, e.g. `HTTPS_PROXY`
+    #[arg(long, conflicts_with = "proxy")]
+    pub no_proxy: bool,
+
+    /// Warn at startup if the built-in Chrome User-Agent is older than the known good version
+    #[arg(long)]
+    pub warn_on_stale_ua: bool,
+
+    /// Query RapidDNS for other hosts sharing an IP with discovered subdomains
+    #[arg(long)]
+    pub pivot_sameip: bool,
+
+    /// Which hosts found by pivoting are kept [default: domain]
+    #[arg(long, value_enum)]
+    pub pivot_scope: Option<PivotScope>,
+
+    /// Load options from a named profile in the config file
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    /// Path to the config file [default: ~/.config/s7r/config.toml]
+    #[arg(long, requires = "profile")]
+    pub config: Option<PathBuf>,
+
+    /// Also enumerate the subdomains found, down to N levels below the target [default: 0, off]
+    #[arg(long, value_name = "N", default_value_t = 0, hide_default_value = true)]
+    pub max_depth: usize,
+
+    /// Save progress to this file as each engine finishes, to pick the run back up with --resume
+    #[arg(long, value_name = "PATH")]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Continue the run saved to this checkpoint, with its targets, instead of starting over
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["domain", "tld_expand", "max_depth"])]
+    pub resume: Option<PathBuf>,
+
+    /// Print the first query each engine would send, then exit without sending anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Print what a single engine extracts from a saved response body, without any network access
+    #[arg(long, requires_all = ["input_file", "domain", "engines"])]
+    pub only_extract: bool,
+
+    /// Saved response body to extract from with --only-extract, e.g. a results page
+    #[arg(long, value_name = "FILE", requires = "only_extract")]
+    pub input_file: Option<PathBuf>,
+
+    /// Time each engine's extraction on the saved bodies in DIR, named after their engine, e.g. bing.html
+    #[arg(long, value_name = "DIR", hide = true, conflicts_with = "only_extract")]
+    pub profile_regex: Option<PathBuf>,
+
+    /// Number of times each body is extracted from with --profile-regex
+    #[arg(long, value_name = "N", hide = true, default_value_t = DEFAULT_ITERATIONS, requires = "profile_regex")]
+    pub profile_iterations: u32,
+
+    /// Check which engines are currently working against example.com
+    #[arg(long, conflicts_with_all = ["domain", "engines"])]
+    pub selftest: bool,
+
+    /// Read targets as NDJSON from stdin, e.g. `{"domain":"example.com","engines":["crtsh"]}`,
+    /// and write one JSON object of results per target
+    #[arg(long, conflicts_with_all = ["domain", "tld_expand", "output", "sources_report", "dry_run", "selftest", "output_template_file", "webhook"])]
+    pub ndjson: bool,
+
+    /// Print build and engine metadata as JSON, for bug reports
+    #[arg(long, exclusive = true)]
+    pub version_json: bool,
+
     /// Generate completion for the given shell
     #[arg(short, long, conflicts_with_all = ["domain", "engines", "verbose"])]
     pub completion: Option<Shell>,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Merge result files into a single normalized, deduplicated and sorted list
+    Merge {
+        /// Result files to merge, one subdomain per line
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Write the merged list to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+impl Cli {
+    /// Fill in options from a profile, flags given on the command line take precedence
+    #[must_use]
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        let Profile {
+            engines,
+            clean,
+            http1_only,
+            pivot_sameip,
+            pivot_scope,
+            engines_weighted,
+            engine_priority,
+        } = profile;
+
+        if self.engines.is_empty() {
+            self.engines = engines;
+        }
+        self.no_clean |= clean == Some(false);
+        self.http1_only |= http1_only.unwrap_or_default();
+        self.pivot_sameip |= pivot_sameip.unwrap_or_default();
+        self.pivot_scope = self.pivot_scope.or(pivot_scope);
+        self.engines_weighted |= engines_weighted.unwrap_or_default() && !self.sequential;
+        if self.engine_priority.is_empty() {
+            self.engine_priority = engine_priority.into_iter().collect();
+        }
+
+        self
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Domain {
     Url(Url),
     Host(Host),
 }
 
+/// A `--domain` value that holds no usable domain, e.g. an empty string or a bare scheme
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoDomain(String);
+
+impl fmt::Display for NoDomain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no domain found in input: {:?}, expected e.g. example.com or https://example.com",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for NoDomain {}
+
 impl FromStr for Domain {
-    type Err = url::ParseError;
+    type Err = NoDomain;
 
+    /// Parse a URL or a bare host, failing unless it holds a domain name
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Url::parse(s)
+        let domain = Url::parse(s)
             .map(Domain::Url)
             .or_else(|_| Host::parse(s).map(Domain::Host))
+            .map_err(|_| NoDomain(s.to_owned()))?;
+
+        match domain.domain() {
+            Some(d) if !d.trim_matches('.').is_empty() => Ok(domain),
+            _ => Err(NoDomain(s.to_owned())),
+        }
     }
 }
 
 impl Domain {
+    #[must_use]
     pub fn domain(&self) -> Option<&str> {
         match self {
             Domain::Url(u) => u.domain(),
@@ -81,6 +508,343 @@ impl Domain {
     }
 }
 
+fn parse_brand(s: &str) -> Result<String, String> {
+    let valid = !s.is_empty()
+        && !s.starts_with('-')
+        && !s.ends_with('-')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if valid {
+        Ok(s.to_ascii_lowercase())
+    } else {
+        Err(format!("`{s}` is not a valid domain label"))
+    }
+}
+
+fn parse_engine_delay(s: &str) -> Result<(EngineChoice, u64), String> {
+    let (name, ms) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected ENGINE=MS, got `{s}`"))?;
+    let engine = <EngineChoice as ValueEnum>::from_str(name.trim(), true)?;
+    let ms = ms
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid delay `{ms}`: {e}"))?;
+    Ok((engine, ms))
+}
+
+fn parse_engine_delay_strategy(s: &str) -> Result<(EngineChoice, DelayKind), String> {
+    let (name, kind) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected ENGINE=STRATEGY, got `{s}`"))?;
+    let engine = <EngineChoice as ValueEnum>::from_str(name.trim(), true)?;
+    let kind = <DelayKind as ValueEnum>::from_str(kind.trim(), true)?;
+    Ok((engine, kind))
+}
+
+fn parse_engine_per_page(s: &str) -> Result<(EngineChoice, usize), String> {
+    let (name, per_page) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected ENGINE=N, got `{s}`"))?;
+    let engine = <EngineChoice as ValueEnum>::from_str(name.trim(), true)?;
+    let per_page = per_page
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid page size `{per_page}`: {e}"))?;
+    Ok((engine, per_page))
+}
+
+fn parse_engine_priority(s: &str) -> Result<(EngineChoice, u8), String> {
+    let (name, priority) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected ENGINE=N, got `{s}`"))?;
+    let engine = <EngineChoice as ValueEnum>::from_str(name.trim(), true)?;
+    let priority = priority
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid priority `{priority}`: {e}"))?;
+    Ok((engine, priority))
+}
+
+fn parse_engine_header(s: &str) -> Result<(EngineChoice, HeaderName, HeaderValue), String> {
+    let (name, header) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected ENGINE:KEY=VALUE, got `{s}`"))?;
+    let engine = <EngineChoice as ValueEnum>::from_str(name.trim(), true)?;
+    let (key, value) = header
+        .split_once('=')
+        .ok_or_else(|| format!("expected ENGINE:KEY=VALUE, got `{s}`"))?;
+    let key =
+        HeaderName::from_str(key.trim()).map_err(|e| format!("invalid header `{key}`: {e}"))?;
+    let value = HeaderValue::from_str(value.trim())
+        .map_err(|e| format!("invalid value for header `{key}`: {e}"))?;
+    Ok((engine, key, value))
+}
+
 pub fn print_completions<G: Generator>(g: G, c: &mut Command) {
     generate(g, c, c.get_name().to_string(), &mut std::io::stdout());
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn profile() -> Profile {
+        Profile {
+            engines: vec![EngineChoice::CrtSh],
+            clean: Some(false),
+            http1_only: Some(true),
+            pivot_sameip: None,
+            pivot_scope: Some(PivotScope::All),
+            engines_weighted: Some(true),
+            engine_priority: HashMap::from([(EngineChoice::Yahoo, 95)]),
+        }
+    }
+
+    #[test]
+    fn test_with_profile() {
+        let cli = Cli::parse_from(["s7r", "-d", "example.com"]).with_profile(profile());
+
+        assert_eq!(cli.engines, vec![EngineChoice::CrtSh]);
+        assert!(cli.no_clean);
+        assert!(cli.http1_only);
+        assert!(!cli.pivot_sameip);
+        assert_eq!(cli.pivot_scope, Some(PivotScope::All));
+        assert!(cli.engines_weighted);
+        assert_eq!(cli.engine_priority, [(EngineChoice::Yahoo, 95)]);
+    }
+
+    #[test]
+    fn test_with_profile_cli_overrides() {
+        let cli = Cli::parse_from([
+            "s7r",
+            "-d",
+            "example.com",
+            "-e",
+            "bing,google",
+            "--pivot-sameip",
+            "--pivot-scope",
+            "domain",
+            "--engines-weighted",
+            "--engine-priority",
+            "bing=10",
+        ])
+        .with_profile(profile());
+
+        assert_eq!(cli.engines, vec![EngineChoice::Bing, EngineChoice::Google]);
+        assert_eq!(cli.engine_priority, [(EngineChoice::Bing, 10)]);
+        assert!(cli.pivot_sameip);
+        assert_eq!(cli.pivot_scope, Some(PivotScope::Domain));
+    }
+
+    #[test]
+    fn test_merge_subcommand() {
+        let cli = Cli::parse_from(["s7r", "merge", "a.txt", "b.txt", "-o", "all.txt"]);
+
+        let Some(Commands::Merge { files, output }) = cli.command else {
+            panic!("expected merge subcommand");
+        };
+        assert_eq!(files, vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")]);
+        assert_eq!(output, Some(PathBuf::from("all.txt")));
+        assert!(cli.domain.is_none());
+    }
+
+    #[test]
+    fn test_engine_delay() {
+        let cli = Cli::parse_from([
+            "s7r",
+            "-d",
+            "example.com",
+            "--engine-delay",
+            "google=2000, Bing=500",
+            "--engine-delay",
+            "crtsh=0",
+        ]);
+
+        assert_eq!(
+            cli.engine_delay,
+            vec![
+                (EngineChoice::Google, 2000),
+                (EngineChoice::Bing, 500),
+                (EngineChoice::CrtSh, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_engine_delay_strategy() {
+        let cli = Cli::parse_from([
+            "s7r",
+            "-d",
+            "example.com",
+            "--delay-strategy",
+            "jittered",
+            "--engine-delay-strategy",
+            "google=adaptive, Bing=Fixed",
+        ]);
+
+        assert_eq!(cli.delay_strategy, Some(DelayKind::Jittered));
+        assert_eq!(
+            cli.engine_delay_strategy,
+            vec![
+                (EngineChoice::Google, DelayKind::Adaptive),
+                (EngineChoice::Bing, DelayKind::Fixed)
+            ]
+        );
+        assert!(
+            Cli::try_parse_from([
+                "s7r",
+                "-d",
+                "example.com",
+                "--engine-delay-strategy",
+                "google=sometimes",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_per_page() {
+        let cli = Cli::parse_from([
+            "s7r",
+            "-d",
+            "example.com",
+            "--per-page",
+            "google=50,Bing=30",
+        ]);
+
+        assert_eq!(
+            cli.per_page,
+            vec![(EngineChoice::Google, 50), (EngineChoice::Bing, 30)]
+        );
+        assert!(
+            Cli::try_parse_from(["s7r", "-d", "example.com", "--per-page", "google=-5"]).is_err()
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::zero("0", false)]
+    #[case::longest("15", true)]
+    #[case::gives_up_at_once("16", false)]
+    fn test_backoff_base_range(#[case] value: &str, #[case] valid: bool) {
+        let res = Cli::try_parse_from(["s7r", "-d", "example.com", "--backoff-base", value]);
+        assert_eq!(res.is_ok(), valid);
+    }
+
+    #[test]
+    fn test_resume() {
+        let cli = Cli::parse_from(["s7r", "--resume", "run.json"]);
+        assert_eq!(cli.resume, Some(PathBuf::from("run.json")));
+
+        // the checkpoint has its own targets and depth
+        for args in [["-d", "example.com"], ["--max-depth", "2"]] {
+            let err =
+                Cli::try_parse_from([["s7r", "--resume", "run.json"].as_slice(), &args].concat())
+                    .unwrap_err();
+            assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+        }
+    }
+
+    #[test]
+    fn test_proxy_conflicts_with_no_proxy() {
+        let err = Cli::try_parse_from([
+            "s7r",
+            "-d",
+            "example.com",
+            "--proxy",
+            "http://127.0.0.1:8080",
+            "--no-proxy",
+        ])
+        .unwrap_err();
+
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn test_format_dnsx_requires_resolve() {
+        let args = ["s7r", "-d", "example.com", "--format", "dnsx"];
+        assert!(Cli::try_parse_from(args).is_err());
+
+        let cli = Cli::parse_from([args.as_slice(), &["--resolve"]].concat());
+        assert_eq!(cli.format, Format::Dnsx);
+    }
+
+    #[rstest::rstest]
+    #[case::missing_ms("google")]
+    #[case::unknown_engine("altavista=100")]
+    #[case::negative("google=-1")]
+    fn test_invalid_engine_delay(#[case] value: &str) {
+        let res = Cli::try_parse_from(["s7r", "-d", "example.com", "--engine-delay", value]);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_engine_header() {
+        let cli = Cli::parse_from([
+            "s7r",
+            "-d",
+            "example.com",
+            "--engine-header",
+            "Google:Cookie=CONSENT=YES+, lang=en",
+        ]);
+
+        assert_eq!(
+            cli.engine_header,
+            vec![(
+                EngineChoice::Google,
+                HeaderName::from_static("cookie"),
+                HeaderValue::from_static("CONSENT=YES+, lang=en")
+            )]
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::missing_engine("Cookie=foo")]
+    #[case::missing_value("google:Cookie")]
+    #[case::unknown_engine("altavista:Cookie=foo")]
+    #[case::bad_name("google:Bad Header=foo")]
+    #[case::bad_value("google:Cookie=a\nb")]
+    fn test_invalid_engine_header(#[case] value: &str) {
+        let res = Cli::try_parse_from(["s7r", "-d", "example.com", "--engine-header", value]);
+        assert!(res.is_err());
+    }
+
+    #[rstest::rstest]
+    #[case::dotted("example.com")]
+    #[case::leading_hyphen("-example")]
+    #[case::space("my brand")]
+    fn test_invalid_tld_expand(#[case] brand: &str) {
+        let res = Cli::try_parse_from(["s7r", "--tld-expand", brand]);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let res =
+            Cli::try_parse_from(["s7r", "-d", "example.com", "--exclude-pattern", "(unclosed"]);
+        assert!(res.is_err());
+    }
+
+    #[rstest::rstest]
+    #[case::empty("")]
+    #[case::lone_dot(".")]
+    #[case::dots("..")]
+    #[case::scheme_only("https://")]
+    #[case::no_host("mailto:admin")]
+    #[case::ip("127.0.0.1")]
+    #[case::ip_url("https://[::1]/")]
+    fn test_domain_without_domain(#[case] input: &str) {
+        let err = Domain::from_str(input).unwrap_err();
+        assert_eq!(err, NoDomain(input.to_owned()));
+        assert!(err.to_string().starts_with("no domain found in input"));
+    }
+
+    #[rstest::rstest]
+    #[case::host("example.com", "example.com")]
+    #[case::url("https://app.example.com/login", "app.example.com")]
+    fn test_domain(#[case] input: &str, #[case] expected: &str) {
+        let domain = Domain::from_str(input).unwrap();
+        assert_eq!(domain.domain(), Some(expected));
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::PivotScope;
+use crate::enumerate::EngineChoice;
+
+/// Contents of the configuration file
+///
+/// ```toml
+/// [profiles.recon-fast]
+/// engines = ["crtsh", "virustotal"]
+/// http1-only = true
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// A named bundle of options, used when the matching CLI flag isn't given
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Profile {
+    pub engines: Vec<EngineChoice>,
+    pub clean: Option<bool>,
+    pub http1_only: Option<bool>,
+    pub pivot_sameip: Option<bool>,
+    pub pivot_scope: Option<PivotScope>,
+    pub engines_weighted: Option<bool>,
+    /// Per-engine priority for `engines-weighted`, e.g. `{ crtsh = 95 }`
+    pub engine_priority: HashMap<EngineChoice, u8>,
+}
+
+impl Config {
+    /// Default location of the configuration file, `$XDG_CONFIG_HOME/s7r/config.toml`,
+    /// falling back to `~/.config/s7r/config.toml`
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+
+        Some(base.join("s7r").join("config.toml"))
+    }
+
+    /// Load the config file at `path`
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read or isn't a valid config
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        content
+            .parse()
+            .with_context(|| format!("failed to parse config file {}", path.display()))
+    }
+
+    /// The profile called `name`
+    ///
+    /// # Errors
+    ///
+    /// If the config has no such profile
+    pub fn profile(&self, name: &str) -> anyhow::Result<Profile> {
+        self.profiles
+            .get(name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("profile '{name}' not found in config"))
+    }
+}
+
+impl std::str::FromStr for Config {
+    type Err = toml::de::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        toml::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        [profiles.recon-fast]
+        engines = ["crtsh", "virustotal", "dnsdumpster"]
+        http1-only = true
+        pivot-scope = "all"
+        engines-weighted = true
+        engine-priority = { yahoo = 95 }
+
+        [profiles.empty]
+    "#;
+
+    #[test]
+    fn test_profile() {
+        let config: Config = CONFIG.parse().unwrap();
+        let profile = config.profile("recon-fast").unwrap();
+
+        assert_eq!(
+            profile.engines,
+            vec![
+                EngineChoice::CrtSh,
+                EngineChoice::VirusTotal,
+                EngineChoice::DNSDumpster
+            ]
+        );
+        assert_eq!(profile.http1_only, Some(true));
+        assert_eq!(profile.pivot_scope, Some(PivotScope::All));
+        assert_eq!(profile.clean, None);
+        assert_eq!(profile.engines_weighted, Some(true));
+        assert_eq!(
+            profile.engine_priority,
+            HashMap::from([(EngineChoice::Yahoo, 95)])
+        );
+    }
+
+    #[test]
+    fn test_profile_empty() {
+        let config: Config = CONFIG.parse().unwrap();
+        let profile = config.profile("empty").unwrap();
+
+        assert!(profile.engines.is_empty());
+        assert_eq!(profile.http1_only, None);
+    }
+
+    #[test]
+    fn test_profile_missing() {
+        let config: Config = CONFIG.parse().unwrap();
+        assert!(config.profile("missing").is_err());
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        let config = "[profiles.typo]\nengine = [\"crtsh\"]".parse::<Config>();
+        assert!(config.is_err());
+    }
+}
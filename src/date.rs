@@ -0,0 +1,102 @@
+/// Seconds in a day, leap seconds aside as in Unix time
+const SECS_PER_DAY: i64 = 86_400;
+/// Days from 0000-03-01, where the calendar math starts, to 1970-01-01
+const UNIX_EPOCH_DAYS: i64 = 719_468;
+/// Days in 400 years, after which the Gregorian calendar repeats
+const DAYS_PER_ERA: i64 = 146_097;
+
+/// Format seconds since the Unix epoch as an RFC 3339 UTC timestamp, e.g. 2024-05-01T12:00:00Z
+pub fn rfc3339(secs: i64) -> String {
+    let (days, secs) = (secs.div_euclid(SECS_PER_DAY), secs.rem_euclid(SECS_PER_DAY));
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// Seconds since the Unix epoch of a UTC timestamp such as `2024-01-15T08:30:00`
+///
+/// Fractional seconds are dropped.
+pub fn parse(s: &str) -> Option<i64> {
+    let (date, time) = s.split_once('T')?;
+
+    let mut date = date.splitn(3, '-').map(str::parse::<i64>);
+    let (y, m, d) = (date.next()?.ok()?, date.next()?.ok()?, date.next()?.ok()?);
+
+    let time = time.split('.').next()?;
+    let mut time = time.splitn(3, ':').map(str::parse::<i64>);
+    let (hh, mm, ss) = (time.next()?.ok()?, time.next()?.ok()?, time.next()?.ok()?);
+
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+
+    Some(days_from_civil(y, m, d) * SECS_PER_DAY + hh * 3_600 + mm * 60 + ss)
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * DAYS_PER_ERA + doe - UNIX_EPOCH_DAYS
+}
+
+/// Year, month and day of the date `days` after 1970-01-01, the inverse of [`days_from_civil`]
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + UNIX_EPOCH_DAYS;
+    let era = z.div_euclid(DAYS_PER_ERA);
+    let doe = z.rem_euclid(DAYS_PER_ERA);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(0, "1970-01-01T00:00:00Z")]
+    #[case(951_782_400, "2000-02-29T00:00:00Z")]
+    #[case(1_709_251_199, "2024-02-29T23:59:59Z")]
+    #[case(-1, "1969-12-31T23:59:59Z")]
+    fn test_rfc3339(#[case] secs: i64, #[case] expected: &str) {
+        assert_eq!(rfc3339(secs), expected);
+    }
+
+    #[rstest]
+    #[case::epoch("1970-01-01T00:00:00", Some(0))]
+    #[case::leap_day("2024-02-29T12:00:00", Some(1_709_208_000))]
+    #[case::fractional("2024-02-29T12:00:00.123", Some(1_709_208_000))]
+    #[case::date_only("2024-02-29", None)]
+    #[case::bad_month("2024-13-01T00:00:00", None)]
+    fn test_parse(#[case] input: &str, #[case] expected: Option<i64>) {
+        assert_eq!(parse(input), expected);
+    }
+
+    #[rstest]
+    #[case::before_epoch(-86_401)]
+    #[case::leap_day(1_709_208_000)]
+    #[case::century(4_107_542_400)]
+    fn test_round_trip(#[case] secs: i64) {
+        let formatted = rfc3339(secs);
+        assert_eq!(parse(formatted.trim_end_matches('Z')), Some(secs));
+    }
+}
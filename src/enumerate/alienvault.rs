@@ -1,19 +1,33 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
+use std::hash::BuildHasher;
 
+use enumerate_derive::ApiExtract;
+use reqwest::header::HeaderMap;
 use reqwest::{Client, Response};
-use serde::Deserialize;
 
-use super::{Extract, Search, Settings};
+use super::{Extract, PaginationKind, Search, Settings};
 
-const SETTINGS: Settings = Settings {
+pub(super) const SETTINGS: Settings = Settings {
     name: "AlienVault",
     base_url: "https://otx.alienvault.com/api/v1/indicators/domain",
     user_agent: "", // not used
     max_rounds: 1,
+    pagination: PaginationKind::Single,
+    passive: true,
+    priority: 70,
+    per_page: 0,
+    max_per_page: 0,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 0,
 };
 
+// passive DNS records can point at unrelated domains, only the target's are extracted
+#[derive(ApiExtract)]
+#[api_extract(path = "passive_dns.*.hostname")]
 pub struct AlienVault {
+    #[api_extract(domain)]
     domain: String,
 }
 
@@ -25,22 +39,12 @@ impl AlienVault {
     }
 }
 
-impl Extract for AlienVault {
-    fn extract(&mut self, input: &str) -> HashSet<String> {
-        let mut found = serde_json::from_str::<AlienVaultResponse>(input)
-            .map(|r| r.data)
-            .unwrap_or_default();
-        found.retain(|d| d.ends_with(&self.domain));
-        found
-    }
-}
-
 impl Search for AlienVault {
     fn settings(&self) -> Settings {
         SETTINGS
     }
 
-    fn next_query(&self, _: &HashSet<String>) -> Option<Cow<'_, str>> {
+    fn next_query<S: BuildHasher>(&self, _: &HashSet<String, S>) -> Option<Cow<'_, str>> {
         let domain = &self.domain;
         let base_url = SETTINGS.base_url;
         let query = format!("{base_url}/{domain}/passive_dns");
@@ -52,39 +56,30 @@ impl Search for AlienVault {
         client: Client,
         url: &str,
         _: usize,
+        headers: &HeaderMap,
     ) -> Result<Response, reqwest::Error> {
-        client.get(url).send().await
+        client.get(url).headers(headers.clone()).send().await
     }
-
-    /// `AlienVault` only runs once, no need to delay
-    async fn delay(&self) {}
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(from = "PassiveDns")]
-struct AlienVaultResponse {
-    data: HashSet<String>,
 }
 
-impl From<PassiveDns> for AlienVaultResponse {
-    fn from(value: PassiveDns) -> Self {
-        let PassiveDns { passive_dns, count } = value;
-
-        let mut data = HashSet::with_capacity(count);
-        let v = passive_dns.into_iter().map(|i| i.hostname);
-        data.extend(v);
-
-        Self { data }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let input = r#"{
+            "passive_dns": [
+                {"hostname": "app.example.com"},
+                {"hostname": "cdn.example.net"},
+                {"hostname": "notexample.com"}
+            ],
+            "count": 3
+        }"#;
+
+        let mut av = AlienVault::new("example.com");
+
+        let expected = HashSet::from(["app.example.com".to_owned()]);
+        assert_eq!(av.extract(input), expected);
     }
 }
-
-#[derive(Debug, Deserialize)]
-struct PassiveDns {
-    passive_dns: Vec<Item>,
-    count: usize,
-}
-
-#[derive(Debug, Deserialize)]
-struct Item {
-    hostname: String,
-}
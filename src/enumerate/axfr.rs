@@ -0,0 +1,345 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+use std::io;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use hickory_client::client::{Client as DnsClient, ClientHandle};
+use hickory_client::proto::op::ResponseCode;
+use hickory_client::proto::rr::Name;
+use hickory_client::proto::runtime::TokioRuntimeProvider;
+use hickory_client::proto::tcp::TcpClientStream;
+use hickory_resolver::TokioResolver;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Response};
+use tracing::{debug, info};
+
+use super::{Extract, PaginationKind, Search, Settings};
+use crate::normalize;
+
+pub(super) const SETTINGS: Settings = Settings {
+    name: "AXFR",
+    // zone transfers go to the domain's nameservers over DNS, not to a web host
+    base_url: "",
+    user_agent: "",
+    max_rounds: 1,
+    pagination: PaginationKind::Single,
+    passive: false,
+    priority: 50,
+    per_page: 0,
+    max_per_page: 0,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 0,
+};
+/// Longest a zone transfer from a single nameserver may take
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Port nameservers accept zone transfers on
+const DNS_PORT: u16 = 53;
+
+/// Asks the domain's nameservers for a zone transfer, which only misconfigured ones allow
+///
+/// This contacts the target's own infrastructure, so it only runs with `--active`.
+pub struct Axfr {
+    domain: String,
+    /// Nameservers to ask, looked up from the domain's NS records if empty
+    nameservers: Vec<SocketAddr>,
+}
+
+impl Axfr {
+    pub fn new(domain: impl Into<String>) -> Self {
+        Self {
+            domain: domain.into(),
+            nameservers: Vec::new(),
+        }
+    }
+
+    /// Addresses of the domain's authoritative nameservers
+    async fn nameservers(&self) -> io::Result<Vec<SocketAddr>> {
+        if !self.nameservers.is_empty() {
+            return Ok(self.nameservers.clone());
+        }
+
+        let resolver = TokioResolver::builder_tokio()
+            .map_err(io::Error::other)?
+            .build();
+        let ns = resolver
+            .ns_lookup(self.domain.as_str())
+            .await
+            .map_err(io::Error::other)?;
+
+        let mut addrs = Vec::new();
+        for name in ns.iter() {
+            match resolver.lookup_ip(name.0.clone()).await {
+                Ok(ips) => addrs.extend(ips.iter().map(|ip| SocketAddr::new(ip, DNS_PORT))),
+                Err(e) => debug!(ns = %name.0, err = %e, "failed to resolve nameserver"),
+            }
+        }
+        Ok(addrs)
+    }
+}
+
+/// Ask `server` for a zone transfer of `domain` and return the names of every record
+///
+/// # Errors
+///
+/// If the server refuses, which most of them do, or doesn't answer in time
+pub async fn transfer(server: SocketAddr, domain: &str) -> io::Result<HashSet<String>> {
+    tokio::time::timeout(TIMEOUT, try_transfer(server, domain))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "zone transfer timed out"))?
+}
+
+async fn try_transfer(server: SocketAddr, domain: &str) -> io::Result<HashSet<String>> {
+    let name = Name::from_str(domain).map_err(io::Error::other)?;
+    let (stream, sender) = TcpClientStream::new(server, None, None, TokioRuntimeProvider::new());
+    let (mut client, background) = DnsClient::new(stream, sender, None)
+        .await
+        .map_err(io::Error::other)?;
+    // sends the queries and reads the replies, until the client is dropped
+    tokio::spawn(background);
+
+    // the zone may come over several messages, the stream ends with its closing SOA record
+    let mut names = HashSet::new();
+    let mut messages = client.zone_transfer(name, None);
+    while let Some(message) = messages.next().await {
+        let message = message.map_err(io::Error::other)?;
+        let code = message.response_code();
+        if code != ResponseCode::NoError {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("zone transfer refused: {code}"),
+            ));
+        }
+        names.extend(message.answers().iter().map(|r| r.name().to_string()));
+    }
+
+    if names.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "zone transfer refused: no records",
+        ));
+    }
+    Ok(names)
+}
+
+impl Extract for Axfr {
+    /// Zone transfers have no text to extract from, their names come from `lookup`
+    fn extract(&mut self, _: &str) -> HashSet<String> {
+        HashSet::new()
+    }
+
+    /// Names are fully qualified and the zone may hold records out of scope
+    fn clean(&self, raw: HashSet<String>) -> HashSet<String> {
+        raw.iter()
+            .map(|n| n.trim_end_matches('.').to_ascii_lowercase())
+            .filter(|n| normalize::is_subdomain_of(n, &self.domain))
+            .collect()
+    }
+
+    fn domain(&self) -> Option<&str> {
+        Some(&self.domain)
+    }
+}
+
+impl Search for Axfr {
+    fn settings(&self) -> Settings {
+        SETTINGS
+    }
+
+    /// The zone asked for, only shown by `--dry-run` as nothing is searched
+    fn next_query<S: BuildHasher>(&self, _: &HashSet<String, S>) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(&self.domain))
+    }
+
+    /// Never called, as `lookup` always answers for this engine
+    async fn search(
+        &self,
+        _: Client,
+        _: &str,
+        _: usize,
+        _: &HeaderMap,
+    ) -> Result<Response, reqwest::Error> {
+        unreachable!("zone transfers aren't HTTP searches")
+    }
+
+    /// Try each nameserver until one allows the transfer
+    ///
+    /// Refusals are expected, so the engine is still reached when every nameserver refuses,
+    /// with nothing found.
+    async fn lookup(&self) -> Option<HashSet<String>> {
+        let nameservers = self.nameservers().await.unwrap_or_else(|e| {
+            debug!(domain = self.domain, err = %e, "failed to look up nameservers");
+            Vec::new()
+        });
+
+        for server in nameservers {
+            match transfer(server, &self.domain).await {
+                Ok(found) => {
+                    info!(%server, records = found.len(), "zone transfer allowed");
+                    return Some(found);
+                }
+                Err(e) => debug!(%server, err = %e, "zone transfer failed"),
+            }
+        }
+        Some(HashSet::new())
+    }
+
+    /// Whether the domain's nameservers can be found, transfers are expected to be refused
+    async fn healthcheck(&self, _: Client) -> Result<(), String> {
+        match self.nameservers().await {
+            Ok(nameservers) if nameservers.is_empty() => Err("no nameservers found".to_owned()),
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use hickory_client::proto::op::{Message, MessageType};
+    use hickory_client::proto::rr::rdata::{A, SOA};
+    use hickory_client::proto::rr::{RData, Record, RecordType};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::enumerate::Enumerator;
+    use crate::enumerate::tests::Logs;
+
+    fn name(s: &str) -> Name {
+        Name::from_str(s).unwrap()
+    }
+
+    fn soa() -> Record {
+        let soa = SOA::new(
+            name("ns1.example.com."),
+            name("admin.example.com."),
+            1,
+            3600,
+            600,
+            86400,
+            300,
+        );
+        Record::from_rdata(name("example.com."), 300, RData::SOA(soa))
+    }
+
+    fn a(host: &str) -> Record {
+        Record::from_rdata(name(host), 300, RData::A(A(Ipv4Addr::LOCALHOST)))
+    }
+
+    /// A nameserver answering a single query with `messages`, or refusing if there are none
+    async fn nameserver(messages: Vec<Vec<Record>>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let len = stream.read_u16().await.unwrap();
+            let mut buf = vec![0; usize::from(len)];
+            stream.read_exact(&mut buf).await.unwrap();
+            let query = Message::from_vec(&buf).unwrap();
+            assert_eq!(query.queries()[0].query_type(), RecordType::AXFR);
+
+            let reply = |answers: Vec<Record>, code| {
+                let mut m = Message::new();
+                m.set_id(query.id())
+                    .set_message_type(MessageType::Response)
+                    .set_response_code(code)
+                    .add_queries(query.queries().to_vec())
+                    .add_answers(answers);
+                m.to_vec().unwrap()
+            };
+            let replies = if messages.is_empty() {
+                vec![reply(Vec::new(), ResponseCode::Refused)]
+            } else {
+                messages
+                    .into_iter()
+                    .map(|answers| reply(answers, ResponseCode::NoError))
+                    .collect()
+            };
+
+            for reply in replies {
+                let len = u16::try_from(reply.len()).unwrap();
+                stream.write_all(&len.to_be_bytes()).await.unwrap();
+                stream.write_all(&reply).await.unwrap();
+            }
+        });
+
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_transfer() {
+        // the zone spans two messages
+        let server = nameserver(vec![
+            vec![soa(), a("app.example.com."), a("api.example.com.")],
+            vec![a("app.example.com."), soa()],
+        ])
+        .await;
+
+        let names = transfer(server, "example.com").await.unwrap();
+
+        let expected = HashSet::from(
+            ["example.com.", "app.example.com.", "api.example.com."].map(String::from),
+        );
+        assert_eq!(names, expected);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_refused() {
+        let server = nameserver(Vec::new()).await;
+
+        let err = transfer(server, "example.com").await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_tries_next_nameserver() {
+        let refusing = nameserver(Vec::new()).await;
+        let allowing = nameserver(vec![vec![
+            soa(),
+            a("app.example.com."),
+            a("www.example.org."),
+            soa(),
+        ]])
+        .await;
+        let engine = Axfr {
+            domain: "example.com".to_owned(),
+            nameservers: vec![refusing, allowing],
+        };
+
+        let found = Enumerator::new(engine)
+            .enumerate(Client::new())
+            .await
+            .unwrap();
+
+        // the apex and records of other domains are dropped
+        assert_eq!(found, HashSet::from(["app.example.com".to_owned()]));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_all_refused() {
+        let logs = Logs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(logs.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let engine = Axfr {
+            domain: "example.com".to_owned(),
+            nameservers: vec![nameserver(Vec::new()).await],
+        };
+
+        // a refusal is neither a failed search nor an empty response
+        let found = Enumerator::new(engine).enumerate(Client::new()).await;
+        assert_eq!(found, Some(HashSet::new()));
+        assert!(!logs.contents().contains("empty response"));
+    }
+}
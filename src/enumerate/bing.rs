@@ -1,44 +1,97 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
+use std::hash::BuildHasher;
 
-use reqwest::{Client, Response, header};
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, Response};
 
-use super::{Extract, Search, Settings};
+use super::{Extract, MAX_REGEX_INPUT_LEN, PaginationKind, Search, Settings, exclusions};
 
 const PER_PAGE: usize = 10;
+/// Most subdomains excluded from a query, Bing cuts long queries short
+const MAX_EXCLUDED: usize = 30;
 // https://learn.microsoft.com/en-us/bing/search-apis/bing-web-search/reference/headers
-const SETTINGS: Settings = Settings {
+pub(super) const SETTINGS: Settings = Settings {
     name: "Bing",
     base_url: "https://www.bing.com/search",
     user_agent: "Mozilla/5.0 (Windows NT 6.3; WOW64; Trident/7.0; Touch; rv:11.0) like Gecko",
     max_rounds: 10,
+    pagination: PaginationKind::OffsetPages,
+    passive: true,
+    priority: 30,
+    per_page: PER_PAGE,
+    max_per_page: 50,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 200,
 };
+/// Pages in a row without anything new after which Bing is out of results
+///
+/// Past its last results, Bing serves its first page again for any offset,
+/// so the same subdomains keep coming back instead of an empty page.
+const MAX_STALE_PAGES: usize = 2;
 /// A random client id that Bing uses to identify the client to ensure consistent results
 const X_MSEDGE_CLIENT_ID: &str = "sublist3r-rs-bing";
 
+/// Markers of Bing's bot challenge page
+const BLOCK_FINGERPRINTS: [&str; 3] = [
+    "/challenge/verify",
+    "b_captcha",
+    "Please solve the challenge below to continue",
+];
+
+/// Matches the URL in a `<cite>` tag, e.g. `<cite>https://app.example.com › login</cite>`
+///
+/// Anything after the hostname must start with a character that can't be part of a hostname,
+/// so `app.example.com.evil.net` isn't cut short to `app.example.com`,
+/// and can't contain `<` or `>`, so a match never runs across tags on malformed HTML.
 #[derive(Extract)]
-#[extract(pattern = r#"<cite>https:\/\/(?<subdomain>[[:alnum:]\-\.]*?\.{domain}).*?<\/cite>"#)]
+#[extract(
+    pattern = r#"<cite>https:\/\/(?<subdomain>[[:alnum:]\-\.]*?\.{domain})(?:[^[:alnum:]\-\.<>][^<>]*?)?<\/cite>"#
+)]
 pub struct Bing {
     #[extract(domain)]
     domain: String,
+    /// Pages in a row that found nothing new
+    stale_pages: usize,
+    per_page: usize,
 }
 
 impl Bing {
     pub fn new(domain: impl Into<String>) -> Self {
         Self {
             domain: domain.into(),
+            stale_pages: 0,
+            per_page: PER_PAGE,
         }
     }
+
+    /// Index of the first result of `page`, 0-based
+    fn offset(&self, page: usize) -> usize {
+        page * self.settings().per_page
+    }
 }
 
 impl Search for Bing {
     fn settings(&self) -> Settings {
-        SETTINGS
+        Settings {
+            per_page: self.per_page,
+            ..SETTINGS
+        }
+    }
+
+    fn set_per_page(&mut self, per_page: usize) {
+        self.per_page = per_page;
     }
 
-    fn next_query(&self, subdomains: &HashSet<String>) -> Option<Cow<'_, str>> {
-        let found = subdomains
-            .iter()
+    /// Returns `None` once pages stop finding anything new, see `MAX_STALE_PAGES`
+    fn next_query<S: BuildHasher>(&self, subdomains: &HashSet<String, S>) -> Option<Cow<'_, str>> {
+        if self.stale_pages >= MAX_STALE_PAGES {
+            return None;
+        }
+
+        let found = exclusions(subdomains, MAX_EXCLUDED)
+            .into_iter()
             .fold(String::new(), |acc, d| format!("{acc} -{d}"));
 
         let query = format!("domain:{0} -www.{0}{1}", self.domain, found);
@@ -50,21 +103,35 @@ impl Search for Bing {
         client: Client,
         query: &str,
         page: usize,
+        headers: &HeaderMap,
     ) -> Result<Response, reqwest::Error> {
-        let offset = page * PER_PAGE;
+        let offset = self.offset(page);
 
         client
             .get(SETTINGS.base_url)
             .query(&[("q", query)])
-            .query(&[("count", PER_PAGE)])
+            .query(&[("count", self.per_page)])
             .query(&[("offset", offset)])
             .header(header::USER_AGENT, SETTINGS.user_agent)
             .header(header::ACCEPT, "text/html")
             .header("X-MSEdge-ClientID", X_MSEDGE_CLIENT_ID)
             .header("Pragma", "no-cache")
+            .headers(headers.clone())
             .send()
             .await
     }
+
+    fn page_done(&mut self, new: usize) {
+        if new == 0 {
+            self.stale_pages += 1;
+        } else {
+            self.stale_pages = 0;
+        }
+    }
+
+    fn is_blocked(&self, body: &str) -> bool {
+        BLOCK_FINGERPRINTS.iter().any(|f| body.contains(f))
+    }
 }
 
 #[cfg(test)]
@@ -74,6 +141,20 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_generate_query_many() {
+        let subdomains: HashSet<String> = (0..10_000)
+            .map(|i| format!("s{i:05}.example.com"))
+            .collect();
+        let bing = Bing::new("example.com");
+        let query = bing.next_query(&subdomains).unwrap();
+
+        let excluded: Vec<_> = query.split_whitespace().skip(2).collect();
+        assert_eq!(excluded.len(), MAX_EXCLUDED);
+        assert_eq!(excluded[0], "-s00000.example.com");
+        assert_eq!(excluded[MAX_EXCLUDED - 1], "-s00029.example.com");
+    }
+
     #[rstest]
     #[case::empty("", vec![])]
     #[case::no_matches("no matches found", vec![])]
@@ -101,6 +182,23 @@ mod tests {
         ",
         vec!["first.example.com", "second.example.com", "fourth.third.example.com"]
     )]
+    #[case::with_path(
+        r"<cite>https://app.example.com › login › index</cite>",
+        vec!["app.example.com"]
+    )]
+    #[case::with_port(r"<cite>https://app.example.com:8443</cite>", vec!["app.example.com"])]
+    #[case::longer_hostname(r"<cite>https://app.example.com.evil.net</cite>", vec![])]
+    #[case::injected_tag(r"<cite>https://app.example.com<img src=x></cite>", vec![])]
+    #[case::injected_angle_bracket(r"<cite>https://app.example.com > evil</cite>", vec![])]
+    #[case::unclosed_cite(
+        r"<cite>https://app.example.com</div><cite>https://api.example.com</cite>",
+        vec!["api.example.com"]
+    )]
+    #[case::across_lines(
+        "<cite>https://app.example.com\n</cite>",
+        vec!["app.example.com"]
+    )]
+    #[case::other_domain(r"<cite>https://www.evil.net/app.example.com</cite>", vec![])]
     fn test_extract(#[case] input: &str, #[case] expected: Vec<&str>) {
         let mut bing = Bing::new("example.com");
         let results = bing.extract(input);
@@ -108,4 +206,60 @@ mod tests {
         let expected: HashSet<String> = expected.into_iter().map(String::from).collect();
         assert_eq!(expected, results);
     }
+
+    #[rstest]
+    #[case::challenge(
+        r#"<form id="challenge" action="/challenge/verify" method="post"></form>"#,
+        true
+    )]
+    #[case::captcha(r#"<div id="b_captcha" class="b_captcha"></div>"#, true)]
+    #[case::solve(r"<p>Please solve the challenge below to continue</p>", true)]
+    #[case::results(r"<cite>https://app.example.com</cite>", false)]
+    #[case::empty("", false)]
+    fn test_is_blocked(#[case] input: &str, #[case] expected: bool) {
+        let bing = Bing::new("example.com");
+        assert_eq!(bing.is_blocked(input), expected);
+    }
+
+    #[rstest]
+    #[case::default(None, 3, 30)]
+    #[case::overridden(Some(50), 3, 150)]
+    #[case::first_page(Some(50), 0, 0)]
+    fn test_offset(#[case] per_page: Option<usize>, #[case] page: usize, #[case] expected: usize) {
+        let mut bing = Bing::new("example.com");
+        if let Some(n) = per_page {
+            bing.set_per_page(n);
+        }
+        assert_eq!(bing.offset(page), expected);
+    }
+
+    #[test]
+    fn test_stops_on_repeated_pages() {
+        let page = r"<cite>https://app.example.com</cite><cite>https://api.example.com</cite>";
+        let mut bing = Bing::new("example.com");
+        let mut found = HashSet::new();
+
+        // the first page is new, then Bing keeps serving it again
+        let mut pages = 0;
+        while bing.next_query(&found).is_some() {
+            let before = found.len();
+            found.extend(bing.extract(page));
+            bing.page_done(found.len() - before);
+            pages += 1;
+        }
+
+        assert_eq!(pages, 1 + MAX_STALE_PAGES);
+    }
+
+    #[test]
+    fn test_new_results_reset_stale_pages() {
+        let mut bing = Bing::new("example.com");
+        bing.page_done(0);
+        bing.page_done(3);
+        bing.page_done(0);
+
+        assert!(bing.next_query(&HashSet::new()).is_some());
+        bing.page_done(0);
+        assert!(bing.next_query(&HashSet::new()).is_none());
+    }
 }
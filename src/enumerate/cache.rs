@@ -0,0 +1,76 @@
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+
+/// Most responses kept by a run's cache, the least recently used are dropped first
+pub const RESPONSE_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(64).unwrap();
+
+/// Bodies of successful searches, so a request repeated within a run isn't sent again
+///
+/// Searches build their URL from the engine's base URL, the query and the page, so together
+/// they stand for the URL. Clones share the same entries, one cache is handed to every engine.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    entries: Arc<Mutex<LruCache<String, String>>>,
+}
+
+impl ResponseCache {
+    #[must_use]
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+
+    /// Key of the request an engine sends for `query` at `page`
+    #[must_use]
+    pub fn key(base_url: &str, query: &str, page: usize) -> String {
+        format!("{base_url} {query} {page}")
+    }
+
+    /// Body of the response cached for `key`, if any
+    ///
+    /// # Panics
+    ///
+    /// If an engine panicked while using the cache
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.entries.lock().unwrap().get(key).cloned()
+    }
+
+    /// Cache `body` for `key`, evicting the least recently used entry when full
+    ///
+    /// # Panics
+    ///
+    /// If an engine panicked while using the cache
+    pub fn insert(&self, key: String, body: String) {
+        self.entries.lock().unwrap().put(key, body);
+    }
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(RESPONSE_CACHE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = ResponseCache::new(NonZeroUsize::new(2).unwrap());
+        cache.insert("a".to_owned(), "1".to_owned());
+        cache.insert("b".to_owned(), "2".to_owned());
+
+        // reading `a` makes `b` the least recently used
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        cache.insert("c".to_owned(), "3".to_owned());
+
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a").as_deref(), Some("1"));
+        assert_eq!(cache.get("c").as_deref(), Some("3"));
+    }
+}
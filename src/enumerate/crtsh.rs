@@ -1,35 +1,106 @@
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasher;
 
-use reqwest::{Client, Response, header};
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, Response};
 use serde::Deserialize;
 
-use super::{DEFAULT_USER_AGENT, Extract, Search, Settings};
+use super::{DEFAULT_USER_AGENT, Extract, FirstSeen, PaginationKind, Search, Settings};
+use crate::date;
 
-const SETTINGS: Settings = Settings {
+pub(super) const SETTINGS: Settings = Settings {
     name: "CrtSh",
     base_url: "https://crt.sh/json",
     user_agent: DEFAULT_USER_AGENT,
     max_rounds: 1,
+    pagination: PaginationKind::Single,
+    passive: true,
+    priority: 90,
+    per_page: 0,
+    max_per_page: 0,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 0,
 };
 
 pub struct CrtSh {
     domain: String,
+    /// Earliest `not_before` of the certificates listing each name
+    first_seen: FirstSeen,
+    /// Report wildcard names as `*.` markers too, besides the host they cover
+    keep_wildcards: bool,
 }
 
 impl CrtSh {
     pub fn new(domain: impl Into<String>) -> Self {
         Self {
             domain: domain.into(),
+            first_seen: HashMap::new(),
+            keep_wildcards: false,
         }
     }
 }
 
 impl Extract for CrtSh {
     fn extract(&mut self, input: &str) -> HashSet<String> {
-        serde_json::from_str::<CrtShResponse>(input)
-            .map(|r| r.data)
-            .unwrap_or_default()
+        let Ok(items) = serde_json::from_str::<Vec<Item>>(input) else {
+            return HashSet::new();
+        };
+
+        let mut data = HashSet::with_capacity(items.len());
+        for i in items {
+            let issued = i.not_before.as_deref().and_then(date::parse);
+            // entries can carry stray whitespace or dots around them, or be empty
+            let names = i
+                .name_value
+                .split('\n')
+                .map(|n| n.trim().trim_matches('.'))
+                .filter(|n| !n.is_empty());
+            for name in names {
+                if let Some(t) = issued {
+                    let host = name.strip_prefix("*.").unwrap_or(name).to_ascii_lowercase();
+                    self.first_seen
+                        .entry(host)
+                        .and_modify(|first| *first = (*first).min(t))
+                        .or_insert(t);
+                }
+                data.insert(name.to_owned());
+            }
+        }
+
+        data
+    }
+
+    /// Certificates list wildcard names such as `*.app.example.com`, keep the host they cover
+    ///
+    /// With `keep_wildcards`, the wildcard itself is kept as well, to show that a wildcard
+    /// certificate exists.
+    fn clean(&self, raw: HashSet<String>) -> HashSet<String> {
+        let mut cleaned = HashSet::with_capacity(raw.len());
+        for s in raw {
+            let Some(host) = s.strip_prefix("*.") else {
+                cleaned.insert(s);
+                continue;
+            };
+            cleaned.insert(host.to_owned());
+            if self.keep_wildcards {
+                cleaned.insert(s);
+            }
+        }
+        cleaned
+    }
+
+    fn set_keep_wildcards(&mut self, keep: bool) {
+        self.keep_wildcards = keep;
+    }
+
+    fn first_seen(&self) -> FirstSeen {
+        self.first_seen.clone()
+    }
+
+    fn domain(&self) -> Option<&str> {
+        Some(&self.domain)
     }
 }
 
@@ -38,42 +109,116 @@ impl Search for CrtSh {
         SETTINGS
     }
 
-    fn next_query(&self, _: &HashSet<String>) -> Option<Cow<'_, str>> {
+    fn next_query<S: BuildHasher>(&self, _: &HashSet<String, S>) -> Option<Cow<'_, str>> {
         Some(Cow::Borrowed(&self.domain))
     }
 
-    async fn search(&self, client: Client, _: &str, _: usize) -> Result<Response, reqwest::Error> {
+    async fn search(
+        &self,
+        client: Client,
+        _: &str,
+        _: usize,
+        headers: &HeaderMap,
+    ) -> Result<Response, reqwest::Error> {
         client
             .get(SETTINGS.base_url)
             .query(&[("q", &self.domain)])
             .header(header::USER_AGENT, SETTINGS.user_agent)
+            .headers(headers.clone())
             .send()
             .await
     }
-
-    /// `CrtSh` only runs once, no need to delay
-    async fn delay(&self) {}
 }
 
 #[derive(Debug, Deserialize)]
-#[serde(from = "Vec<Item>")]
-struct CrtShResponse {
-    data: HashSet<String>,
+struct Item {
+    name_value: String,
+    /// Start of the certificate's validity, e.g. `2024-01-15T08:30:00`
+    #[serde(default)]
+    not_before: Option<String>,
 }
 
-impl From<Vec<Item>> for CrtShResponse {
-    fn from(value: Vec<Item>) -> Self {
-        let mut data = HashSet::with_capacity(value.len());
-        for i in value {
-            let domains = i.name_value.split('\n').map(String::from);
-            data.extend(domains);
-        }
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::super::tests::gzip;
+    use super::super::{DEFAULT_MAX_BODY_SIZE, decode, gunzip};
+    use super::*;
+
+    #[test]
+    fn test_clean() {
+        let input = r#"[
+            {"name_value": "*.app.example.com\napp.example.com"},
+            {"name_value": "api.example.com"}
+        ]"#;
+
+        let mut crtsh = CrtSh::new("example.com");
+        let raw = crtsh.extract(input);
+        assert!(raw.contains("*.app.example.com"));
 
-        Self { data }
+        let expected = HashSet::from(["app.example.com".to_owned(), "api.example.com".to_owned()]);
+        assert_eq!(crtsh.clean(raw), expected);
     }
-}
 
-#[derive(Debug, Deserialize)]
-struct Item {
-    name_value: String,
+    #[rstest]
+    #[case::dropped(false, &["app.example.com", "example.com"])]
+    #[case::kept(true, &["app.example.com", "*.app.example.com", "example.com", "*.example.com"])]
+    fn test_clean_wildcards(#[case] keep: bool, #[case] expected: &[&str]) {
+        let input = r#"[{"name_value": "*.app.example.com\n*.example.com"}]"#;
+
+        let mut crtsh = CrtSh::new("example.com");
+        crtsh.set_keep_wildcards(keep);
+        let raw = crtsh.extract(input);
+
+        let expected: HashSet<_> = expected.iter().map(|s| (*s).to_owned()).collect();
+        assert_eq!(crtsh.clean(raw), expected);
+    }
+
+    #[test]
+    fn test_extract_trims_entries() {
+        let input = r#"[
+            {"name_value": " app.example.com \n\n.api.example.com\nmail.example.com.\n \n"}
+        ]"#;
+
+        let mut crtsh = CrtSh::new("example.com");
+
+        let expected = HashSet::from([
+            "app.example.com".to_owned(),
+            "api.example.com".to_owned(),
+            "mail.example.com".to_owned(),
+        ]);
+        assert_eq!(crtsh.extract(input), expected);
+    }
+
+    #[test]
+    fn test_extract_unlabeled_gzip() {
+        let input = r#"[{"name_value": "app.example.com"}, {"name_value": "api.example.com"}]"#;
+        let body = gzip(input.as_bytes());
+
+        let mut crtsh = CrtSh::new("example.com");
+        let results = crtsh.extract(&decode(&gunzip(&body, DEFAULT_MAX_BODY_SIZE), None));
+
+        let expected = HashSet::from(["app.example.com".to_owned(), "api.example.com".to_owned()]);
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn test_first_seen() {
+        let input = r#"[
+            {"name_value": "*.app.example.com\napp.example.com", "not_before": "2024-03-01T00:00:00"},
+            {"name_value": "APP.example.com", "not_before": "2023-03-01T00:00:00"},
+            {"name_value": "api.example.com", "not_before": "2024-06-01T00:00:00"},
+            {"name_value": "old.example.com"}
+        ]"#;
+
+        let mut crtsh = CrtSh::new("example.com");
+        crtsh.extract(input);
+
+        let expected = HashMap::from([
+            ("app.example.com".to_owned(), 1_677_628_800),
+            ("api.example.com".to_owned(), 1_717_200_000),
+        ]);
+        assert_eq!(crtsh.first_seen(), expected);
+    }
 }
@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+/// Maximum jitter added to the delay between pages, in milliseconds
+pub const MAX_JITTER_MS: u64 = 100;
+/// Smallest delay `Adaptive` backs off to, so an engine with no delay of its own can still slow down
+const ADAPTIVE_STEP: Duration = Duration::from_millis(250);
+/// `Adaptive` never waits longer than this many times the base delay, or `ADAPTIVE_MIN_CEILING`
+const ADAPTIVE_MAX_FACTOR: u32 = 8;
+const ADAPTIVE_MIN_CEILING: Duration = Duration::from_secs(2);
+
+/// Decides how long to wait before each page
+pub trait DelayStrategy {
+    /// Delay before the next page, after one that went through
+    fn next_delay(&mut self) -> Duration;
+
+    /// Told the engine was blocked or rate limited, returns how long to wait before trying again
+    ///
+    /// Strategies that don't slow down return `None`, and give up on a blocked engine.
+    fn blocked(&mut self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Always the same delay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixed(pub Duration);
+
+impl DelayStrategy for Fixed {
+    fn next_delay(&mut self) -> Duration {
+        self.0
+    }
+}
+
+/// The base delay plus up to `MAX_JITTER_MS` at random, so requests don't follow a fixed rhythm
+#[derive(Debug, Clone)]
+pub struct Jittered {
+    pub base: Duration,
+    pub rng: fastrand::Rng,
+}
+
+impl DelayStrategy for Jittered {
+    fn next_delay(&mut self) -> Duration {
+        self.base + Duration::from_millis(self.rng.u64(0..=MAX_JITTER_MS))
+    }
+}
+
+/// Slows down after each block or rate limit and speeds back up with every page that goes
+/// through, the same way retries are spent and rewarded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Adaptive {
+    current: Duration,
+    min: Duration,
+    max: Duration,
+    /// Blocked since the last page went through, the next delay keeps the slowdown
+    blocked: bool,
+}
+
+impl Adaptive {
+    /// Start at `base`, never going below a quarter of it
+    #[must_use]
+    pub fn new(base: Duration) -> Self {
+        Self {
+            current: base,
+            min: base / 4,
+            max: (base * ADAPTIVE_MAX_FACTOR).max(ADAPTIVE_MIN_CEILING),
+            blocked: false,
+        }
+    }
+}
+
+impl DelayStrategy for Adaptive {
+    fn next_delay(&mut self) -> Duration {
+        if !std::mem::take(&mut self.blocked) {
+            self.current = (self.current / 2).max(self.min);
+        }
+        self.current
+    }
+
+    fn blocked(&mut self) -> Option<Duration> {
+        self.current = (self.current * 2).max(ADAPTIVE_STEP).min(self.max);
+        self.blocked = true;
+        Some(self.current)
+    }
+}
+
+/// Strategy picked with `--delay-strategy`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum DelayKind {
+    /// Always the engine's delay
+    #[default]
+    Fixed,
+    /// The engine's delay plus a little random jitter
+    Jittered,
+    /// Longer after a block or rate limit, shorter as pages go through, retries blocks
+    Adaptive,
+}
+
+impl DelayKind {
+    /// Build the strategy around an engine's `base` delay, `rng` only feeds the jitter
+    #[must_use]
+    pub fn build(self, base: Duration, rng: fastrand::Rng) -> Box<dyn DelayStrategy + Send> {
+        match self {
+            DelayKind::Fixed => Box::new(Fixed(base)),
+            DelayKind::Jittered => Box::new(Jittered { base, rng }),
+            DelayKind::Adaptive => Box::new(Adaptive::new(base)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    /// Delays before each page, `blocked` says whether each attempt was blocked first
+    fn sequence(strategy: &mut dyn DelayStrategy, blocked: &[bool]) -> Vec<Duration> {
+        blocked
+            .iter()
+            .map(|&b| {
+                if b {
+                    strategy.blocked();
+                }
+                strategy.next_delay()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fixed() {
+        let mut fixed = Fixed(ms(500));
+        let delays = sequence(&mut fixed, &[false, true, true]);
+
+        assert_eq!(delays, [ms(500); 3]);
+        assert_eq!(fixed.blocked(), None);
+    }
+
+    #[test]
+    fn test_jittered() {
+        let jittered = |seed| Jittered {
+            base: ms(200),
+            rng: fastrand::Rng::with_seed(seed),
+        };
+        let delays = sequence(&mut jittered(42), &[false; 8]);
+
+        assert_eq!(delays, sequence(&mut jittered(42), &[false; 8]));
+        assert_ne!(delays, sequence(&mut jittered(43), &[false; 8]));
+        assert!(
+            delays
+                .iter()
+                .all(|d| (ms(200)..=ms(200 + MAX_JITTER_MS)).contains(d))
+        );
+    }
+
+    #[test]
+    fn test_adaptive() {
+        let mut adaptive = Adaptive::new(ms(400));
+        let delays = sequence(
+            &mut adaptive,
+            &[true, true, true, true, false, false, false, false],
+        );
+
+        // doubles with each block up to 8x the base, then halves down to a quarter of it
+        let expected = [800, 1600, 3200, 3200, 1600, 800, 400, 200].map(ms);
+        assert_eq!(delays, expected);
+        assert_eq!(adaptive.next_delay(), ms(100));
+        assert_eq!(adaptive.next_delay(), ms(100));
+    }
+
+    #[test]
+    fn test_adaptive_blocked_twice_before_a_page() {
+        let mut adaptive = Adaptive::new(ms(400));
+        assert_eq!(adaptive.blocked(), Some(ms(800)));
+        assert_eq!(adaptive.blocked(), Some(ms(1600)));
+
+        assert_eq!(adaptive.next_delay(), ms(1600));
+        assert_eq!(adaptive.next_delay(), ms(800));
+    }
+
+    #[test]
+    fn test_adaptive_without_base_delay() {
+        let delays = sequence(
+            &mut Adaptive::new(Duration::ZERO),
+            &[true, true, false, false],
+        );
+
+        assert_eq!(delays, [250, 500, 250, 125].map(ms));
+    }
+}
@@ -1,20 +1,37 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
+use std::hash::BuildHasher;
 use std::sync::LazyLock;
+use std::time::Duration;
 
 use regex::Regex;
-use reqwest::{Client, Response, header};
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, Response};
+use tracing::warn;
 
-use super::{DEFAULT_USER_AGENT, Extract, Search, Settings};
+use super::{DEFAULT_USER_AGENT, Extract, MAX_REGEX_INPUT_LEN, PaginationKind, Search, Settings};
 
 const API_URL: &str = "https://api.dnsdumpster.com/htmld/";
-const SETTINGS: Settings = Settings {
+pub(super) const SETTINGS: Settings = Settings {
     name: "DNSDumpster",
     base_url: "https://dnsdumpster.com",
     user_agent: DEFAULT_USER_AGENT,
     max_rounds: 1,
+    pagination: PaginationKind::Single,
+    passive: true,
+    priority: 50,
+    per_page: 0,
+    max_per_page: 0,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 0,
 };
 
+/// Number of attempts to fetch the init token before giving up
+const INIT_ATTEMPTS: u8 = 3;
+/// Delay before the first init retry, doubled after each attempt
+const INIT_BACKOFF: Duration = Duration::from_millis(250);
+
 static INIT_TOKEN_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r#"hx-headers='\{"Authorization":\s*"(?<token>[[:alnum:]_\.-]+)"\}'"#)
         .expect("failed to compile regex for DNSDumpster init token")
@@ -36,13 +53,36 @@ impl DNSDumpster {
 
     /// Initializes the `DNSDumpster` session and returns the authorization token
     async fn init(&self, client: Client) -> Result<Option<String>, reqwest::Error> {
-        let init = client
-            .get(SETTINGS.base_url)
-            .header(header::REFERER, "https://dnsdumpster.com")
-            .send()
-            .await?;
+        Self::init_at(client, SETTINGS.base_url).await
+    }
+
+    /// Fetches the authorization token from `url`
+    ///
+    /// A flaky token fetch is retried a few times with a short backoff here,
+    /// so it doesn't burn the retry budget of the enumerate loop.
+    async fn init_at(client: Client, url: &str) -> Result<Option<String>, reqwest::Error> {
+        let mut backoff = INIT_BACKOFF;
+        let mut attempt = 1;
+
+        let body = loop {
+            let res = client
+                .get(url)
+                .header(header::REFERER, "https://dnsdumpster.com")
+                .send()
+                .await
+                .and_then(Response::error_for_status);
 
-        let body = init.text().await?;
+            match res {
+                Ok(r) => break r.text().await?,
+                Err(e) if attempt < INIT_ATTEMPTS => {
+                    warn!(attempt, err = ?e, "failed to fetch init token, retrying");
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        };
 
         let token = INIT_TOKEN_RE.captures(&body).map(|c| c["token"].to_owned());
 
@@ -55,12 +95,18 @@ impl Search for DNSDumpster {
         SETTINGS
     }
 
-    fn next_query(&self, _: &HashSet<String>) -> Option<Cow<'_, str>> {
+    fn next_query<S: BuildHasher>(&self, _: &HashSet<String, S>) -> Option<Cow<'_, str>> {
         Some(Cow::Borrowed(&self.domain))
     }
 
     #[doc = " Search for a query on a page"]
-    async fn search(&self, client: Client, _: &str, _: usize) -> Result<Response, reqwest::Error> {
+    async fn search(
+        &self,
+        client: Client,
+        _: &str,
+        _: usize,
+        headers: &HeaderMap,
+    ) -> Result<Response, reqwest::Error> {
         // default token to an empty string,
         // which will cause 401 Unauthorized when the post request is made
         let token = self.init(client.clone()).await?.unwrap_or_default();
@@ -76,10 +122,54 @@ impl Search for DNSDumpster {
             .header(header::ORIGIN, SETTINGS.base_url)
             .header(header::REFERER, SETTINGS.base_url)
             .header(header::USER_AGENT, SETTINGS.user_agent)
+            .headers(headers.clone())
             .send()
             .await
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    const INIT_PAGE: &str = r#"<div hx-headers='{"Authorization": "abc.DEF-123_x"}'></div>"#;
 
-    /// `DNSDumpster` only runs once, no need to delay
-    async fn delay(&self) {}
+    #[tokio::test]
+    async fn test_init_retries_once() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(INIT_PAGE))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let token = DNSDumpster::init_at(Client::new(), &server.uri())
+            .await
+            .unwrap();
+
+        assert_eq!(token.as_deref(), Some("abc.DEF-123_x"));
+    }
+
+    #[tokio::test]
+    async fn test_init_gives_up() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(u64::from(INIT_ATTEMPTS))
+            .mount(&server)
+            .await;
+
+        let res = DNSDumpster::init_at(Client::new(), &server.uri()).await;
+
+        assert!(res.is_err());
+    }
 }
@@ -0,0 +1,74 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use super::EngineChoice;
+
+/// Something that happened while an engine ran, for embedders rendering their own progress
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    EngineStarted {
+        engine: &'static str,
+    },
+    /// A page was read, `page` is its index as the engine paginates
+    PageFetched {
+        engine: &'static str,
+        page: usize,
+    },
+    /// A subdomain the engine hadn't found yet, before any post-processing
+    SubdomainFound {
+        engine: &'static str,
+        name: String,
+    },
+    /// The engine stopped, having found `found` subdomains
+    EngineCompleted {
+        engine: &'static str,
+        found: usize,
+    },
+}
+
+/// Called with every [`ProgressEvent`] of every engine it's handed to
+///
+/// Engines run concurrently, so it's called from several tasks at once and should return quickly.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(ProgressEvent) + Send + Sync>);
+
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(ProgressEvent) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub(crate) fn emit(&self, event: ProgressEvent) {
+        (self.0)(event);
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// Called with the subdomains an engine found once it's done with a domain, e.g. to save progress
+///
+/// Like [`ProgressCallback`], it's called from several tasks at once.
+#[derive(Clone)]
+pub struct EngineDoneCallback(Arc<EngineDoneFn>);
+
+type EngineDoneFn = dyn Fn(EngineChoice, &HashSet<String>) + Send + Sync;
+
+impl EngineDoneCallback {
+    pub fn new(callback: impl Fn(EngineChoice, &HashSet<String>) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+
+    pub(crate) fn call(&self, engine: EngineChoice, found: &HashSet<String>) {
+        (self.0)(engine, found);
+    }
+}
+
+impl fmt::Debug for EngineDoneCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EngineDoneCallback")
+    }
+}
@@ -0,0 +1,220 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, Response};
+use serde::Deserialize;
+
+use super::{DEFAULT_USER_AGENT, Extract, PaginationKind, Search, Settings};
+use crate::normalize;
+
+const PER_PAGE: usize = 100;
+pub(super) const SETTINGS: Settings = Settings {
+    name: "FacebookCT",
+    base_url: "https://graph.facebook.com/certificates",
+    user_agent: DEFAULT_USER_AGENT,
+    max_rounds: 10,
+    pagination: PaginationKind::Cursor,
+    passive: true,
+    priority: 60,
+    per_page: 0,
+    max_per_page: 0,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 200,
+};
+/// Environment variable holding the Graph API access token, in the form `{app-id}|{app-secret}`
+const ACCESS_TOKEN_ENV: &str = "FB_ACCESS_TOKEN";
+/// Environment variables the Graph API can't be searched without
+pub(super) const REQUIRED_ENV: &[&str] = &[ACCESS_TOKEN_ENV];
+
+pub struct FacebookCT {
+    domain: String,
+    access_token: Option<String>,
+    paging: Option<Paging>,
+}
+
+impl FacebookCT {
+    pub fn new(domain: impl Into<String>) -> Self {
+        Self {
+            domain: domain.into(),
+            access_token: std::env::var(ACCESS_TOKEN_ENV).ok(),
+            paging: None,
+        }
+    }
+}
+
+impl Extract for FacebookCT {
+    fn extract(&mut self, input: &str) -> HashSet<String> {
+        match serde_json::from_str::<FacebookCTResponse>(input) {
+            Ok(r) => {
+                self.paging = Some(r.paging.unwrap_or_default());
+                r.data.into_iter().flat_map(|c| c.domains).collect()
+            }
+            Err(_) => HashSet::new(),
+        }
+    }
+
+    /// Certificates can cover other domains as well, only keep the target's subdomains
+    fn clean(&self, raw: HashSet<String>) -> HashSet<String> {
+        raw.into_iter()
+            .map(|d| d.to_ascii_lowercase())
+            .filter(|d| normalize::is_subdomain_of(d, &self.domain))
+            .collect()
+    }
+
+    fn domain(&self) -> Option<&str> {
+        Some(&self.domain)
+    }
+}
+
+impl Search for FacebookCT {
+    fn settings(&self) -> Settings {
+        SETTINGS
+    }
+
+    /// Returns `None` without an access token, though such an engine is never selected to run
+    fn next_query<S: BuildHasher>(&self, _: &HashSet<String, S>) -> Option<Cow<'_, str>> {
+        let Self {
+            domain,
+            access_token,
+            paging,
+        } = self;
+
+        access_token.as_ref()?;
+
+        let base_url = SETTINGS.base_url;
+        let base_query = format!("{base_url}?query={domain}&fields=domains&limit={PER_PAGE}");
+
+        let query = match paging {
+            Some(Paging {
+                next: Some(_),
+                cursors: Some(Cursors { after: Some(c) }),
+            }) => format!("{base_query}&after={c}"),
+            Some(_) => return None,
+            None => base_query,
+        };
+
+        Some(Cow::Owned(query))
+    }
+
+    async fn search(
+        &self,
+        client: Client,
+        url: &str,
+        _: usize,
+        headers: &HeaderMap,
+    ) -> Result<Response, reqwest::Error> {
+        client
+            .get(url)
+            .query(&[(
+                "access_token",
+                self.access_token.as_deref().unwrap_or_default(),
+            )])
+            .header(header::USER_AGENT, SETTINGS.user_agent)
+            .header(header::ACCEPT, "application/json")
+            .headers(headers.clone())
+            .send()
+            .await
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FacebookCTResponse {
+    data: Vec<Certificate>,
+    paging: Option<Paging>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Certificate {
+    #[serde(default)]
+    domains: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Paging {
+    cursors: Option<Cursors>,
+    /// Only present when there's another page
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Cursors {
+    after: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> FacebookCT {
+        FacebookCT {
+            domain: "example.com".to_owned(),
+            access_token: Some("app|secret".to_owned()),
+            paging: None,
+        }
+    }
+
+    #[test]
+    fn test_extract() {
+        let input = r#"{
+            "data": [
+                {"domains": ["app.example.com", "API.example.com"], "id": "1"},
+                {"domains": ["mail.example.com", "example.org"], "id": "2"},
+                {"id": "3"}
+            ],
+            "paging": {
+                "cursors": {"before": "MAZDZD", "after": "MQZDZD"},
+                "next": "https://graph.facebook.com/v19.0/certificates?after=MQZDZD"
+            }
+        }"#;
+
+        let mut fb = engine();
+        let results = fb.extract(input);
+        let results = fb.clean(results);
+
+        let expected = HashSet::from([
+            "app.example.com".to_owned(),
+            "api.example.com".to_owned(),
+            "mail.example.com".to_owned(),
+        ]);
+        assert_eq!(results, expected);
+
+        let query = fb.next_query(&HashSet::new()).unwrap();
+        assert!(query.ends_with("&after=MQZDZD"));
+    }
+
+    #[test]
+    fn test_last_page() {
+        let input = r#"{
+            "data": [{"domains": ["app.example.com"]}],
+            "paging": {"cursors": {"before": "MAZDZD", "after": "MQZDZD"}}
+        }"#;
+
+        let mut fb = engine();
+        fb.extract(input);
+
+        assert!(fb.next_query(&HashSet::new()).is_none());
+    }
+
+    #[test]
+    fn test_first_query() {
+        let fb = engine();
+        let query = fb.next_query(&HashSet::new()).unwrap();
+        assert_eq!(
+            query,
+            "https://graph.facebook.com/certificates?query=example.com&fields=domains&limit=100"
+        );
+        assert!(!query.contains("secret"));
+    }
+
+    #[test]
+    fn test_no_access_token() {
+        let fb = FacebookCT {
+            access_token: None,
+            ..engine()
+        };
+        assert!(fb.next_query(&HashSet::new()).is_none());
+    }
+}
@@ -0,0 +1,239 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, Response};
+use serde::Deserialize;
+
+use super::{DEFAULT_USER_AGENT, Extract, PaginationKind, Search, Settings};
+use crate::normalize;
+
+const PER_PAGE: usize = 100;
+pub(super) const SETTINGS: Settings = Settings {
+    name: "Fofa",
+    base_url: "https://fofa.info/api/v1/search/all",
+    user_agent: DEFAULT_USER_AGENT,
+    max_rounds: 10,
+    pagination: PaginationKind::Cursor,
+    passive: true,
+    priority: 50,
+    per_page: 0,
+    max_per_page: 0,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 200,
+};
+/// Environment variable holding the email of the Fofa account
+const EMAIL_ENV: &str = "FOFA_EMAIL";
+/// Environment variable holding the Fofa API key
+const KEY_ENV: &str = "FOFA_KEY";
+/// Environment variables Fofa can't search without
+pub(super) const REQUIRED_ENV: &[&str] = &[EMAIL_ENV, KEY_ENV];
+
+pub struct Fofa {
+    domain: String,
+    credentials: Option<(String, String)>,
+    /// 1-based index of the next page to fetch
+    page: usize,
+    /// Total number of results, known after the first page
+    total: Option<usize>,
+}
+
+impl Fofa {
+    pub fn new(domain: impl Into<String>) -> Self {
+        let credentials = std::env::var(EMAIL_ENV)
+            .ok()
+            .zip(std::env::var(KEY_ENV).ok());
+        Self {
+            domain: domain.into(),
+            credentials,
+            page: 1,
+            total: None,
+        }
+    }
+
+    /// Fofa takes its query base64-encoded, e.g. `domain="example.com"`
+    fn qbase64(&self) -> String {
+        BASE64_STANDARD.encode(format!(r#"domain="{}""#, self.domain))
+    }
+}
+
+impl Extract for Fofa {
+    fn extract(&mut self, input: &str) -> HashSet<String> {
+        match serde_json::from_str::<FofaResponse>(input) {
+            Ok(r) if !r.error => {
+                self.page += 1;
+                self.total = Some(r.size);
+                r.results.into_iter().map(Row::into_host).collect()
+            }
+            // an error (e.g. bad credentials or no credits left) won't go away on the next page
+            _ => {
+                self.total = Some(0);
+                HashSet::new()
+            }
+        }
+    }
+
+    /// Hosts can carry a scheme or a port and may belong to other domains
+    fn clean(&self, raw: HashSet<String>) -> HashSet<String> {
+        raw.iter()
+            .map(|h| normalize::clean(h).to_ascii_lowercase())
+            .filter(|h| normalize::is_subdomain_of(h, &self.domain))
+            .collect()
+    }
+
+    fn domain(&self) -> Option<&str> {
+        Some(&self.domain)
+    }
+}
+
+impl Search for Fofa {
+    fn settings(&self) -> Settings {
+        SETTINGS
+    }
+
+    /// Returns `None` without credentials, though such an engine is never selected to run
+    fn next_query<S: BuildHasher>(&self, _: &HashSet<String, S>) -> Option<Cow<'_, str>> {
+        self.credentials.as_ref()?;
+
+        if self.total.is_some_and(|t| (self.page - 1) * PER_PAGE >= t) {
+            return None;
+        }
+
+        let base_url = SETTINGS.base_url;
+        let qbase64 = self.qbase64();
+        let page = self.page;
+        let query = format!("{base_url}?qbase64={qbase64}&fields=host&page={page}&size={PER_PAGE}");
+
+        Some(Cow::Owned(query))
+    }
+
+    async fn search(
+        &self,
+        client: Client,
+        url: &str,
+        _: usize,
+        headers: &HeaderMap,
+    ) -> Result<Response, reqwest::Error> {
+        let (email, key) = self.credentials.clone().unwrap_or_default();
+        client
+            .get(url)
+            .query(&[("email", email), ("key", key)])
+            .header(header::USER_AGENT, SETTINGS.user_agent)
+            .header(header::ACCEPT, "application/json")
+            .headers(headers.clone())
+            .send()
+            .await
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FofaResponse {
+    #[serde(default)]
+    error: bool,
+    /// Total number of results across all pages
+    #[serde(default)]
+    size: usize,
+    #[serde(default)]
+    results: Vec<Row>,
+}
+
+/// A result row, a bare value when a single field is requested
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Row {
+    Host(String),
+    Fields(Vec<String>),
+}
+
+impl Row {
+    fn into_host(self) -> String {
+        match self {
+            Row::Host(h) => h,
+            Row::Fields(f) => f.into_iter().next().unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> Fofa {
+        Fofa {
+            domain: "example.com".to_owned(),
+            credentials: Some(("me@example.org".to_owned(), "secret".to_owned())),
+            page: 1,
+            total: None,
+        }
+    }
+
+    #[test]
+    fn test_first_query() {
+        let fofa = engine();
+        let query = fofa.next_query(&HashSet::new()).unwrap();
+
+        // ZG9tYWluPSJleGFtcGxlLmNvbSI= is `domain="example.com"` in base64
+        assert_eq!(
+            query,
+            "https://fofa.info/api/v1/search/all?qbase64=ZG9tYWluPSJleGFtcGxlLmNvbSI=&fields=host&page=1&size=100"
+        );
+        assert!(!query.contains("secret"));
+    }
+
+    #[test]
+    fn test_extract() {
+        let input = r#"{
+            "error": false,
+            "size": 150,
+            "page": 1,
+            "mode": "extended",
+            "query": "domain=\"example.com\"",
+            "results": [
+                "app.example.com",
+                "https://API.example.com",
+                "mail.example.com:8443",
+                "example.org"
+            ]
+        }"#;
+
+        let mut fofa = engine();
+        let results = fofa.extract(input);
+        let results = fofa.clean(results);
+
+        let expected = HashSet::from([
+            "app.example.com".to_owned(),
+            "api.example.com".to_owned(),
+            "mail.example.com".to_owned(),
+        ]);
+        assert_eq!(results, expected);
+
+        // 150 results over pages of 100, so there's exactly one more page
+        let query = fofa.next_query(&HashSet::new()).unwrap();
+        assert!(query.contains("&page=2&"));
+        fofa.extract(r#"{"error": false, "size": 150, "results": [["www.example.com"]]}"#);
+        assert!(fofa.next_query(&HashSet::new()).is_none());
+    }
+
+    #[test]
+    fn test_extract_error() {
+        let mut fofa = engine();
+        let results =
+            fofa.extract(r#"{"error": true, "errmsg": "[820031] insufficient F points"}"#);
+
+        assert!(results.is_empty());
+        assert!(fofa.next_query(&HashSet::new()).is_none());
+    }
+
+    #[test]
+    fn test_no_credentials() {
+        let fofa = Fofa {
+            credentials: None,
+            ..engine()
+        };
+        assert!(fofa.next_query(&HashSet::new()).is_none());
+    }
+}
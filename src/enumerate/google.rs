@@ -1,13 +1,18 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use reqwest::header::{self};
+use reqwest::header::{self, HeaderMap};
 use reqwest::{Client, Response};
+use tracing::warn;
 
-use super::{Extract, Search, Settings};
+use super::{Extract, MAX_REGEX_INPUT_LEN, PaginationKind, Search, Settings, exclusions};
 
 const PER_PAGE: usize = 20;
-const SETTINGS: Settings = Settings {
+/// Most subdomains excluded from a query, Google ignores every word past the 32nd
+const MAX_EXCLUDED: usize = 30;
+pub(super) const SETTINGS: Settings = Settings {
     name: "Google",
     base_url: "https://www.google.com/search",
     // Google requires JavaScript to be enabled for `/search` public endpoint.
@@ -20,26 +25,109 @@ const SETTINGS: Settings = Settings {
     // - "w3m/0.5.3"
     user_agent: "Lynx/2.8.6rel.5 libwww-FM/2.14",
     max_rounds: 20,
+    pagination: PaginationKind::OffsetPages,
+    passive: true,
+    priority: 30,
+    per_page: PER_PAGE,
+    max_per_page: 100,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 200,
 };
 
+/// Markers of Google's "unusual traffic" CAPTCHA page
+const BLOCK_FINGERPRINTS: [&str; 3] = [
+    "Our systems have detected unusual traffic",
+    "/sorry/index",
+    "g-recaptcha",
+];
+
+/// Host of the cookie consent interstitial Google redirects to in some regions
+const CONSENT_HOST: &str = "consent.google.com";
+
+/// Cookies recording that the cookie consent was answered, which skips the interstitial
+const CONSENT_COOKIE: &str = "SOCS=CAESEwgDEgk0ODE3Nzk3MjQaAmVuIAEaBgiA_LyaBg; CONSENT=YES+";
+
 #[derive(Extract)]
 #[extract(pattern = r#"<span.*?>(?<subdomain>[[:alnum:]\-\.]*?\.{domain})\s&#8250;.*?<\/span>"#)]
 pub struct Google {
     #[extract(domain)]
     domain: String,
+    /// Whether requests carry the consent cookie, set once the interstitial shows up
+    consent: AtomicBool,
+    per_page: usize,
 }
 
 impl Google {
     pub fn new(domain: impl Into<String>) -> Self {
         Self {
             domain: domain.into(),
+            consent: AtomicBool::new(false),
+            per_page: PER_PAGE,
         }
     }
+
+    /// Index of the first result of `page`
+    fn offset(&self, page: usize) -> usize {
+        // Google's search pagination uses a 0-based index.
+        // For a page size of 20, the pagination is as follows:
+        // `page` = 0 (1st page): start=0  (results 1-20)
+        // `page` = 1 (2nd page): start=20 (results 21-40)
+        // `page` = 2 (3rd page): start=40 (results 41-60)
+        // and so on...
+        page * self.settings().per_page
+    }
+
+    /// Search `url`, answering the cookie consent interstitial if Google redirects to it
+    async fn search_at(
+        &self,
+        client: Client,
+        url: &str,
+        query: &str,
+        page: usize,
+        headers: &HeaderMap,
+    ) -> Result<Response, reqwest::Error> {
+        let start = self.offset(page);
+        let per_page = self.settings().per_page;
+
+        let send = |consent: bool| {
+            let mut req = client
+                .get(url)
+                .query(&[("q", query)])
+                .query(&[("hl", "en-US")])
+                .query(&[("num", per_page)]) // number of search results per page
+                .query(&[("start", start)]) // starting position for pagination
+                .query(&[("filter", "0")]) // duplicates content filter, 0 = include duplicates
+                .header(header::USER_AGENT, SETTINGS.user_agent);
+            if consent {
+                req = req.header(header::COOKIE, CONSENT_COOKIE);
+            }
+            req.headers(headers.clone()).send()
+        };
+
+        let consent = self.consent.load(Ordering::Relaxed);
+        let resp = send(consent).await?;
+        if consent || resp.url().host_str() != Some(CONSENT_HOST) {
+            return Ok(resp);
+        }
+
+        // the interstitial would be scraped as an empty page, retry with the consent cookie
+        warn!("redirected to the cookie consent page, retrying with the consent cookie");
+        self.consent.store(true, Ordering::Relaxed);
+        send(true).await
+    }
 }
 
 impl Search for Google {
     fn settings(&self) -> Settings {
-        SETTINGS
+        Settings {
+            per_page: self.per_page,
+            ..SETTINGS
+        }
+    }
+
+    fn set_per_page(&mut self, per_page: usize) {
+        self.per_page = per_page;
     }
 
     /// Constructs a search query for subdomain enumeration
@@ -47,7 +135,8 @@ impl Search for Google {
     /// Creates a query using Google's search syntax. The query structure is:
     /// * `site:domain.com` to restrict results to the target domain
     /// * `-www.domain.com` to exclude the default `www` subdomain
-    /// * Additional `-subdomain.domain.com` terms for each known subdomain
+    /// * Additional `-subdomain.domain.com` terms for up to `MAX_EXCLUDED` known subdomains,
+    ///   the shallowest first
     ///
     /// For example:
     ///
@@ -56,10 +145,9 @@ impl Search for Google {
     ///
     ///  - If subdomains have been discovered, the query will be:
     ///    `site:example.com -www.example.com -subdomain1.example.com -subdomain2.example.com`
-    fn next_query(&self, subdomains: &HashSet<String>) -> Option<Cow<'_, str>> {
-        // TODO: consider limiting the number of subdomains to exclude
-        let found = subdomains
-            .iter()
+    fn next_query<S: BuildHasher>(&self, subdomains: &HashSet<String, S>) -> Option<Cow<'_, str>> {
+        let found = exclusions(subdomains, MAX_EXCLUDED)
+            .into_iter()
             .fold(String::new(), |acc, d| format!("{acc} -{d}"));
 
         let query = format!("site:{0} -www.{0}{1}", self.domain, found);
@@ -71,32 +159,23 @@ impl Search for Google {
         client: Client,
         query: &str,
         page: usize,
+        headers: &HeaderMap,
     ) -> Result<Response, reqwest::Error> {
-        // Google's search pagination uses a 0-based index.
-        // For `PER_PAGE` = 20, the pagination is as follows:
-        // `page` = 0 (1st page): start=0  (results 1-20)
-        // `page` = 1 (2nd page): start=20 (results 21-40)
-        // `page` = 2 (3rd page): start=40 (results 41-60)
-        // and so on...
-        let start = page * PER_PAGE;
-
-        client
-            .get(SETTINGS.base_url)
-            .query(&[("q", query)])
-            .query(&[("hl", "en-US")])
-            .query(&[("num", PER_PAGE)]) // number of search results per page
-            .query(&[("start", start)]) // starting position for pagination
-            .query(&[("filter", "0")]) // duplicates content filter, 0 = include duplicates
-            .header(header::USER_AGENT, SETTINGS.user_agent)
-            .send()
+        self.search_at(client, SETTINGS.base_url, query, page, headers)
             .await
     }
+
+    fn is_blocked(&self, body: &str) -> bool {
+        BLOCK_FINGERPRINTS.iter().any(|f| body.contains(f))
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use rstest::rstest;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     use super::*;
 
@@ -124,12 +203,35 @@ mod tests {
         let google = Google::new(domain);
         let query = google.next_query(&subdomains).unwrap();
 
-        let (expected1, expected2) = (
-            "site:example.com -www.example.com -first.example.com -second.example.com",
-            "site:example.com -www.example.com -second.example.com -first.example.com",
+        assert_eq!(
+            query,
+            "site:example.com -www.example.com -first.example.com -second.example.com"
+        );
+    }
+
+    #[test]
+    fn test_generate_query_many() {
+        let mut names: Vec<String> = (0..10_000).map(|i| format!("s{i}.example.com")).collect();
+        let google = Google::new("example.com");
+        let query = |names: &[String]| {
+            let subdomains: HashSet<String> = names.iter().cloned().collect();
+            google.next_query(&subdomains).unwrap().into_owned()
+        };
+
+        // the same exclusions however the set is built
+        let first = query(&names);
+        assert_eq!(
+            first,
+            query(&names.iter().rev().cloned().collect::<Vec<_>>())
         );
 
-        assert!(query == expected1 || query == expected2);
+        names.sort_unstable();
+        let expected = names[..MAX_EXCLUDED]
+            .iter()
+            .fold("site:example.com -www.example.com".to_owned(), |acc, d| {
+                format!("{acc} -{d}")
+            });
+        assert_eq!(first, expected);
     }
 
     #[rstest]
@@ -162,4 +264,120 @@ mod tests {
         let expected: HashSet<String> = expected.into_iter().map(String::from).collect();
         assert_eq!(expected, results);
     }
+
+    #[rstest]
+    #[case::unusual_traffic(
+        r"<div>Our systems have detected unusual traffic from your computer network.</div>",
+        true
+    )]
+    #[case::sorry_redirect(
+        r#"<form action="/sorry/index" method="post"><input type="hidden" name="q"></form>"#,
+        true
+    )]
+    #[case::recaptcha(r#"<div class="g-recaptcha" data-sitekey="abc"></div>"#, true)]
+    #[case::results(r"<span>app.example.com &#8250; Text</span>", false)]
+    #[case::empty("", false)]
+    fn test_is_blocked(#[case] input: &str, #[case] expected: bool) {
+        let google = Google::new("example.com");
+        assert_eq!(google.is_blocked(input), expected);
+    }
+
+    #[tokio::test]
+    async fn test_search_consent_redirect() {
+        let server = MockServer::start().await;
+        let consent_url = format!(
+            "http://{CONSENT_HOST}:{}/ml?continue=/search",
+            server.address().port()
+        );
+
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .and(header("cookie", CONSENT_COOKIE))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("<span>app.example.com &#8250; Text</span>"),
+            )
+            .with_priority(1)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .respond_with(ResponseTemplate::new(302).insert_header("location", consent_url))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/ml"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("Before you continue"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // send the consent host to the mock server instead of Google
+        let client = Client::builder()
+            .resolve(CONSENT_HOST, *server.address())
+            .build()
+            .unwrap();
+        let url = format!("{}/search", server.uri());
+        let google = Google::new("example.com");
+
+        let resp = google
+            .search_at(
+                client.clone(),
+                &url,
+                "site:example.com",
+                0,
+                &HeaderMap::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.url().path(), "/search");
+
+        // later pages send the cookie right away
+        google
+            .search_at(client, &url, "site:example.com", 1, &HeaderMap::new())
+            .await
+            .unwrap();
+    }
+
+    #[rstest]
+    #[case::default(None, 2, 40)]
+    #[case::overridden(Some(50), 2, 100)]
+    #[case::first_page(Some(50), 0, 0)]
+    fn test_offset(#[case] per_page: Option<usize>, #[case] page: usize, #[case] expected: usize) {
+        let mut google = Google::new("example.com");
+        if let Some(n) = per_page {
+            google.set_per_page(n);
+        }
+        assert_eq!(google.offset(page), expected);
+    }
+
+    #[tokio::test]
+    async fn test_search_per_page() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .and(query_param("num", "50"))
+            .and(query_param("start", "150"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut google = Google::new("example.com");
+        google.set_per_page(50);
+        let url = format!("{}/search", server.uri());
+        let resp = google
+            .search_at(
+                Client::new(),
+                &url,
+                "site:example.com",
+                3,
+                &HeaderMap::new(),
+            )
+            .await
+            .unwrap();
+        assert!(resp.status().is_success());
+    }
 }
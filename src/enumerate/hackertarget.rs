@@ -1,37 +1,66 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
+use std::hash::BuildHasher;
 
+use reqwest::header::HeaderMap;
 use reqwest::{Client, Response};
+use tracing::warn;
 
-use super::{Extract, Search, Settings};
+use super::{Extract, PaginationKind, Search, Settings};
 
-const SETTINGS: Settings = Settings {
+pub(super) const SETTINGS: Settings = Settings {
     name: "HackerTarget",
     base_url: "https://api.hackertarget.com/hostsearch/",
     user_agent: "", // not used
     max_rounds: 1,
+    pagination: PaginationKind::Single,
+    passive: true,
+    priority: 70,
+    per_page: 0,
+    max_per_page: 0,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 0,
 };
+/// Environment variable holding the HackerTarget API key, optional
+const KEY_ENV: &str = "HACKERTARGET_API_KEY";
+/// Start of the plain text answer served instead of results once the daily quota is used up
+const QUOTA_EXCEEDED: &str = "API count exceeded";
 
+/// Works without a key, up to the free tier's daily quota
 pub struct HackerTarget {
     domain: String,
+    api_key: Option<String>,
 }
 
 impl HackerTarget {
     pub fn new(domain: impl Into<String>) -> Self {
         Self {
             domain: domain.into(),
+            api_key: std::env::var(KEY_ENV).ok(),
         }
     }
 }
 
 impl Extract for HackerTarget {
     fn extract(&mut self, input: &str) -> HashSet<String> {
+        if input.trim_start().starts_with(QUOTA_EXCEEDED) {
+            warn!(
+                "HackerTarget's daily API quota is used up, set {KEY_ENV} to raise it or try again tomorrow"
+            );
+            return HashSet::new();
+        }
+
         input
             .split('\n')
             .filter_map(|l| l.split_once(','))
             .map(|(host, _)| host.to_owned())
             .collect()
     }
+
+    fn domain(&self) -> Option<&str> {
+        Some(&self.domain)
+    }
 }
 
 impl Search for HackerTarget {
@@ -39,7 +68,7 @@ impl Search for HackerTarget {
         SETTINGS
     }
 
-    fn next_query(&self, _: &HashSet<String>) -> Option<Cow<'_, str>> {
+    fn next_query<S: BuildHasher>(&self, _: &HashSet<String, S>) -> Option<Cow<'_, str>> {
         Some(Cow::Borrowed(&self.domain))
     }
 
@@ -48,14 +77,36 @@ impl Search for HackerTarget {
         client: Client,
         query: &str,
         _: usize,
+        headers: &HeaderMap,
     ) -> Result<Response, reqwest::Error> {
-        client
-            .get(SETTINGS.base_url)
-            .query(&[("q", query)])
-            .send()
-            .await
+        let mut req = client.get(SETTINGS.base_url).query(&[("q", query)]);
+        if let Some(key) = &self.api_key {
+            req = req.query(&[("apikey", key)]);
+        }
+        req.headers(headers.clone()).send().await
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
 
-    /// `HackerTarget` only runs once, no need to delay
-    async fn delay(&self) {}
+    #[rstest]
+    #[case::results(
+        "app.example.com,192.0.2.1\napi.example.com,192.0.2.2\n",
+        vec!["app.example.com", "api.example.com"]
+    )]
+    #[case::quota_exceeded("API count exceeded - Increase Quota with Membership", vec![])]
+    // the message itself must never come out as a name, even if it someday holds a comma
+    #[case::quota_exceeded_comma("API count exceeded, example.com,192.0.2.1\n", vec![])]
+    #[case::empty("", vec![])]
+    fn test_extract(#[case] input: &str, #[case] expected: Vec<&str>) {
+        let mut engine = HackerTarget::new("example.com");
+        let results = engine.extract(input);
+
+        let expected: HashSet<String> = expected.into_iter().map(String::from).collect();
+        assert_eq!(results, expected);
+    }
 }
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use url::Url;
+
+/// Requests each host serves at once by default
+pub const DEFAULT_PER_HOST_CONCURRENCY: usize = 1;
+
+/// Limits how many requests are in flight to the same host, across every engine
+///
+/// Clones share the same limits, so one limiter is handed to every engine of a run.
+#[derive(Debug, Clone)]
+pub struct HostLimiter {
+    per_host: usize,
+    hosts: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+}
+
+impl HostLimiter {
+    #[must_use]
+    pub fn new(per_host: usize) -> Self {
+        Self {
+            per_host,
+            hosts: Arc::default(),
+        }
+    }
+
+    /// Wait until a request may be sent to the host of `url`, which lasts as long as the permit
+    ///
+    /// # Panics
+    ///
+    /// If an engine panicked while acquiring a permit
+    pub async fn acquire(&self, url: &str) -> OwnedSemaphorePermit {
+        let semaphore = self
+            .hosts
+            .lock()
+            .unwrap()
+            .entry(host(url))
+            .or_insert_with(|| Arc::new(Semaphore::new(self.per_host)))
+            .clone();
+
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed")
+    }
+}
+
+/// Host of `url`, or `url` itself if it has none
+fn host(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_owned))
+        .unwrap_or_else(|| url.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_host() {
+        assert_eq!(host("https://www.google.com/search"), "www.google.com");
+        assert_eq!(host("not a url"), "not a url");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_acquire_serializes_same_host() {
+        let limiter = HostLimiter::new(1);
+        let permit = limiter.acquire("https://a.test/search").await;
+
+        // another host isn't held up
+        let other =
+            tokio::time::timeout(Duration::from_secs(1), limiter.acquire("https://b.test/"));
+        assert!(other.await.is_ok());
+
+        let same = tokio::time::timeout(Duration::from_secs(1), limiter.acquire("https://a.test/"));
+        assert!(same.await.is_err());
+
+        drop(permit);
+        let same = tokio::time::timeout(Duration::from_secs(1), limiter.acquire("https://a.test/"));
+        assert!(same.await.is_ok());
+    }
+}
@@ -0,0 +1,132 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Response};
+
+use super::{Extract, PaginationKind, Search, Settings};
+
+/// An engine that never touches the network and serves the same body every round
+pub struct Mock {
+    pub query: &'static str,
+    pub body: &'static str,
+    /// Status code of the served response
+    pub status: u16,
+    /// Headers of the served response
+    pub headers: Vec<(&'static str, &'static str)>,
+    pub max_rounds: usize,
+    pub pagination: PaginationKind,
+    /// Send requests to this server instead of serving `body`
+    pub server: Option<String>,
+    /// Number of times `search` was called
+    pub searches: Arc<AtomicUsize>,
+    /// Every query `search` was called with
+    pub queries: Arc<Mutex<Vec<String>>>,
+    /// Exclude known subdomains from the query, the way search engines do
+    pub excludes: bool,
+    /// Panic while extracting, to simulate a buggy parser
+    pub panics: bool,
+    /// Reported as the engine's `max_input_len`
+    pub max_input_len: Option<usize>,
+}
+
+impl Mock {
+    pub fn new(query: &'static str, body: &'static str) -> Self {
+        Self {
+            query,
+            body,
+            status: 200,
+            headers: Vec::new(),
+            max_rounds: 1,
+            pagination: PaginationKind::OffsetPages,
+            server: None,
+            searches: Arc::default(),
+            queries: Arc::default(),
+            excludes: false,
+            panics: false,
+            max_input_len: None,
+        }
+    }
+}
+
+impl Extract for Mock {
+    fn extract(&mut self, input: &str) -> HashSet<String> {
+        assert!(!self.panics, "mock engine panicked");
+        input.split_whitespace().map(String::from).collect()
+    }
+
+    fn clean(&self, raw: HashSet<String>) -> HashSet<String> {
+        raw.into_iter().map(|s| s.to_ascii_lowercase()).collect()
+    }
+
+    fn max_input_len(&self) -> Option<usize> {
+        self.max_input_len
+    }
+}
+
+impl Search for Mock {
+    fn settings(&self) -> Settings {
+        Settings {
+            name: "Mock",
+            base_url: "https://mock.test",
+            user_agent: "",
+            max_rounds: self.max_rounds,
+            pagination: self.pagination,
+            passive: true,
+            priority: 50,
+            per_page: 0,
+            max_per_page: 0,
+            backoff_base_secs: 1,
+            backoff_factor: 2,
+            delay_ms: 0,
+        }
+    }
+
+    fn next_query<S: BuildHasher>(&self, subdomains: &HashSet<String, S>) -> Option<Cow<'_, str>> {
+        if !self.excludes {
+            return Some(Cow::Borrowed(self.query));
+        }
+        let mut known: Vec<_> = subdomains.iter().collect();
+        known.sort_unstable();
+        let query = known
+            .iter()
+            .fold(self.query.to_owned(), |acc, d| format!("{acc} -{d}"));
+        Some(Cow::Owned(query))
+    }
+
+    async fn search(
+        &self,
+        client: Client,
+        query: &str,
+        page: usize,
+        headers: &HeaderMap,
+    ) -> Result<Response, reqwest::Error> {
+        self.searches.fetch_add(1, Ordering::Relaxed);
+        self.queries.lock().unwrap().push(query.to_owned());
+        if let Some(url) = &self.server {
+            return client
+                .get(format!("{url}/search"))
+                .query(&[("q", query)])
+                .query(&[("page", page)])
+                .headers(headers.clone())
+                .send()
+                .await;
+        }
+
+        let resp = self
+            .headers
+            .iter()
+            .fold(http::Response::builder(), |b, (k, v)| b.header(*k, *v))
+            .status(self.status)
+            .body(self.body)
+            .expect("mock response should be valid");
+        Ok(resp.into())
+    }
+
+    fn is_blocked(&self, body: &str) -> bool {
+        body.contains("unusual traffic")
+    }
+}
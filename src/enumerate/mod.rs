@@ -1,38 +1,88 @@
 use std::borrow::Cow;
-use std::collections::HashSet;
-use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{BuildHasher, RandomState};
+use std::io::Read;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
 
 use clap::ValueEnum;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
 use enum_dispatch::enum_dispatch;
 use enumerate_derive::Extract;
-use owo_colors::OwoColorize;
-use reqwest::header::{ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, HeaderMap, HeaderValue};
-use reqwest::{Client, Response};
+use flate2::read::GzDecoder;
+use owo_colors::{OwoColorize, Stream};
+use regex::Regex;
+use reqwest::header::{ACCEPT, ACCEPT_ENCODING, ACCEPT_LANGUAGE, CONTENT_TYPE, HeaderMap,
+                      HeaderValue, RETRY_AFTER};
+use reqwest::{Client, Response, StatusCode};
+use serde::{Deserialize, Serialize};
 use strum::{EnumDiscriminants, VariantArray};
-use tracing::{info, trace, warn};
+use tracing::{debug, info, trace, warn};
+use url::{Host, Url};
 
 use self::alienvault::AlienVault;
+use self::axfr::Axfr;
 use self::bing::Bing;
+use self::cache::ResponseCache;
 use self::crtsh::CrtSh;
+use self::delay::{DelayKind, DelayStrategy};
 use self::dnsdumpster::DNSDumpster;
+use self::events::{ProgressCallback, ProgressEvent};
+use self::facebookct::FacebookCT;
+use self::fofa::Fofa;
 use self::google::Google;
 use self::hackertarget::HackerTarget;
+use self::hosts::HostLimiter;
+use self::pacer::Pacer;
 use self::rapiddns::RapidDNS;
+use self::subdomaincenter::SubdomainCenter;
 use self::virustotal::VirusTotal;
 use self::yahoo::Yahoo;
+use self::zoomeye::ZoomEye;
+use crate::{Error, normalize, sources};
 
 pub mod alienvault;
+pub mod axfr;
 pub mod bing;
+pub mod cache;
 pub mod crtsh;
+pub mod delay;
 pub mod dnsdumpster;
+pub mod events;
+pub mod facebookct;
+pub mod fofa;
 pub mod google;
 pub mod hackertarget;
+pub mod hosts;
+#[cfg(test)]
+pub(crate) mod mock;
+pub mod pacer;
+pub mod profile;
 pub mod rapiddns;
+pub mod subdomaincenter;
 pub mod virustotal;
 pub mod yahoo;
+pub mod zoomeye;
 
 const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/133.0.0.0 Safari/537.36";
 
+/// Chrome stable releases, major version and release date in seconds since the Unix epoch
+const CHROME_RELEASES: [(u32, i64); 6] = [
+    (128, 1_724_112_000), // 2024-08-20
+    (131, 1_731_369_600), // 2024-11-12
+    (133, 1_738_627_200), // 2025-02-04
+    (137, 1_748_304_000), // 2025-05-27
+    (139, 1_754_352_000), // 2025-08-05
+    (142, 1_761_609_600), // 2025-10-28
+];
+/// Chrome ships a major version every four weeks, past the last known release
+const CHROME_CADENCE_SECS: i64 = 28 * 86_400;
+/// Major versions a User-Agent can lag behind the current Chrome and still get past bot checks
+const CHROME_LAG: u32 = 3;
+/// When this binary was built, in seconds since the Unix epoch
+const BUILD_TIME: &str = env!("S7R_BUILD_TIME");
+
 /// regex pattern for a subdomain
 /// It ensures proper domain name format:
 ///  1. One or more labels separated by dots
@@ -42,6 +92,178 @@ const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) Appl
 const SUBDOMAIN_RE_STR: &str =
     r"(?:[[:alnum:]](?:[[:alnum:]-]*[[:alnum:]])?)(?:\.[[:alnum:]](?:[[:alnum:]-]*[[:alnum:]])?)*";
 
+/// The labels in front of the domain of every valid result
+static SUBDOMAIN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!("^{SUBDOMAIN_RE_STR}$")).expect("subdomain regex should compile")
+});
+
+/// Query parameters whose values are secrets, or identify the user, and must never be logged
+const SENSITIVE_PARAMS: [&str; 7] = [
+    "email",
+    "key",
+    "apikey",
+    "api_key",
+    "access_token",
+    "token",
+    "secret",
+];
+
+/// Replace the values of sensitive query parameters in a URL with `REDACTED`
+///
+/// Inputs that aren't URLs (e.g. plain search queries) are returned unchanged.
+fn redact(input: &str) -> Cow<'_, str> {
+    let Ok(mut url) = Url::parse(input) else {
+        return Cow::Borrowed(input);
+    };
+
+    let is_sensitive = |k: &str| SENSITIVE_PARAMS.iter().any(|p| k.eq_ignore_ascii_case(p));
+    if !url.query_pairs().any(|(k, _)| is_sensitive(&k)) {
+        return Cow::Borrowed(input);
+    }
+
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| {
+            let v = if is_sensitive(&k) {
+                "REDACTED".into()
+            } else {
+                v.into_owned()
+            };
+            (k.into_owned(), v)
+        })
+        .collect();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+
+    Cow::Owned(url.into())
+}
+
+/// Extract the major version from a Chrome User-Agent, e.g. `133` from `... Chrome/133.0.0.0 ...`
+fn chrome_version(user_agent: &str) -> Option<u32> {
+    let (_, rest) = user_agent.split_once("Chrome/")?;
+    rest.split('.').next()?.parse().ok()
+}
+
+fn is_stale(user_agent: &str, known_good: u32) -> bool {
+    chrome_version(user_agent).is_some_and(|v| v < known_good)
+}
+
+/// Oldest Chrome major version still getting past the engines' bot checks at `at`,
+/// in seconds since the Unix epoch
+///
+/// Versions past the last known release are extrapolated from Chrome's release cadence.
+fn known_good_chrome(at: i64) -> u32 {
+    let (first, _) = CHROME_RELEASES[0];
+    let current = CHROME_RELEASES
+        .iter()
+        .rev()
+        .find(|(_, released)| *released <= at)
+        .map_or(first, |&(version, released)| {
+            let later = (at - released) / CHROME_CADENCE_SECS;
+            version + u32::try_from(later).unwrap_or(u32::MAX - version)
+        });
+    current.saturating_sub(CHROME_LAG)
+}
+
+/// Chrome version of `user_agent` if it was already stale at `at`
+fn stale_at(user_agent: &str, at: i64) -> Option<u32> {
+    if is_stale(user_agent, known_good_chrome(at)) {
+        chrome_version(user_agent)
+    } else {
+        None
+    }
+}
+
+/// Return the Chrome version of the default User-Agent if it was already stale
+/// when this binary was built
+#[must_use]
+pub fn stale_user_agent() -> Option<u32> {
+    stale_at(DEFAULT_USER_AGENT, BUILD_TIME.parse().unwrap_or_default())
+}
+
+/// Charset label from a `Content-Type` header, e.g. `iso-8859-1` from `text/html; charset="ISO-8859-1"`
+fn charset(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Decode a response body using its declared charset
+///
+/// Bodies without a known charset are decoded as UTF-8. If that fails, including when a body
+/// is mislabeled as UTF-8, they're decoded as Windows-1252 (a superset of Latin-1) instead,
+/// which never fails and keeps accented characters intact rather than replacing them.
+fn decode(bytes: &[u8], charset: Option<&str>) -> String {
+    let encoding = charset
+        .and_then(|c| Encoding::for_label(c.as_bytes()))
+        .unwrap_or(UTF_8);
+
+    let (text, actual, malformed) = encoding.decode(bytes);
+    if malformed && actual == UTF_8 {
+        let (text, _, _) = WINDOWS_1252.decode(bytes);
+        return text.into_owned();
+    }
+
+    text.into_owned()
+}
+
+/// Read a response body, failing once it grows past `limit` bytes
+///
+/// A `Content-Length` over the limit fails right away, but it can be missing or wrong,
+/// so the limit is also enforced as the body streams in.
+async fn read_body(mut resp: Response, limit: usize) -> anyhow::Result<Vec<u8>> {
+    if let Some(len) = resp.content_length().filter(|&len| len > limit as u64) {
+        anyhow::bail!("body of {len} bytes is over the limit of {limit} bytes");
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        if body.len() + chunk.len() > limit {
+            anyhow::bail!("body is over the limit of {limit} bytes");
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body)
+}
+
+/// Start of every gzip stream
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Decompress a body that's gzipped without saying so
+///
+/// reqwest only gunzips bodies labeled with `Content-Encoding: gzip`, but crt.sh sometimes
+/// serves gzipped JSON without it. Neither JSON nor HTML can start with the gzip magic bytes,
+/// so other bodies, and those that fail to decompress, are returned as they are.
+/// So are those that decompress to more than `limit` bytes, which keeps a gzip bomb
+/// from getting past the body size limit.
+fn gunzip(bytes: &[u8], limit: usize) -> Cow<'_, [u8]> {
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return Cow::Borrowed(bytes);
+    }
+
+    let mut out = Vec::with_capacity(bytes.len() * 4);
+    match GzDecoder::new(bytes)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut out)
+    {
+        Ok(n) if n > limit => {
+            debug!(limit, "decompressed body is over the limit");
+            Cow::Borrowed(bytes)
+        }
+        Ok(_) => {
+            debug!("decompressed an unlabeled gzip body");
+            Cow::Owned(out)
+        }
+        Err(e) => {
+            debug!(err = %e, "body starts like gzip but failed to decompress");
+            Cow::Borrowed(bytes)
+        }
+    }
+}
+
 pub fn defaults_headers() -> HeaderMap {
     let mut headers = HeaderMap::with_capacity(3);
 
@@ -55,9 +277,14 @@ pub fn defaults_headers() -> HeaderMap {
     );
     // advertises that this client understands US English.
     headers.insert(ACCEPT_LANGUAGE, HeaderValue::from_static("en-US,en;q=0.8"));
-    // advertises that this client can handle gzip-compressed responses.
+    // advertises that this client can handle compressed responses.
     // this is to potentially save bandwidth and speed up the response time.
-    headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("gzip"));
+    // `identity` is listed explicitly so servers that can't compress still have a safe fallback.
+    // every advertised encoding must also be enabled on the client so it gets decoded.
+    headers.insert(
+        ACCEPT_ENCODING,
+        HeaderValue::from_static("gzip, deflate, br, identity"),
+    );
 
     headers
 }
@@ -65,44 +292,311 @@ pub fn defaults_headers() -> HeaderMap {
 #[enum_dispatch(Extract, Search)]
 #[derive(EnumDiscriminants)]
 #[strum_discriminants(name(EngineChoice))]
-#[strum_discriminants(derive(VariantArray))]
+#[strum_discriminants(derive(Hash, PartialOrd, Ord, VariantArray))]
 #[strum_discriminants(derive(ValueEnum), clap(rename_all = "lower"))]
+#[strum_discriminants(derive(Serialize, Deserialize), serde(rename_all = "lowercase"))]
 pub enum Engine {
     AlienVault,
+    Axfr,
     Bing,
     CrtSh,
     DNSDumpster,
+    FacebookCT,
+    Fofa,
     Google,
     HackerTarget,
     RapidDNS,
+    SubdomainCenter,
     VirusTotal,
     Yahoo,
+    ZoomEye,
 }
 
 impl Engine {
-    pub fn from_iter<I>(iter: I, domain: &str) -> Vec<Engine>
+    /// Build the engine for `choice`, failing before any network work if `domain` is invalid
+    ///
+    /// Every engine gets the domain as returned by [`normalize_domain`].
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidDomain`] if `domain` isn't a valid domain name
+    pub fn new(choice: EngineChoice, domain: &str) -> Result<Engine, Error> {
+        let domain = normalize_domain(domain)?;
+        Ok(Self::with_domain(choice, domain))
+    }
+
+    /// Build the engines for `iter`, validating `domain` once for all of them
+    ///
+    /// # Errors
+    ///
+    /// [`Error::InvalidDomain`] if `domain` isn't a valid domain name
+    pub fn from_iter<I>(iter: I, domain: &str) -> Result<Vec<Engine>, Error>
     where
         I: IntoIterator<Item = EngineChoice>,
     {
-        iter.into_iter()
-            .map(|c| match c {
-                EngineChoice::AlienVault => AlienVault::new(domain).into(),
-                EngineChoice::Bing => Bing::new(domain).into(),
-                EngineChoice::CrtSh => CrtSh::new(domain).into(),
-                EngineChoice::DNSDumpster => DNSDumpster::new(domain).into(),
-                EngineChoice::Google => Google::new(domain).into(),
-                EngineChoice::HackerTarget => HackerTarget::new(domain).into(),
-                EngineChoice::RapidDNS => RapidDNS::new(domain).into(),
-                EngineChoice::VirusTotal => VirusTotal::new(domain).into(),
-                EngineChoice::Yahoo => Yahoo::new(domain).into(),
-            })
+        let domain = normalize_domain(domain)?;
+        Ok(iter
+            .into_iter()
+            .map(|c| Self::with_domain(c, domain.as_str()))
+            .collect())
+    }
+
+    /// Settings of the engine for `choice`, without building it for a domain
+    #[must_use]
+    pub fn settings_for(choice: EngineChoice) -> Settings {
+        match choice {
+            EngineChoice::AlienVault => alienvault::SETTINGS,
+            EngineChoice::Axfr => axfr::SETTINGS,
+            EngineChoice::Bing => bing::SETTINGS,
+            EngineChoice::CrtSh => crtsh::SETTINGS,
+            EngineChoice::DNSDumpster => dnsdumpster::SETTINGS,
+            EngineChoice::FacebookCT => facebookct::SETTINGS,
+            EngineChoice::Fofa => fofa::SETTINGS,
+            EngineChoice::Google => google::SETTINGS,
+            EngineChoice::HackerTarget => hackertarget::SETTINGS,
+            EngineChoice::RapidDNS => rapiddns::SETTINGS,
+            EngineChoice::SubdomainCenter => subdomaincenter::SETTINGS,
+            EngineChoice::VirusTotal => virustotal::SETTINGS,
+            EngineChoice::Yahoo => yahoo::SETTINGS,
+            EngineChoice::ZoomEye => zoomeye::SETTINGS,
+        }
+    }
+
+    /// Build the engine for `choice` from a domain already normalized
+    fn with_domain(choice: EngineChoice, domain: impl Into<String>) -> Engine {
+        let domain = domain.into();
+        match choice {
+            EngineChoice::AlienVault => AlienVault::new(domain).into(),
+            EngineChoice::Axfr => Axfr::new(domain).into(),
+            EngineChoice::Bing => Bing::new(domain).into(),
+            EngineChoice::CrtSh => CrtSh::new(domain).into(),
+            EngineChoice::DNSDumpster => DNSDumpster::new(domain).into(),
+            EngineChoice::FacebookCT => FacebookCT::new(domain).into(),
+            EngineChoice::Fofa => Fofa::new(domain).into(),
+            EngineChoice::Google => Google::new(domain).into(),
+            EngineChoice::HackerTarget => HackerTarget::new(domain).into(),
+            EngineChoice::RapidDNS => RapidDNS::new(domain).into(),
+            EngineChoice::SubdomainCenter => SubdomainCenter::new(domain).into(),
+            EngineChoice::VirusTotal => VirusTotal::new(domain).into(),
+            EngineChoice::Yahoo => Yahoo::new(domain).into(),
+            EngineChoice::ZoomEye => ZoomEye::new(domain).into(),
+        }
+    }
+}
+
+impl fmt::Display for EngineChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl EngineChoice {
+    /// Name the engine reports its results under, e.g. "CrtSh"
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        Engine::settings_for(self).name
+    }
+
+    /// Whether the engine only queries third-party data sources, never the target itself
+    #[must_use]
+    pub fn is_passive(self) -> bool {
+        Engine::settings_for(self).passive
+    }
+
+    /// How early the engine starts with `--engines-weighted`, higher first
+    #[must_use]
+    pub fn priority(self) -> u8 {
+        Engine::settings_for(self).priority
+    }
+
+    /// Environment variables holding credentials the engine needs, but that aren't set
+    #[must_use]
+    pub fn missing_env(self) -> Vec<&'static str> {
+        let required = match self {
+            EngineChoice::FacebookCT => facebookct::REQUIRED_ENV,
+            EngineChoice::Fofa => fofa::REQUIRED_ENV,
+            EngineChoice::ZoomEye => zoomeye::REQUIRED_ENV,
+            _ => &[],
+        };
+        required
+            .iter()
+            .copied()
+            .filter(|v| std::env::var(v).is_err())
             .collect()
     }
 }
 
+/// Validate `domain` and bring it to the form every engine expects
+///
+/// The result is lowercase, has no trailing dot, and internationalized names are converted to
+/// punycode. It only holds letters, digits, hyphens and dots, so escaping its dots is enough
+/// to embed it in a regex.
+///
+/// # Errors
+///
+/// [`Error::InvalidDomain`] if `domain` isn't a valid domain name
+pub fn normalize_domain(domain: &str) -> Result<String, Error> {
+    let domain = domain.trim().trim_end_matches('.');
+    let Ok(Host::Domain(domain)) = Host::parse(domain) else {
+        return Err(Error::InvalidDomain);
+    };
+
+    let valid = domain.contains('.')
+        && domain.split('.').all(|l| {
+            !l.is_empty()
+                && !l.starts_with('-')
+                && !l.ends_with('-')
+                && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if valid {
+        Ok(domain)
+    } else {
+        Err(Error::InvalidDomain)
+    }
+}
+
 #[enum_dispatch]
 pub trait Extract {
     fn extract(&mut self, input: &str) -> HashSet<String>;
+
+    /// Engine-specific cleanup applied to every batch of extracted results
+    fn clean(&self, raw: HashSet<String>) -> HashSet<String> {
+        raw
+    }
+
+    /// Longest input passed to `extract` in bytes, longer bodies are truncated first
+    ///
+    /// `None` for no bound, e.g. for JSON that's useless unless parsed whole.
+    fn max_input_len(&self) -> Option<usize> {
+        None
+    }
+
+    /// When each subdomain extracted so far was first seen by the source,
+    /// empty for engines whose results aren't dated
+    fn first_seen(&self) -> FirstSeen {
+        FirstSeen::new()
+    }
+
+    /// Domain every result must belong to, `None` to keep results unchecked
+    fn domain(&self) -> Option<&str> {
+        None
+    }
+
+    /// Report wildcard names such as `*.app.example.com` as they are, besides the host they cover
+    ///
+    /// Only engines whose sources list wildcards override it, the default ignores it.
+    fn set_keep_wildcards(&mut self, _keep: bool) {}
+}
+
+/// Whether `name` is `domain` itself or a well-formed subdomain of it
+///
+/// The host is checked rather than `name` as is, so results kept with their scheme or port
+/// (e.g. with `--no-clean`) aren't dropped for it. A wildcard such as `*.app.example.com`
+/// is valid when the host it covers is.
+#[must_use]
+pub fn is_valid_subdomain(name: &str, domain: &str) -> bool {
+    let host = sources::canonical(normalize::clean(name));
+    let host = host.strip_prefix("*.").unwrap_or(&host);
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .and_then(|rest| rest.strip_suffix('.'))
+            .is_some_and(|labels| SUBDOMAIN_RE.is_match(labels))
+}
+
+/// Parse the subdomains of `domain` out of a response `body` from `engine`,
+/// for responses captured outside of this crate
+///
+/// The body goes through the same extraction and cleanup as during enumeration,
+/// and fails the same way if `domain` is invalid.
+///
+/// # Errors
+///
+/// [`Error::InvalidDomain`] if `domain` isn't a valid domain name
+///
+/// # Examples
+///
+/// ```
+/// use sublist3r_rs::enumerate::{EngineChoice, parse_subdomains};
+///
+/// let body = r#"[{"name_value": "app.example.com\n*.api.example.com"}]"#;
+/// let found = parse_subdomains(body, EngineChoice::CrtSh, "example.com").unwrap();
+/// assert_eq!(found.len(), 2);
+/// assert!(found.contains("api.example.com"));
+/// ```
+///
+/// ```
+/// use sublist3r_rs::enumerate::{EngineChoice, parse_subdomains};
+///
+/// let body = "app.example.com,192.0.2.1\nmail.example.com,192.0.2.2";
+/// let found = parse_subdomains(body, EngineChoice::HackerTarget, "example.com").unwrap();
+/// assert!(found.contains("app.example.com") && found.contains("mail.example.com"));
+///
+/// assert!(parse_subdomains(body, EngineChoice::HackerTarget, "not a domain").is_err());
+/// ```
+pub fn parse_subdomains(
+    body: &str,
+    engine: EngineChoice,
+    domain: &str,
+) -> Result<HashSet<String>, Error> {
+    let mut engine = Engine::new(engine, domain)?;
+    let raw = engine.extract(body);
+    Ok(validate(&engine, engine.clean(raw)))
+}
+
+/// Seconds to wait after a rate limited response, as asked by its `Retry-After` within bounds
+///
+/// Only a number of seconds is understood, an HTTP date falls back to the default wait.
+fn rate_limit_backoff(resp: &Response) -> u64 {
+    resp.headers()
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(RATE_LIMIT_BACKOFF)
+        .min(MAX_RATE_LIMIT_BACKOFF)
+}
+
+/// Drop the results that aren't subdomains of the engine's domain, such as malformed captures
+fn validate<E: Extract>(engine: &E, mut names: HashSet<String>) -> HashSet<String> {
+    if let Some(domain) = engine.domain() {
+        let before = names.len();
+        names.retain(|n| is_valid_subdomain(n, domain));
+        if names.len() < before {
+            debug!(dropped = before - names.len(), "dropped malformed results");
+        }
+    }
+    names
+}
+
+/// Up to `limit` of `subdomains` to exclude from a search query, the same ones whatever the set
+///
+/// Names closest to the domain come first, they're the ones results most often repeat,
+/// and ties are broken alphabetically.
+fn exclusions<S: BuildHasher>(subdomains: &HashSet<String, S>, limit: usize) -> Vec<&str> {
+    let mut names: Vec<&str> = subdomains.iter().map(String::as_str).collect();
+    names.sort_unstable_by(|a, b| {
+        let depth = |s: &str| s.matches('.').count();
+        depth(a).cmp(&depth(b)).then(a.cmp(b))
+    });
+    names.truncate(limit);
+    names
+}
+
+/// Earliest time each subdomain was seen, in seconds since the Unix epoch
+pub type FirstSeen = HashMap<String, i64>;
+
+/// Subdomains found so far by every engine of a run
+pub type Found = Arc<Mutex<HashSet<String>>>;
+
+/// How an engine moves from one request to the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaginationKind {
+    /// Numbered pages of the same query, `search` is given the page to fetch
+    OffsetPages,
+    /// The engine follows a cursor from the last response, built into its next query
+    Cursor,
+    /// Every result comes in a single response
+    Single,
 }
 
 /// Settings for a Search Engine
@@ -110,9 +604,59 @@ pub struct Settings {
     name: &'static str,
     base_url: &'static str,
     user_agent: &'static str,
+    /// Maximum number of requests, a page or a cursor hop each
+    max_rounds: usize,
+    pagination: PaginationKind,
+    /// Whether the engine only queries third-party data sources, never the target itself
+    passive: bool,
+    /// How early the engine starts when not all of them run at once, higher first,
+    /// roughly how much it usually finds
+    priority: u8,
+    /// Results asked for per page, 0 for engines whose page size can't be changed
+    per_page: usize,
+    /// Largest page size the engine is known to serve, `--per-page` is clamped to it
+    max_per_page: usize,
+    /// Backoff after the first failed search
+    backoff_base_secs: u64,
+    /// Multiplier applied to the backoff after each failed search
+    backoff_factor: u64,
+    /// Base delay between pages, to avoid being blocked
+    delay_ms: u64,
+}
+
+/// Metadata about a compiled-in engine
+#[derive(Debug, Serialize)]
+pub struct EngineInfo {
+    name: &'static str,
     max_rounds: usize,
+    passive: bool,
+}
+
+impl From<&Engine> for EngineInfo {
+    fn from(engine: &Engine) -> Self {
+        engine.settings().into()
+    }
+}
+
+impl From<Settings> for EngineInfo {
+    fn from(settings: Settings) -> Self {
+        let Settings {
+            name,
+            max_rounds,
+            passive,
+            ..
+        } = settings;
+
+        Self {
+            name,
+            max_rounds,
+            passive,
+        }
+    }
 }
 
+// engines are driven through the concrete `Engine` type, so no `Send` bound on the trait is needed
+#[allow(async_fn_in_trait)]
 #[enum_dispatch]
 pub trait Search {
     fn settings(&self) -> Settings;
@@ -125,25 +669,104 @@ pub trait Search {
     /// # Returns
     /// * `Some(query)` - The next search query to execute
     /// * `None` - No more queries to execute, enumeration should stop
-    fn next_query(&self, subdomains: &HashSet<String>) -> Option<Cow<'_, str>>;
+    fn next_query<S: BuildHasher>(&self, subdomains: &HashSet<String, S>) -> Option<Cow<'_, str>>;
 
     /// Search for a query on a page
+    ///
+    /// `headers` are added to the search request last, replacing the engine's own on conflict.
     async fn search(
         &self,
         client: Client,
         query: &str,
         page: usize,
+        headers: &HeaderMap,
     ) -> Result<Response, reqwest::Error>;
 
-    /// Delay between pages to avoid being blocked  
-    async fn delay(&self) {
-        let dur = Duration::from_millis(200);
-        tokio::time::sleep(dur).await;
+    /// Called after each page with the number of subdomains it found that weren't known yet
+    ///
+    /// Engines that should stop once pages run dry keep track of it, the default ignores it.
+    fn page_done(&mut self, _new: usize) {}
+
+    /// Ask for `per_page` results per page from now on, already clamped to `max_per_page`
+    ///
+    /// Only engines with an adjustable page size override it, the default ignores it.
+    fn set_per_page(&mut self, _per_page: usize) {}
+
+    /// Names found without searching over HTTP, e.g. over DNS
+    ///
+    /// Engines that return `Some` are never searched, the default returns `None`.
+    async fn lookup(&self) -> Option<HashSet<String>> {
+        None
+    }
+
+    /// Check that the engine is reachable and serving results
+    ///
+    /// The default runs a single search for the first query and fails on
+    /// network errors, error statuses and block pages.
+    async fn healthcheck(&self, client: Client) -> Result<(), String> {
+        let query = self
+            .next_query(&HashSet::new())
+            .ok_or_else(|| "no query to run".to_owned())?;
+
+        let body = self
+            .search(client, &query, 0, &HeaderMap::new())
+            .await
+            .and_then(Response::error_for_status)
+            .map_err(|e| e.to_string())?
+            .text()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if self.is_blocked(&body) {
+            return Err("blocked".to_owned());
+        }
+
+        Ok(())
+    }
+
+    /// Whether the response body is a CAPTCHA or block page rather than results
+    ///
+    /// Some engines serve these with a 200 status, so retrying only burns the retry budget.
+    fn is_blocked(&self, _body: &str) -> bool {
+        false
     }
 }
 
-pub struct Enumerator<E> {
+/// Runs an engine page after page, collecting results into a set hashed with `S`
+pub struct Enumerator<E, S = RandomState> {
     engine: E,
+    /// Global cap on the number of rounds, never raises the engine's own `max_rounds`
+    max_rounds_cap: Option<usize>,
+    /// Overrides the engine's `backoff_base_secs`
+    backoff_base_secs: Option<u64>,
+    /// Overrides the engine's `backoff_factor`
+    backoff_factor: Option<u64>,
+    /// Replaces the engine's own delay between pages
+    delay: Option<Duration>,
+    /// How the delay between pages changes from page to page
+    delay_kind: DelayKind,
+    /// Source of the jitter added to the delay between pages
+    rng: fastrand::Rng,
+    /// Responses with a larger body, in bytes, count as failed searches
+    max_body_size: usize,
+    /// Failed searches in a row, before any success, after which the engine stops
+    circuit_breaker: usize,
+    /// Where the engine's first-seen times are merged once it's done
+    first_seen: Option<Arc<Mutex<FirstSeen>>>,
+    /// Extra headers sent with every search
+    headers: HeaderMap,
+    /// Limits requests to the engine's host, shared with the other engines
+    host_limiter: Option<HostLimiter>,
+    /// Spaces out requests across every engine
+    pacer: Option<Pacer>,
+    /// Bodies of earlier searches, served instead of sending the same request again
+    cache: Option<ResponseCache>,
+    /// Told about the engine's progress as it goes
+    progress: Option<ProgressCallback>,
+    /// Results of every engine, excluded from queries and added to as pages come in
+    shared: Option<Found>,
+    /// Hasher of the result set
+    hasher: S,
 }
 
 impl<E> Enumerator<E>
@@ -151,112 +774,1671 @@ where
     E: Search + Extract,
 {
     pub fn new(engine: E) -> Self {
-        Self { engine }
+        Self {
+            engine,
+            max_rounds_cap: None,
+            backoff_base_secs: None,
+            backoff_factor: None,
+            delay: None,
+            delay_kind: DelayKind::default(),
+            rng: fastrand::Rng::new(),
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            circuit_breaker: DEFAULT_CIRCUIT_BREAKER,
+            first_seen: None,
+            headers: HeaderMap::new(),
+            host_limiter: None,
+            pacer: None,
+            cache: None,
+            progress: None,
+            shared: None,
+            hasher: RandomState::new(),
+        }
     }
 }
 
+impl<E, S> Enumerator<E, S>
+where
+    E: Search + Extract,
+    S: BuildHasher,
+{
+    /// Collect results into a set hashed with `hasher`
+    ///
+    /// A fixed hasher such as `BuildHasherDefault<DefaultHasher>` makes the iteration order of
+    /// results, and so the queries built from them, the same on every run, which keeps tests
+    /// and benchmarks reproducible. Results come from third parties though, so a fixed hasher
+    /// lets a malicious source send names that all collide and slow the set down to a crawl
+    /// (HashDoS). The default `RandomState` is seeded per process and isn't open to this.
+    pub fn with_hasher<T: BuildHasher>(self, hasher: T) -> Enumerator<E, T> {
+        Enumerator {
+            engine: self.engine,
+            max_rounds_cap: self.max_rounds_cap,
+            backoff_base_secs: self.backoff_base_secs,
+            backoff_factor: self.backoff_factor,
+            delay: self.delay,
+            delay_kind: self.delay_kind,
+            rng: self.rng,
+            max_body_size: self.max_body_size,
+            circuit_breaker: self.circuit_breaker,
+            first_seen: self.first_seen,
+            headers: self.headers,
+            host_limiter: self.host_limiter,
+            pacer: self.pacer,
+            cache: self.cache,
+            progress: self.progress,
+            shared: self.shared,
+            hasher,
+        }
+    }
+
+    /// Lower the engine's `max_rounds` to at most `cap`
+    #[must_use]
+    pub fn with_max_rounds(mut self, cap: Option<usize>) -> Self {
+        self.max_rounds_cap = cap;
+        self
+    }
+
+    /// Override the engine's backoff base and factor
+    ///
+    /// The base is clamped below [`MAX_BACKOFF`], from which the engine would give up
+    /// before its first search.
+    #[must_use]
+    pub fn with_backoff(mut self, base_secs: Option<u64>, factor: Option<u64>) -> Self {
+        let name = self.engine.settings().name;
+        self.backoff_base_secs = base_secs.map(|base| {
+            let clamped = base.min(MAX_BACKOFF - 1);
+            if clamped != base {
+                warn!(
+                    engine = name,
+                    base,
+                    max = MAX_BACKOFF - 1,
+                    "backoff base too long, clamping it"
+                );
+            }
+            clamped
+        });
+        self.backoff_factor = factor;
+        self
+    }
+
+    /// Override the engine's delay between pages
+    #[must_use]
+    pub fn with_delay(mut self, delay: Option<Duration>) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Pick how the delay between pages changes, `DelayKind::Fixed` otherwise
+    #[must_use]
+    pub fn with_delay_strategy(mut self, kind: Option<DelayKind>) -> Self {
+        if let Some(kind) = kind {
+            self.delay_kind = kind;
+        }
+        self
+    }
+
+    /// Seed the jitter so delays are reproducible, otherwise it's seeded from entropy
+    #[must_use]
+    pub fn with_jitter_seed(mut self, seed: Option<u64>) -> Self {
+        if let Some(seed) = seed {
+            self.rng = fastrand::Rng::with_seed(seed);
+        }
+        self
+    }
+
+    /// Override the largest response body read, `DEFAULT_MAX_BODY_SIZE` otherwise
+    #[must_use]
+    pub fn with_max_body_size(mut self, bytes: Option<usize>) -> Self {
+        if let Some(bytes) = bytes {
+            self.max_body_size = bytes;
+        }
+        self
+    }
+
+    /// Stop after `failures` failed searches in a row if none succeeded, `DEFAULT_CIRCUIT_BREAKER` otherwise
+    #[must_use]
+    pub fn with_circuit_breaker(mut self, failures: Option<usize>) -> Self {
+        if let Some(failures) = failures {
+            self.circuit_breaker = failures;
+        }
+        self
+    }
+
+    /// Merge the engine's first-seen times into `sink` once it's done, keeping the earliest
+    #[must_use]
+    pub fn with_first_seen(mut self, sink: Option<Arc<Mutex<FirstSeen>>>) -> Self {
+        self.first_seen = sink;
+        self
+    }
+
+    /// Send `headers` with every search, on top of the engine's own
+    #[must_use]
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Wait for `limiter` before each search, so requests to one host don't overlap
+    #[must_use]
+    pub fn with_host_limiter(mut self, limiter: Option<HostLimiter>) -> Self {
+        self.host_limiter = limiter;
+        self
+    }
+
+    /// Wait for `pacer` before each search, so requests of all engines are spaced out
+    #[must_use]
+    pub fn with_pacer(mut self, pacer: Option<Pacer>) -> Self {
+        self.pacer = pacer;
+        self
+    }
+
+    /// Look up each search in `cache` before sending it, and keep its body there once read
+    #[must_use]
+    pub fn with_cache(mut self, cache: Option<ResponseCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Override the engine's page size, clamped to the largest it's known to serve
+    #[must_use]
+    pub fn with_per_page(mut self, per_page: Option<usize>) -> Self {
+        let Some(per_page) = per_page else {
+            return self;
+        };
+        let Settings {
+            name, max_per_page, ..
+        } = self.engine.settings();
+        if max_per_page == 0 {
+            warn!(engine = name, "the engine's page size can't be changed");
+            return self;
+        }
+
+        let clamped = per_page.clamp(1, max_per_page);
+        if clamped != per_page {
+            warn!(
+                engine = name,
+                per_page,
+                max = max_per_page,
+                "page size out of range, clamping it"
+            );
+        }
+        self.engine.set_per_page(clamped);
+        self
+    }
+
+    /// Keep the wildcard names the engine finds, see [`Extract::set_keep_wildcards`]
+    #[must_use]
+    pub fn with_keep_wildcards(mut self, keep: bool) -> Self {
+        self.engine.set_keep_wildcards(keep);
+        self
+    }
+
+    /// Report the engine's start, pages, new subdomains and end to `callback`
+    #[must_use]
+    pub fn with_progress(mut self, callback: Option<ProgressCallback>) -> Self {
+        self.progress = callback;
+        self
+    }
+
+    /// Share results with the other engines through `found`
+    ///
+    /// Queries then exclude what any engine found so far, not only this one, so search engines
+    /// don't spend pages on subdomains another engine already returned.
+    #[must_use]
+    pub fn with_shared(mut self, found: Option<Found>) -> Self {
+        self.shared = found;
+        self
+    }
+
+    /// The delay strategy around the engine's delay, or its override
+    fn delay_strategy(&mut self) -> Box<dyn DelayStrategy + Send> {
+        let base = self
+            .delay
+            .unwrap_or(Duration::from_millis(self.engine.settings().delay_ms));
+        self.delay_kind.build(base, self.rng.fork())
+    }
+
+    /// The engine's `max_rounds`, lowered by the global cap if any
+    pub fn max_rounds(&self) -> usize {
+        let max_rounds = self.engine.settings().max_rounds;
+        self.max_rounds_cap
+            .map_or(max_rounds, |cap| max_rounds.min(cap))
+    }
+}
+
+/// Run a single engine to completion and return the subdomains it found
+///
+/// A shortcut for [`Enumerator::new`] followed by [`Enumerator::enumerate`],
+/// an engine that never gets a response finds nothing.
+///
+/// ```no_run
+/// use reqwest::Client;
+/// use sublist3r_rs::enumerate::crtsh::CrtSh;
+/// use sublist3r_rs::enumerate::{Engine, enumerate_one};
+///
+/// # async fn example() {
+/// let engine = Engine::from(CrtSh::new("example.com"));
+/// let found = enumerate_one(engine, Client::new()).await;
+/// for sub in found {
+///     println!("{sub}");
+/// }
+/// # }
+/// ```
+pub async fn enumerate_one(engine: Engine, client: Client) -> HashSet<String> {
+    Enumerator::new(engine)
+        .enumerate(client)
+        .await
+        .unwrap_or_default()
+}
+
+/// Rough number of subdomains a single engine finds, used to pre-size result sets
+/// so they don't rehash repeatedly while growing
+pub const EXPECTED_PER_ENGINE: usize = 128;
+
 /// Maximum number of retries, give up after this number of retries
 const MAX_RETRIES: u8 = 5;
+/// Failed searches in a row, without a single success, after which an engine is given up on
+///
+/// Until a search succeeds, failures don't use up retries and the backoff stays below
+/// [`MAX_BACKOFF`], so this is what decides when to stop.
+pub const DEFAULT_CIRCUIT_BREAKER: usize = 5;
 /// Maximum backoff time, give up after backoff reaches this value
-const MAX_BACKOFF: u8 = 16;
+pub const MAX_BACKOFF: u64 = 16;
+/// Seconds to wait after a rate limited search without a `Retry-After`
+const RATE_LIMIT_BACKOFF: u64 = 10;
+/// Longest wait after a rate limited search, whatever its `Retry-After` asks for
+const MAX_RATE_LIMIT_BACKOFF: u64 = 60;
+/// Largest response body read by default, in bytes
+pub const DEFAULT_MAX_BODY_SIZE: usize = 50 * 1024 * 1024;
+/// Longest input matched by regex engines, in bytes
+///
+/// Scanning a multi-megabyte page holds a worker thread for a while, and a real results page
+/// is far smaller than this, anything larger is rarely worth the time.
+pub const MAX_REGEX_INPUT_LEN: usize = 4 * 1024 * 1024;
 
-impl<E> Enumerator<E>
+impl<E, S> Enumerator<E, S>
 where
     E: Search + Extract,
+    S: BuildHasher + Clone,
 {
+    pub fn name(&self) -> &'static str {
+        self.engine.settings().name
+    }
+
+    /// Check that the engine is reachable and serving results
+    ///
+    /// # Errors
+    ///
+    /// Why the engine isn't working, e.g. a network error or a block page
+    pub async fn healthcheck(&self, client: Client) -> Result<(), String> {
+        self.engine.healthcheck(client).await
+    }
+
+    /// The first query the engine would send, with secrets redacted, `None` if it would skip
+    ///
+    /// Queries that aren't URLs themselves are prefixed with the URL they're sent to, if any.
+    pub fn first_query(&self) -> Option<String> {
+        let empty = HashSet::with_hasher(self.hasher.clone());
+        let query = self.engine.next_query(&empty)?;
+        let query = redact(&query);
+
+        let base_url = self.engine.settings().base_url;
+        if base_url.is_empty() || Url::parse(&query).is_ok_and(|u| u.has_host()) {
+            Some(query.into_owned())
+        } else {
+            Some(format!("{base_url} {query}"))
+        }
+    }
+
     pub fn print_banner(&self) {
         println!(
             "{} {}..",
-            "[-] Searching now in".green(),
-            self.engine.settings().name.green()
+            "[-] Searching now in".if_supports_color(Stream::Stdout, |t| t.green()),
+            self.engine
+                .settings()
+                .name
+                .if_supports_color(Stream::Stdout, |t| t.green())
         );
     }
 
+    /// Search page after page and collect the subdomains found
+    ///
+    /// Returns `None` if not a single search got a successful response.
     #[tracing::instrument(skip_all, fields(NAME))]
-    pub async fn enumerate(mut self, client: Client) -> HashSet<String> {
+    pub async fn enumerate(mut self, client: Client) -> Option<HashSet<String, S>> {
+        let mut reached = false;
         let mut rounds = 0;
         let mut retries = 0;
         let mut page = 0;
-        let mut backoff_secs = 1;
         let mut found = 0;
-        let mut subdomains = HashSet::new();
+        // Failed searches since the last successful one
+        let mut failures = 0;
+        // Time spent sleeping between failed searches
+        let mut total_backoff_secs: u64 = 0;
+        let mut subdomains =
+            HashSet::with_capacity_and_hasher(EXPECTED_PER_ENGINE, self.hasher.clone());
 
         #[allow(non_snake_case)]
         let Settings {
             name: NAME,
-            max_rounds: MAX_ROUNDS,
+            base_url,
+            pagination,
+            backoff_base_secs,
+            backoff_factor,
             ..
         } = self.engine.settings();
+        #[allow(non_snake_case)]
+        let MAX_ROUNDS = self.max_rounds();
+
+        let mut backoff_secs = self.backoff_base_secs.unwrap_or(backoff_base_secs);
+        let backoff_factor = self.backoff_factor.unwrap_or(backoff_factor);
+        let mut delay = self.delay_strategy();
+        let done = |rounds, retries, backoff_secs| {
+            rounds >= MAX_ROUNDS || retries >= MAX_RETRIES || backoff_secs >= MAX_BACKOFF
+        };
 
         // Record the name as part of the current span.
         tracing::Span::current().record("NAME", NAME);
+        self.emit(|| ProgressEvent::EngineStarted { engine: NAME });
+
+        if let Some(raw) = self.engine.lookup().await {
+            for name in validate(&self.engine, self.engine.clean(raw)) {
+                self.emit(|| ProgressEvent::SubdomainFound {
+                    engine: NAME,
+                    name: name.clone(),
+                });
+                subdomains.insert(name);
+            }
+            if let Some(shared) = &self.shared {
+                shared.lock().unwrap().extend(subdomains.iter().cloned());
+            }
+            info!(found = subdomains.len(), "completed");
+            self.emit(|| ProgressEvent::EngineCompleted {
+                engine: NAME,
+                found: subdomains.len(),
+            });
+            return Some(subdomains);
+        }
 
         loop {
             trace!(page, found, retries, "searching");
-            if rounds >= MAX_ROUNDS || retries >= MAX_RETRIES || backoff_secs >= MAX_BACKOFF {
-                info!(retries, rounds, stop = false, "completed");
+            if done(rounds, retries, backoff_secs) {
+                info!(
+                    retries,
+                    rounds,
+                    total_backoff_secs,
+                    stop = false,
+                    "completed"
+                );
                 break;
             }
 
-            let Some(query) = self.engine.next_query(&subdomains) else {
-                info!(retries, rounds, stop = true, "completed");
+            let query = match &self.shared {
+                Some(shared) => {
+                    let mut known = subdomains.clone();
+                    known.extend(shared.lock().unwrap().iter().cloned());
+                    self.engine.next_query(&known)
+                }
+                None => self.engine.next_query(&subdomains),
+            };
+            let Some(query) = query else {
+                info!(
+                    retries,
+                    rounds,
+                    total_backoff_secs,
+                    stop = true,
+                    "completed"
+                );
                 break;
             };
 
-            // If the search fails, backoff and retry
-            // backoff time is doubled each time
-            let resp = match self
-                .engine
-                .search(client.clone(), &query, page)
-                .await
-                .and_then(Response::error_for_status)
-            {
-                Ok(r) => r,
-                Err(e) => {
-                    warn!(err = ?e, backoff = backoff_secs, "failed to search");
-                    tokio::time::sleep(Duration::from_secs(u64::from(backoff_secs))).await;
-                    retries += 1;
-                    backoff_secs *= 2;
-                    continue;
+            debug!(rounds, page, query = %redact(&query), "generated query");
+
+            let key = ResponseCache::key(base_url, &query, page);
+            // the request succeeded before, maybe for another engine
+            let body = if let Some(body) = self.cache.as_ref().and_then(|c| c.get(&key)) {
+                debug!(query = %redact(&query), page, "serving search from cache");
+                reached = true;
+                failures = 0;
+                body
+            } else {
+                // held until the body is read, the request isn't over before that
+                let permit = match &self.host_limiter {
+                    Some(limiter) => Some(limiter.acquire(base_url).await),
+                    None => None,
+                };
+                if let Some(pacer) = &self.pacer {
+                    pacer.wait().await;
                 }
-            };
 
-            info!(url = resp.url().to_string(), "searching");
+                // If the search fails, backoff and retry
+                // backoff time is multiplied by `backoff_factor` each time
+                let resp = match self
+                    .engine
+                    .search(client.clone(), &query, page, &self.headers)
+                    .await
+                {
+                    // Rate limits wait out the time asked for instead of doubling the backoff,
+                    // so they don't give up on the engine early, only retries run out
+                    Ok(r) if r.status() == StatusCode::TOO_MANY_REQUESTS => {
+                        drop(permit);
+                        let wait = rate_limit_backoff(&r);
+                        warn!(retries, backoff = wait, "rate limited");
+                        // the wait asked for stands, the strategy only slows the pages after it
+                        delay.blocked();
+                        tokio::time::sleep(Duration::from_secs(wait)).await;
+                        total_backoff_secs = total_backoff_secs.saturating_add(wait);
+                        retries += 1;
+                        continue;
+                    }
+                    resp => resp.and_then(Response::error_for_status),
+                };
+                // Other failures, e.g. server errors, back off with a doubling delay
+                let resp = match resp {
+                    Ok(r) => r,
+                    Err(e) => {
+                        drop(permit);
+                        failures += 1;
+                        // Unlike retries, which tolerate the odd failure, a source that never
+                        // answered is most likely down and not worth waiting for any longer
+                        if !reached && failures >= self.circuit_breaker {
+                            warn!(
+                                err = ?e,
+                                failures,
+                                total_backoff_secs,
+                                "every search failed, giving up on the engine"
+                            );
+                            break;
+                        }
+                        warn!(err = ?e, backoff = backoff_secs, "failed to search");
+                        tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+                        total_backoff_secs = total_backoff_secs.saturating_add(backoff_secs);
+                        backoff_secs = backoff_secs.saturating_mul(backoff_factor);
+                        if reached {
+                            retries += 1;
+                        } else {
+                            // the circuit breaker, not the retries, ends an engine that was never reached
+                            backoff_secs = backoff_secs.min(MAX_BACKOFF - 1);
+                        }
+                        continue;
+                    }
+                };
+
+                reached = true;
+                failures = 0;
+                info!(url = %redact(resp.url().as_str()), "searching");
 
-            let body = match resp.text().await {
-                Ok(b) => b,
-                Err(e) => {
-                    warn!(err = ?e, "failed to parse search results");
-                    retries += 1;
-                    continue;
+                let charset = resp
+                    .headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(charset)
+                    .map(str::to_owned);
+                let body = read_body(resp, self.max_body_size).await;
+                drop(permit);
+                let body = match body {
+                    Ok(b) => decode(&gunzip(&b, self.max_body_size), charset.as_deref()),
+                    Err(e) => {
+                        warn!(err = ?e, "failed to parse search results");
+                        retries += 1;
+                        continue;
+                    }
+                };
+                // a block page would keep being served to the retries
+                if let Some(cache) = self
+                    .cache
+                    .as_ref()
+                    .filter(|_| !body.trim().is_empty() && !self.engine.is_blocked(&body))
+                {
+                    cache.insert(key, body.clone());
                 }
+                body
             };
+            self.emit(|| ProgressEvent::PageFetched { engine: NAME, page });
+
+            if self.engine.is_blocked(&body) {
+                let Some(wait) = delay.blocked() else {
+                    warn!(retries, rounds, "blocked");
+                    break;
+                };
+                warn!(
+                    retries,
+                    rounds,
+                    wait_ms = wait.as_millis(),
+                    "blocked, slowing down"
+                );
+                tokio::time::sleep(wait).await;
+                retries += 1;
+                continue;
+            }
+            // Rate limited or blocked sources may answer with nothing at all instead of an error,
+            // which isn't a page without results, so it costs an extra retry
+            if body.trim().is_empty() {
+                warn!(retries, rounds, "empty response");
+                retries += 1;
+            }
 
             // Informs the executor that this task is about to block the thread
             // so any other tasks can be moved to a new worker thread
             tokio::task::block_in_place(|| {
-                subdomains.extend(self.engine.extract(&body));
+                let input = match self.engine.max_input_len() {
+                    Some(limit) if body.len() > limit => {
+                        warn!(
+                            len = body.len(),
+                            limit, "body too long, only extracting from its start"
+                        );
+                        &body[..body.floor_char_boundary(limit)]
+                    }
+                    _ => &body,
+                };
+                let started = Instant::now();
+                let raw = self.engine.extract(input);
+                debug!(
+                    len = input.len(),
+                    elapsed_ms = started.elapsed().as_millis(),
+                    "extracted"
+                );
+                for name in validate(&self.engine, self.engine.clean(raw)) {
+                    if !subdomains.contains(&name) {
+                        self.emit(|| ProgressEvent::SubdomainFound {
+                            engine: NAME,
+                            name: name.clone(),
+                        });
+                    }
+                    subdomains.insert(name);
+                }
             });
 
             // Adjust retry counter based on search results:
             // - If new subdomains found: Reward progress by reducing retry count (-2)
-            // - If no new findings: Move to next page and increment retry count (+1)
-            if found != subdomains.len() {
+            // - If no new findings: Increment retry count (+1)
+            let progressed = found != subdomains.len();
+            self.engine.page_done(subdomains.len() - found);
+            if progressed {
                 found = subdomains.len();
+                if let Some(shared) = &self.shared {
+                    shared.lock().unwrap().extend(subdomains.iter().cloned());
+                }
                 // Subtracts 2 and saturates at 0
                 // instead of underflowing if the result would be negative.
                 retries = retries.saturating_sub(2);
             } else {
-                page += 1;
                 retries += 1;
             }
+            rounds += 1;
 
-            // Delay after each page to avoid being blocked
-            self.engine.delay().await;
+            match pagination {
+                // Stay on the page while the query keeps changing with new findings,
+                // move to the next one once it stops yielding anything new
+                PaginationKind::OffsetPages if !progressed => page += 1,
+                // The engine already moved its cursor while extracting
+                PaginationKind::OffsetPages | PaginationKind::Cursor => {}
+                PaginationKind::Single => {
+                    info!(
+                        retries,
+                        rounds,
+                        total_backoff_secs,
+                        stop = true,
+                        "completed"
+                    );
+                    break;
+                }
+            }
 
-            rounds += 1;
+            // Delay between pages to avoid being blocked, there's nothing to wait for after the last
+            if !done(rounds, retries, backoff_secs) {
+                tokio::time::sleep(delay.next_delay()).await;
+            }
+        }
+
+        if let Some(sink) = &self.first_seen {
+            let mut sink = sink.lock().unwrap();
+            for (name, t) in self.engine.first_seen() {
+                sink.entry(name)
+                    .and_modify(|first| *first = (*first).min(t))
+                    .or_insert(t);
+            }
+        }
+
+        self.emit(|| ProgressEvent::EngineCompleted {
+            engine: NAME,
+            found: subdomains.len(),
+        });
+        reached.then_some(subdomains)
+    }
+
+    /// Send the event built by `event` to the progress callback, only building it if there's one
+    fn emit(&self, event: impl FnOnce() -> ProgressEvent) {
+        if let Some(callback) = &self.progress {
+            callback.emit(event());
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::hash::{BuildHasherDefault, DefaultHasher};
+    use std::io::Write;
+    use std::sync::atomic::Ordering;
+    use std::sync::{Arc, Mutex};
+
+    use enumerate_derive::ApiExtract;
+    use reqwest::header::COOKIE;
+    use rstest::rstest;
+    use tracing::Level;
+    use tracing_subscriber::fmt::MakeWriter;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::{header, method};
+
+    use super::crtsh::CrtSh;
+    use super::google::Google;
+    use super::mock::Mock;
+    use super::yahoo::Yahoo;
+    use super::*;
+
+    /// An in-memory sink for captured log lines
+    #[derive(Clone, Default)]
+    pub(crate) struct Logs(Arc<Mutex<Vec<u8>>>);
+
+    impl Logs {
+        pub(crate) fn contents(&self) -> String {
+            String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+        }
+    }
+
+    impl Write for Logs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl MakeWriter<'_> for Logs {
+        type Writer = Logs;
+
+        fn make_writer(&self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    /// RapidDNS style, one name per table cell
+    #[derive(Extract)]
+    #[extract(pattern = r"<td>(?<subdomain>[a-z.]*?\.{domain})</td>")]
+    struct Cells {
+        #[extract(domain)]
+        domain: String,
+    }
+
+    #[test]
+    fn test_extract_two_domains() {
+        let cells = |domain: &str| Cells {
+            domain: domain.to_owned(),
+        };
+        let input = "<td>app.example.com</td><td>api.example.org</td>";
+
+        // the second domain gets a regex of its own, not the first one's
+        assert_eq!(
+            cells("example.com").extract(input),
+            HashSet::from(["app.example.com".to_owned()])
+        );
+        assert_eq!(
+            cells("example.org").extract(input),
+            HashSet::from(["api.example.org".to_owned()])
+        );
+        assert_eq!(
+            cells("example.com").extract(input),
+            HashSet::from(["app.example.com".to_owned()])
+        );
+    }
+
+    #[test]
+    fn test_exclusions() {
+        let subdomains: HashSet<String> =
+            ["b.app.example.com", "www.example.com", "api.example.com"]
+                .map(String::from)
+                .into();
+
+        assert_eq!(
+            exclusions(&subdomains, 2),
+            ["api.example.com", "www.example.com"]
+        );
+        assert_eq!(exclusions(&subdomains, 10).len(), 3);
+    }
+
+    #[test]
+    fn test_parse_subdomains_two_domains() {
+        let body = "<td>app.example.com</td>\n<td>api.example.org</td>";
+        let parse = |domain| parse_subdomains(body, EngineChoice::RapidDNS, domain).unwrap();
+
+        assert_eq!(
+            parse("example.com"),
+            HashSet::from(["app.example.com".to_owned()])
+        );
+        assert_eq!(
+            parse("example.org"),
+            HashSet::from(["api.example.org".to_owned()])
+        );
+    }
+
+    /// crt.sh style, a root array of objects whose names are separated by newlines
+    #[derive(ApiExtract)]
+    #[api_extract(path = "*.name_value")]
+    struct Fqdns {
+        #[api_extract(domain)]
+        domain: String,
+    }
+
+    /// A list of bare labels under a nested key
+    #[derive(ApiExtract)]
+    #[api_extract(path = "result.subdomains", labels)]
+    struct Labels {
+        #[api_extract(domain)]
+        domain: String,
+    }
+
+    #[rstest]
+    #[case::fqdns(
+        r#"[{"name_value": "app.example.com\nexample.com"}, {"name_value": "cdn.example.net"}]"#,
+        &["app.example.com", "example.com"]
+    )]
+    #[case::missing_key(r#"[{"common_name": "app.example.com"}]"#, &[])]
+    #[case::not_json("<html>rate limited</html>", &[])]
+    fn test_api_extract_fqdns(#[case] input: &str, #[case] expected: &[&str]) {
+        let mut e = Fqdns {
+            domain: "example.com".to_owned(),
+        };
+        let expected: HashSet<String> = expected.iter().map(|s| (*s).to_owned()).collect();
+        assert_eq!(e.extract(input), expected);
+    }
+
+    #[test]
+    fn test_api_extract_labels() {
+        let mut e = Labels {
+            domain: "example.com".to_owned(),
+        };
+        let input = r#"{"result": {"subdomains": ["www", "api.v2"]}, "subdomains": ["ignored"]}"#;
+
+        let expected = HashSet::from([
+            "www.example.com".to_owned(),
+            "api.v2.example.com".to_owned(),
+        ]);
+        assert_eq!(e.extract(input), expected);
+    }
+
+    #[rstest]
+    #[case::plain_query(
+        "site:example.com -www.example.com",
+        "site:example.com -www.example.com"
+    )]
+    #[case::url_without_secrets(
+        "https://api.example.com/v1?domain=example.com",
+        "https://api.example.com/v1?domain=example.com"
+    )]
+    #[case::api_key(
+        "https://api.example.com/v1?domain=example.com&apikey=hunter2",
+        "https://api.example.com/v1?domain=example.com&apikey=REDACTED"
+    )]
+    #[case::access_token(
+        "https://graph.example.com/?access_token=hunter2&query=example.com",
+        "https://graph.example.com/?access_token=REDACTED&query=example.com"
+    )]
+    #[case::account_email(
+        "https://fofa.info/api/v1/search/all?email=me%40example.org&key=hunter2&page=1",
+        "https://fofa.info/api/v1/search/all?email=REDACTED&key=REDACTED&page=1"
+    )]
+    fn test_redact(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(redact(input), expected);
+    }
+
+    #[rstest]
+    #[case::plain("text/html; charset=iso-8859-1", Some("iso-8859-1"))]
+    #[case::quoted("text/html; Charset=\"UTF-8\"", Some("UTF-8"))]
+    #[case::other_params("text/html; boundary=x; charset=latin1", Some("latin1"))]
+    #[case::missing("text/html", None)]
+    fn test_charset(#[case] content_type: &str, #[case] expected: Option<&str>) {
+        assert_eq!(charset(content_type), expected);
+    }
+
+    #[rstest]
+    #[case::utf8("café".as_bytes(), None)]
+    #[case::latin1(b"caf\xe9", Some("iso-8859-1"))]
+    #[case::unlabeled_latin1(b"caf\xe9", None)]
+    #[case::mislabeled_latin1(b"caf\xe9", Some("utf-8"))]
+    #[case::unknown_label(b"caf\xe9", Some("x-made-up"))]
+    fn test_decode(#[case] bytes: &[u8], #[case] charset: Option<&str>) {
+        assert_eq!(decode(bytes, charset), "café");
+    }
+
+    pub(super) fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[rstest]
+    #[case::plain(b"[{\"name_value\": \"app.example.com\"}]".to_vec())]
+    #[case::gzipped(gzip(b"[{\"name_value\": \"app.example.com\"}]"))]
+    fn test_gunzip(#[case] body: Vec<u8>) {
+        assert_eq!(
+            &*gunzip(&body, DEFAULT_MAX_BODY_SIZE),
+            b"[{\"name_value\": \"app.example.com\"}]"
+        );
+    }
+
+    #[test]
+    fn test_gunzip_corrupt() {
+        let body = [0x1f, 0x8b, 0x00, 0x01];
+        assert_eq!(&*gunzip(&body, DEFAULT_MAX_BODY_SIZE), body);
+    }
+
+    #[test]
+    fn test_gunzip_over_limit() {
+        let body = gzip(&[b'a'; 4096]);
+        assert_eq!(&*gunzip(&body, 1024), body);
+    }
+
+    #[rstest]
+    #[case::content_length(ResponseTemplate::new(200).set_body_string("app.example.com ".repeat(64)))]
+    // no `Content-Length`, so the limit is only hit while streaming the body
+    #[case::streamed(
+        ResponseTemplate::new(200)
+            .set_body_string("app.example.com ".repeat(64))
+            .insert_header("transfer-encoding", "chunked")
+    )]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_rejects_oversized_body(#[case] response: ResponseTemplate) {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(method("GET"))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+
+        let mut engine = Mock::new("site:example.com", "");
+        engine.server = Some(server.uri());
+        engine.max_rounds = 3;
+
+        let found = Enumerator::new(engine)
+            .with_max_body_size(Some(512))
+            .enumerate(Client::new())
+            .await;
+
+        // the server answered, but every body was thrown away
+        assert_eq!(found, Some(HashSet::new()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_truncates_long_input() {
+        let logs = Logs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(logs.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        // a large synthetic page, with a name on each side of the cut
+        let body = format!(
+            "app.example.com {}late.example.com",
+            " ".repeat(2 * MAX_REGEX_INPUT_LEN)
+        );
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&server)
+            .await;
+
+        let mut engine = Mock::new("site:example.com", "");
+        engine.server = Some(server.uri());
+        engine.max_input_len = Some(MAX_REGEX_INPUT_LEN);
+
+        let found = Enumerator::new(engine).enumerate(Client::new()).await;
+
+        assert_eq!(found, Some(HashSet::from(["app.example.com".to_owned()])));
+        assert!(logs.contents().contains("body too long"));
+    }
+
+    #[rstest]
+    #[case::plain("example.com", "example.com")]
+    #[case::mixed_case_trailing_dot(" Example.COM. ", "example.com")]
+    #[case::idn("bücher.example", "xn--bcher-kva.example")]
+    fn test_normalize_domain(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(normalize_domain(input).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::single_label("localhost")]
+    #[case::ip("127.0.0.1")]
+    #[case::empty_label("app..example.com")]
+    #[case::hyphen("-app.example.com")]
+    #[case::underscore("_dmarc.example.com")]
+    #[case::space("my domain.com")]
+    fn test_normalize_domain_invalid(#[case] input: &str) {
+        assert!(matches!(normalize_domain(input), Err(Error::InvalidDomain)));
+    }
+
+    #[test]
+    fn test_engine_new() {
+        assert!(Engine::new(EngineChoice::CrtSh, "not a domain").is_err());
+        assert!(Engine::from_iter([EngineChoice::CrtSh, EngineChoice::Bing], "").is_err());
+
+        let engine = Engine::new(EngineChoice::CrtSh, "Example.COM.").unwrap();
+        let query = Enumerator::new(engine).first_query().unwrap();
+        assert!(query.ends_with(" example.com"), "{query}");
+    }
+
+    #[rstest]
+    #[case::subdomain("app.example.com", true)]
+    #[case::nested("api.v2.example.com", true)]
+    #[case::apex("example.com", true)]
+    #[case::wildcard("*.app.example.com", true)]
+    #[case::wildcard_apex("*.example.com", true)]
+    #[case::inner_wildcard("app.*.example.com", false)]
+    #[case::url("https://App.example.com:443/login", true)]
+    #[case::other_domain("app.example.org", false)]
+    #[case::suffix_only("badexample.com", false)]
+    #[case::leading_hyphen("-app.example.com", false)]
+    #[case::underscore("my_app.example.com", false)]
+    #[case::empty_label("app..example.com", false)]
+    #[case::markup(r#"<a href="/">app.example.com"#, false)]
+    fn test_is_valid_subdomain(#[case] name: &str, #[case] valid: bool) {
+        assert_eq!(is_valid_subdomain(name, "example.com"), valid);
+    }
+
+    #[rstest]
+    #[case::bing(
+        EngineChoice::Bing,
+        "<cite>https://-bad.example.com</cite><cite>https://ok.example.com/login</cite>"
+    )]
+    #[case::dnsdumpster(
+        EngineChoice::DNSDumpster,
+        r#"<td>ok.example.com</td><td><a href="/">bad.example.com</td>"#
+    )]
+    #[case::google(
+        EngineChoice::Google,
+        "<span>a..example.com &#8250; x</span><span>ok.example.com &#8250; x</span>"
+    )]
+    #[case::hackertarget(
+        EngineChoice::HackerTarget,
+        "ok.example.com,192.0.2.1\nbad_host.example.com,192.0.2.2\nexample.org,192.0.2.3"
+    )]
+    #[case::rapiddns(
+        EngineChoice::RapidDNS,
+        "<td>ok.example.com</td><td>bad host.example.com</td>"
+    )]
+    fn test_validate_drops_malformed(#[case] choice: EngineChoice, #[case] body: &str) {
+        let mut engine = Engine::new(choice, "example.com").unwrap();
+        let raw = engine.extract(body);
+        assert!(raw.len() > 1, "{raw:?}");
+
+        let names = validate(&engine, engine.clean(raw));
+        assert_eq!(names.len(), 1, "{names:?}");
+        assert!(names.iter().all(|n| n.contains("ok.example.com")));
+    }
+
+    #[test]
+    fn test_validate_skips_engines_without_domain() {
+        let raw = HashSet::from(["not a host".to_owned()]);
+        assert_eq!(validate(&Mock::new("", ""), raw.clone()), raw);
+    }
+
+    #[test]
+    fn test_engine_choice_name() {
+        assert_eq!(EngineChoice::CrtSh.name(), "CrtSh");
+        assert_eq!(EngineChoice::SubdomainCenter.name(), "SubdomainCenter");
+    }
+
+    #[test]
+    fn test_engine_identity() {
+        for &choice in EngineChoice::VARIANTS {
+            let engine = Engine::new(choice, "example.com").unwrap();
+
+            assert_eq!(EngineChoice::from(&engine), choice);
+            assert_eq!(choice.to_string(), engine.settings().name);
+        }
+        assert_eq!(EngineChoice::VirusTotal.to_string(), "VirusTotal");
+    }
+
+    #[test]
+    fn test_settings_for() {
+        for &choice in EngineChoice::VARIANTS {
+            let settings = Engine::settings_for(choice);
+            let built = Engine::new(choice, "example.com").unwrap().settings();
+
+            assert_eq!(settings.name, built.name);
+            assert_eq!(settings.base_url, built.base_url);
+            assert_eq!(settings.max_rounds, built.max_rounds);
+            assert_eq!(settings.passive, built.passive);
+            assert_eq!(settings.priority, built.priority);
+        }
+    }
+
+    #[test]
+    fn test_max_input_len() {
+        assert_eq!(
+            Yahoo::new("example.com").max_input_len(),
+            Some(MAX_REGEX_INPUT_LEN)
+        );
+        assert_eq!(CrtSh::new("example.com").max_input_len(), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_with_headers() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(method("GET"))
+            .and(header("cookie", "CONSENT=YES+"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("app.example.com"))
+            .mount(&server)
+            .await;
+
+        let engine = || {
+            let mut engine = Mock::new("site:example.com", "");
+            engine.server = Some(server.uri());
+            engine
+        };
+        let headers = HeaderMap::from_iter([(COOKIE, HeaderValue::from_static("CONSENT=YES+"))]);
+
+        let targeted = Enumerator::new(engine())
+            .with_headers(headers)
+            .enumerate(Client::new())
+            .await;
+        let other = Enumerator::new(engine())
+            .with_backoff(Some(0), None)
+            .enumerate(Client::new())
+            .await;
+
+        assert_eq!(
+            targeted,
+            Some(HashSet::from(["app.example.com".to_owned()]))
+        );
+        // without the header, the server only answers 404
+        assert_eq!(other, None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_serializes_requests_to_same_host() {
+        const LATENCY: Duration = Duration::from_millis(300);
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("app.example.com")
+                    .set_delay(LATENCY),
+            )
+            .mount(&server)
+            .await;
+
+        // both engines share the same base URL, so the same host
+        let limiter = HostLimiter::new(1);
+        let enumerator = || {
+            let mut engine = Mock::new("site:example.com", "");
+            engine.server = Some(server.uri());
+            Enumerator::new(engine).with_host_limiter(Some(limiter.clone()))
+        };
+
+        let started = Instant::now();
+        let (a, b) = tokio::join!(
+            enumerator().enumerate(Client::new()),
+            enumerator().enumerate(Client::new())
+        );
+
+        assert!(a.is_some() && b.is_some());
+        // one request only starts once the other is over
+        assert!(started.elapsed() >= 2 * LATENCY, "{:?}", started.elapsed());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_spaces_requests_across_engines() {
+        const INTERVAL: Duration = Duration::from_millis(200);
+        let pacer = Pacer::new(INTERVAL);
+        let enumerator = |body| {
+            let mut engine = Mock::new("site:example.com", body);
+            engine.max_rounds = 2;
+            Enumerator::new(engine).with_pacer(Some(pacer.clone()))
+        };
+
+        let started = Instant::now();
+        let (a, b) = tokio::join!(
+            enumerator("a.example.com").enumerate(Client::new()),
+            enumerator("b.example.com").enumerate(Client::new())
+        );
+
+        assert!(a.is_some() && b.is_some());
+        // four requests, the first one doesn't wait
+        assert!(started.elapsed() >= 3 * INTERVAL, "{:?}", started.elapsed());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_excludes_results_of_other_engines() {
+        let shared = Found::default();
+
+        Enumerator::new(Mock::new("", "crt.example.com"))
+            .with_shared(Some(shared.clone()))
+            .enumerate(Client::new())
+            .await
+            .unwrap();
+
+        let mut engine = Mock::new("site:example.com", "app.example.com");
+        engine.excludes = true;
+        engine.max_rounds = 2;
+        let queries = engine.queries.clone();
+        Enumerator::new(engine)
+            .with_shared(Some(shared.clone()))
+            .enumerate(Client::new())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *queries.lock().unwrap(),
+            [
+                "site:example.com -crt.example.com",
+                "site:example.com -app.example.com -crt.example.com"
+            ]
+        );
+        let mut found: Vec<_> = shared.lock().unwrap().iter().cloned().collect();
+        found.sort_unstable();
+        assert_eq!(found, ["app.example.com", "crt.example.com"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_unlabeled_gzip_body() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(gzip(b"api.example.com app.example.com"), "application/json"),
+            )
+            .mount(&server)
+            .await;
+
+        let mut engine = Mock::new("site:example.com", "");
+        engine.server = Some(server.uri());
+
+        let found = Enumerator::new(engine).enumerate(Client::new()).await;
+
+        let expected = HashSet::from(["api.example.com".to_owned(), "app.example.com".to_owned()]);
+        assert_eq!(found, Some(expected));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_latin1_body() {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                b"r\xe9sum\xe9.example.com app.example.com".to_vec(),
+                "text/plain; charset=iso-8859-1",
+            ))
+            .mount(&server)
+            .await;
+
+        let mut engine = Mock::new("site:example.com", "");
+        engine.server = Some(server.uri());
+
+        let found = Enumerator::new(engine).enumerate(Client::new()).await;
+
+        let expected = HashSet::from([
+            "résumé.example.com".to_owned(),
+            "app.example.com".to_owned(),
+        ]);
+        assert_eq!(found, Some(expected));
+    }
+
+    #[rstest]
+    #[case::chrome(DEFAULT_USER_AGENT, Some(133))]
+    #[case::newer(
+        "Mozilla/5.0 (X11; Linux x86_64) Chrome/140.0.7339.80 Safari/537.36",
+        Some(140)
+    )]
+    #[case::lynx("Lynx/2.8.9rel.1 libwww-FM/2.14", None)]
+    #[case::malformed("Mozilla/5.0 Chrome/abc", None)]
+    fn test_chrome_version(#[case] user_agent: &str, #[case] expected: Option<u32>) {
+        assert_eq!(chrome_version(user_agent), expected);
+    }
+
+    #[rstest]
+    #[case::older(132, 133, true)]
+    #[case::same(133, 133, false)]
+    #[case::newer(134, 133, false)]
+    fn test_is_stale(#[case] version: u32, #[case] known_good: u32, #[case] expected: bool) {
+        let user_agent = format!("Mozilla/5.0 Chrome/{version}.0.0.0 Safari/537.36");
+        assert_eq!(is_stale(&user_agent, known_good), expected);
+    }
+
+    #[rstest]
+    #[case::before_the_table(0, 125)]
+    #[case::release_day(1_738_627_200, 130)]
+    #[case::between_releases(1_740_787_200, 130)]
+    // a year after the last known release, 13 more versions have shipped
+    #[case::extrapolated(1_761_609_600 + 365 * 86_400, 152)]
+    fn test_known_good_chrome(#[case] at: i64, #[case] expected: u32) {
+        assert_eq!(known_good_chrome(at), expected);
+    }
+
+    #[rstest]
+    // 2025-03-01, a month after Chrome 133 shipped
+    #[case::fresh(1_740_787_200, None)]
+    // 2026-10-18, long after
+    #[case::outdated(1_792_281_600, Some(133))]
+    fn test_default_user_agent_stale_at(#[case] at: i64, #[case] expected: Option<u32>) {
+        assert_eq!(stale_at(DEFAULT_USER_AGENT, at), expected);
+    }
+
+    #[test]
+    fn test_is_stale_not_chrome() {
+        assert!(!is_stale("Lynx/2.8.9rel.1 libwww-FM/2.14", u32::MAX));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_logs_query() {
+        let logs = Logs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(logs.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let engine = Mock::new(
+            "https://mock.test/?q=example.com&apikey=hunter2",
+            "app.example.com",
+        );
+        let found = Enumerator::new(engine).enumerate(Client::new()).await;
+        assert_eq!(found, Some(HashSet::from(["app.example.com".to_owned()])));
+
+        let logs = logs.contents();
+        let line = logs
+            .lines()
+            .find(|l| l.contains("generated query"))
+            .expect("query should be logged");
+        assert!(line.contains("DEBUG"));
+        assert!(line.contains("apikey=REDACTED"));
+        assert!(!logs.contains("hunter2"));
+    }
+
+    #[rstest]
+    #[case::fixed(DelayKind::Fixed, 1, 0)]
+    // 250ms, 500ms, 1s, then 2s at most, until retries run out
+    #[case::adaptive(DelayKind::Adaptive, 5, 5750)]
+    #[tokio::test(start_paused = true)]
+    async fn test_enumerate_when_blocked(
+        #[case] kind: DelayKind,
+        #[case] searched: usize,
+        #[case] waited_ms: u64,
+    ) {
+        let mut engine = Mock::new(
+            "site:example.com",
+            "Our systems have detected unusual traffic",
+        );
+        engine.max_rounds = 10;
+
+        let searches = engine.searches.clone();
+        let start = tokio::time::Instant::now();
+        let found = Enumerator::new(engine)
+            .with_delay_strategy(Some(kind))
+            .enumerate(Client::new())
+            .await;
+
+        assert_eq!(found, Some(HashSet::new()));
+        assert_eq!(searches.load(Ordering::Relaxed), searched);
+        assert_eq!(start.elapsed(), Duration::from_millis(waited_ms));
+    }
+
+    #[rstest]
+    #[case::empty("", &[], 3)]
+    #[case::whitespace(" \r\n\t", &[], 3)]
+    // the same page over and over, nothing new after the first one
+    #[case::same_page("app.example.com", &["app.example.com"], 6)]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_empty_body(
+        #[case] body: &'static str,
+        #[case] expected: &[&str],
+        #[case] searched: usize,
+    ) {
+        let mut engine = Mock::new("site:example.com", body);
+        engine.max_rounds = 10;
+        engine.pagination = PaginationKind::Cursor;
+
+        let searches = engine.searches.clone();
+        let found = Enumerator::new(engine).enumerate(Client::new()).await;
+
+        // empty bodies use up retries twice as fast
+        let expected = expected.iter().map(|s| (*s).to_owned()).collect();
+        assert_eq!(found, Some(expected));
+        assert_eq!(searches.load(Ordering::Relaxed), searched);
+    }
+
+    #[rstest]
+    #[case::no_cap(None, 50)]
+    #[case::lowered(Some(5), 5)]
+    #[case::never_raised(Some(100), 50)]
+    fn test_max_rounds_yahoo(#[case] cap: Option<usize>, #[case] expected: usize) {
+        let e = Enumerator::new(Yahoo::new("example.com")).with_max_rounds(cap);
+        assert_eq!(e.max_rounds(), expected);
+    }
+
+    #[rstest]
+    #[case::no_cap(None)]
+    #[case::lowered(Some(5))]
+    #[case::zero(Some(0))]
+    fn test_max_rounds_crtsh(#[case] cap: Option<usize>) {
+        let e = Enumerator::new(CrtSh::new("example.com")).with_max_rounds(cap);
+        assert_eq!(e.max_rounds(), cap.unwrap_or(1).min(1));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_max_rounds_cap() {
+        let mut engine = Mock::new("site:example.com", "app.example.com");
+        engine.max_rounds = 10;
+        let searches = engine.searches.clone();
+
+        Enumerator::new(engine)
+            .with_max_rounds(Some(3))
+            .enumerate(Client::new())
+            .await;
+
+        assert_eq!(searches.load(Ordering::Relaxed), 3);
+    }
+
+    #[rstest]
+    #[case::google(EngineChoice::Google, Some(50), 50)]
+    #[case::google_clamped(EngineChoice::Google, Some(500), 100)]
+    #[case::google_zero(EngineChoice::Google, Some(0), 1)]
+    #[case::google_default(EngineChoice::Google, None, 20)]
+    #[case::yahoo_fixed(EngineChoice::Yahoo, Some(50), 7)]
+    #[case::not_adjustable(EngineChoice::CrtSh, Some(50), 0)]
+    fn test_with_per_page(
+        #[case] choice: EngineChoice,
+        #[case] per_page: Option<usize>,
+        #[case] expected: usize,
+    ) {
+        let engine = Engine::new(choice, "example.com").unwrap();
+        let e = Enumerator::new(engine).with_per_page(per_page);
+        assert_eq!(e.engine.settings().per_page, expected);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_progress_events() {
+        let mut engine = Mock::new("site:example.com", "app.example.com");
+        engine.max_rounds = 2;
+        engine.pagination = PaginationKind::Cursor;
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let callback = ProgressCallback::new(move |e| sink.lock().unwrap().push(e));
+
+        Enumerator::new(engine)
+            .with_progress(Some(callback))
+            .enumerate(Client::new())
+            .await;
+
+        // the second page finds the same subdomain again, it's only reported once
+        let engine = "Mock";
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                ProgressEvent::EngineStarted { engine },
+                ProgressEvent::PageFetched { engine, page: 0 },
+                ProgressEvent::SubdomainFound {
+                    engine,
+                    name: "app.example.com".to_owned()
+                },
+                ProgressEvent::PageFetched { engine, page: 0 },
+                ProgressEvent::EngineCompleted { engine, found: 1 },
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_cache() {
+        let cache = ResponseCache::default();
+        // the query and page never change, so every round sends the same request
+        let engine = || {
+            let mut engine = Mock::new("site:example.com", "app.example.com");
+            engine.max_rounds = 3;
+            engine.pagination = PaginationKind::Cursor;
+            engine
+        };
+
+        let first = engine();
+        let searches = first.searches.clone();
+        let found = Enumerator::new(first)
+            .with_cache(Some(cache.clone()))
+            .enumerate(Client::new())
+            .await;
+        assert_eq!(found, Some(HashSet::from(["app.example.com".to_owned()])));
+        assert_eq!(searches.load(Ordering::Relaxed), 1);
+
+        // later engines of the run are served from the cache too
+        let second = engine();
+        let searches = second.searches.clone();
+        let found = Enumerator::new(second)
+            .with_cache(Some(cache))
+            .enumerate(Client::new())
+            .await;
+        assert_eq!(found, Some(HashSet::from(["app.example.com".to_owned()])));
+        assert_eq!(searches.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_cleans_results() {
+        let engine = Mock::new("site:example.com", "APP.example.com api.EXAMPLE.com");
+
+        let found = Enumerator::new(engine).enumerate(Client::new()).await;
+
+        let expected = HashSet::from(["app.example.com".to_owned(), "api.example.com".to_owned()]);
+        assert_eq!(found, Some(expected));
+    }
+
+    #[rstest]
+    // the first page yields something new, so it's asked for again before moving on
+    #[case::offset_pages(PaginationKind::OffsetPages, &["0", "0", "1", "2"])]
+    #[case::cursor(PaginationKind::Cursor, &["0", "0", "0", "0"])]
+    #[case::single(PaginationKind::Single, &["0"])]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_pagination(
+        #[case] pagination: PaginationKind,
+        #[case] expected_pages: &[&str],
+    ) {
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("app.example.com"))
+            .mount(&server)
+            .await;
+
+        let mut engine = Mock::new("site:example.com", "");
+        engine.server = Some(server.uri());
+        engine.pagination = pagination;
+        engine.max_rounds = 4;
+
+        Enumerator::new(engine).enumerate(Client::new()).await;
+
+        let pages: Vec<_> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter_map(|r| r.url.query_pairs().find(|(k, _)| k == "page"))
+            .map(|(_, v)| v.into_owned())
+            .collect();
+        assert_eq!(pages, expected_pages);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_with_hasher() {
+        type Fixed = BuildHasherDefault<DefaultHasher>;
+        let body = "a.example.com b.example.com c.example.com d.example.com e.example.com";
+
+        let mut orders = Vec::new();
+        for _ in 0..2 {
+            let found = Enumerator::new(Mock::new("site:example.com", body))
+                .with_hasher(Fixed::default())
+                .enumerate(Client::new())
+                .await
+                .unwrap();
+            orders.push(found.into_iter().collect::<Vec<_>>());
         }
+        assert_eq!(orders[0], orders[1]);
+
+        // queries built from the results are stable too
+        let query = || {
+            let mut found = HashSet::with_hasher(Fixed::default());
+            found.extend(orders[0].iter().cloned());
+            Google::new("example.com")
+                .next_query(&found)
+                .unwrap()
+                .into_owned()
+        };
+        assert_eq!(query(), query());
+    }
+
+    #[test]
+    fn test_jitter_seed() {
+        let sequence = |seed| {
+            let mut e = Enumerator::new(Mock::new("", ""))
+                .with_delay_strategy(Some(DelayKind::Jittered))
+                .with_jitter_seed(seed);
+            let mut delay = e.delay_strategy();
+            (0..8).map(|_| delay.next_delay()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(sequence(Some(42)), sequence(Some(42)));
+        assert_ne!(sequence(Some(42)), sequence(Some(43)));
+        assert!(
+            sequence(None)
+                .iter()
+                .all(|d| *d <= Duration::from_millis(delay::MAX_JITTER_MS))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_enumerate_delay_override() {
+        let mut engine = Mock::new("site:example.com", "app.example.com");
+        engine.max_rounds = 2;
+
+        let start = std::time::Instant::now();
+        Enumerator::new(engine)
+            .with_delay(Some(Duration::from_millis(400)))
+            .with_delay_strategy(Some(DelayKind::Fixed))
+            .enumerate(Client::new())
+            .await;
+
+        // the mock's own delay is a no-op, so any waiting comes from the override,
+        // once between the two rounds and not after the last
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(400), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(800), "{elapsed:?}");
+    }
+
+    #[rstest]
+    // 1s, 2s, 4s, 8s, then the circuit breaker opens on the 5th failure
+    #[case::defaults(None, None, 5, 15)]
+    // 2s, 6s, then held at 15s, below the backoff's limit, until the circuit breaker opens
+    #[case::custom(Some(2), Some(3), 5, 38)]
+    // 1s each time until the circuit breaker opens on the 5th failure
+    #[case::flat(None, Some(1), 5, 4)]
+    // clamped to 15s, so the engine still searches until the circuit breaker opens
+    #[case::long_base(Some(100), None, 5, 60)]
+    #[tokio::test(start_paused = true)]
+    async fn test_enumerate_backoff_schedule(
+        #[case] base: Option<u64>,
+        #[case] factor: Option<u64>,
+        #[case] searches: usize,
+        #[case] waited_secs: u64,
+    ) {
+        let mut engine = Mock::new("site:example.com", "");
+        engine.status = 503;
+        engine.max_rounds = 10;
+        let count = engine.searches.clone();
+
+        let start = tokio::time::Instant::now();
+        let found = Enumerator::new(engine)
+            .with_backoff(base, factor)
+            .enumerate(Client::new())
+            .await;
+
+        assert!(found.is_none());
+        assert_eq!(count.load(Ordering::Relaxed), searches);
+        assert_eq!(start.elapsed(), Duration::from_secs(waited_secs));
+    }
+
+    #[rstest]
+    #[case::opens_early(Some(2), 2, 1)]
+    // after 1s, 2s, 4s and 8s
+    #[case::default(None, 5, 15)]
+    // 1s, 2s, 4s, 8s, then held at 15s instead of giving up once the backoff reaches 16s
+    #[case::past_backoff(Some(7), 7, 45)]
+    #[tokio::test(start_paused = true)]
+    async fn test_enumerate_circuit_breaker(
+        #[case] threshold: Option<usize>,
+        #[case] searches: usize,
+        #[case] waited_secs: u64,
+    ) {
+        let mut engine = Mock::new("site:example.com", "");
+        engine.status = 503;
+        engine.max_rounds = 10;
+        let count = engine.searches.clone();
+
+        let start = tokio::time::Instant::now();
+        let found = Enumerator::new(engine)
+            .with_circuit_breaker(threshold)
+            .enumerate(Client::new())
+            .await;
+
+        assert!(found.is_none());
+        assert_eq!(count.load(Ordering::Relaxed), searches);
+        assert_eq!(start.elapsed(), Duration::from_secs(waited_secs));
+    }
+
+    #[rstest]
+    // rate limits don't grow the backoff, so only retries run out
+    #[case::retry_after(Some("2"), 10)]
+    #[case::default(None, 5 * RATE_LIMIT_BACKOFF)]
+    #[case::capped(Some("3600"), 5 * MAX_RATE_LIMIT_BACKOFF)]
+    #[case::http_date(Some("Wed, 21 Oct 2015 07:28:00 GMT"), 5 * RATE_LIMIT_BACKOFF)]
+    #[tokio::test(start_paused = true)]
+    async fn test_enumerate_rate_limited(
+        #[case] retry_after: Option<&'static str>,
+        #[case] waited_secs: u64,
+    ) {
+        let mut engine = Mock::new("site:example.com", "");
+        engine.status = 429;
+        engine.headers = retry_after
+            .map(|v| ("retry-after", v))
+            .into_iter()
+            .collect();
+        engine.max_rounds = 10;
+        let count = engine.searches.clone();
+
+        let start = tokio::time::Instant::now();
+        let found = Enumerator::new(engine).enumerate(Client::new()).await;
+
+        assert!(found.is_none());
+        // as many searches as retries, unlike server errors, see `test_enumerate_backoff_schedule`
+        assert_eq!(count.load(Ordering::Relaxed), usize::from(MAX_RETRIES));
+        assert_eq!(start.elapsed(), Duration::from_secs(waited_secs));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_enumerate_logs_total_backoff() {
+        let logs = Logs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(logs.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let mut engine = Mock::new("site:example.com", "");
+        engine.status = 503;
+        engine.max_rounds = 10;
+
+        Enumerator::new(engine).enumerate(Client::new()).await;
 
-        subdomains
+        // 1s + 2s + 4s + 8s before the circuit breaker opens
+        let logs = logs.contents();
+        let line = logs
+            .lines()
+            .find(|l| l.contains("giving up on the engine"))
+            .expect("giving up should be logged");
+        assert!(line.contains("total_backoff_secs=15"));
     }
 }
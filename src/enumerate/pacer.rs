@@ -0,0 +1,64 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Keeps at least `interval` between any two requests, across every engine
+///
+/// Clones share the time of the last request, so one pacer is handed to every engine of a run.
+#[derive(Debug, Clone)]
+pub struct Pacer {
+    interval: Duration,
+    last: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Pacer {
+    #[must_use]
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: Arc::default(),
+        }
+    }
+
+    /// Wait until a request may be sent, `interval` after the previous one
+    pub async fn wait(&self) {
+        // held while sleeping, so requests waiting their turn go out one interval apart
+        let mut last = self.last.lock().await;
+        if let Some(last) = *last {
+            tokio::time::sleep_until(last + self.interval).await;
+        }
+        *last = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wait_spaces_requests() {
+        let pacer = Pacer::new(Duration::from_millis(500));
+        let started = Instant::now();
+
+        // the first request doesn't wait
+        pacer.wait().await;
+        assert_eq!(started.elapsed(), Duration::ZERO);
+
+        let (a, b) = (pacer.clone(), pacer.clone());
+        tokio::join!(a.wait(), b.wait());
+        assert_eq!(started.elapsed(), Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_wait_after_interval() {
+        let pacer = Pacer::new(Duration::from_millis(500));
+        pacer.wait().await;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let started = Instant::now();
+        pacer.wait().await;
+        assert_eq!(started.elapsed(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,138 @@
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use clap::ValueEnum;
+use tracing::warn;
+
+use super::{Engine, EngineChoice, Extract};
+
+/// How many times each body is extracted from by default
+pub const DEFAULT_ITERATIONS: u32 = 100;
+
+/// How long an engine's `extract` took on a saved body, for `--profile-regex`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timing {
+    pub engine: EngineChoice,
+    /// Size of the body, in bytes
+    pub len: usize,
+    pub iterations: u32,
+    /// Time of a single `extract`, averaged over every iteration
+    pub per_op: Duration,
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} ns/op ({} bytes, {} iterations)",
+            self.engine,
+            self.per_op.as_nanos(),
+            self.len,
+            self.iterations
+        )
+    }
+}
+
+/// Read the saved bodies in `dir`, each named after the engine it's for, e.g. `bing.html`
+///
+/// Files not named after an engine are skipped. Bodies come back in the order engines are
+/// declared in.
+///
+/// # Errors
+///
+/// If `dir` or one of the bodies can't be read
+pub fn load_bodies(dir: &Path) -> anyhow::Result<Vec<(EngineChoice, String)>> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+
+    let mut bodies = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        let Ok(engine) = <EngineChoice as ValueEnum>::from_str(stem, true) else {
+            warn!(path = %path.display(), "not named after an engine, skipping it");
+            continue;
+        };
+        let body =
+            std::fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        bodies.push((engine, String::from_utf8_lossy(&body).into_owned()));
+    }
+    bodies.sort_by_key(|(engine, _)| *engine);
+
+    Ok(bodies)
+}
+
+/// Time each engine's `extract` on its body over `iterations` runs
+///
+/// The whole body is extracted from, however long, so a pattern that backtracks
+/// catastrophically shows up as a timing far above the others.
+///
+/// # Errors
+///
+/// If `domain` is invalid
+pub fn profile(
+    bodies: &[(EngineChoice, String)],
+    domain: &str,
+    iterations: u32,
+) -> anyhow::Result<Vec<Timing>> {
+    let iterations = iterations.max(1);
+    bodies
+        .iter()
+        .map(|(choice, body)| {
+            let mut engine = Engine::new(*choice, domain)?;
+            let started = Instant::now();
+            for _ in 0..iterations {
+                std::hint::black_box(engine.extract(std::hint::black_box(body)));
+            }
+            Ok(Timing {
+                engine: *choice,
+                len: body.len(),
+                iterations,
+                per_op: started.elapsed() / iterations,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("bing.html"),
+            "<cite>https://app.example.com</cite>".repeat(100),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("crtsh.json"),
+            r#"[{"name_value":"api.example.com"}]"#,
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a body").unwrap();
+
+        let bodies = load_bodies(dir.path()).unwrap();
+        let timings = profile(&bodies, "example.com", 10).unwrap();
+
+        let engines: Vec<_> = timings.iter().map(|t| t.engine).collect();
+        assert_eq!(engines, [EngineChoice::Bing, EngineChoice::CrtSh]);
+        assert!(timings.iter().all(|t| t.iterations == 10));
+        assert_eq!(timings[0].len, 3600);
+
+        let line = timings[1].to_string();
+        assert!(line.starts_with("CrtSh: "), "{line}");
+        assert!(line.ends_with(" ns/op (34 bytes, 10 iterations)"), "{line}");
+    }
+
+    #[test]
+    fn test_load_bodies_missing_dir() {
+        assert!(load_bodies(Path::new("does-not-exist")).is_err());
+    }
+}
@@ -1,17 +1,37 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
+use std::hash::BuildHasher;
+use std::net::IpAddr;
+use std::sync::LazyLock;
 
-use reqwest::{Client, Response, header};
+use regex::Regex;
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, Response};
 
-use super::{Extract, Search, Settings};
+use super::{Extract, MAX_REGEX_INPUT_LEN, PaginationKind, SUBDOMAIN_RE_STR, Search, Settings};
 
-const SETTINGS: Settings = Settings {
+const SAMEIP_URL: &str = "https://rapiddns.io/sameip";
+
+pub(super) const SETTINGS: Settings = Settings {
     name: "RapidDNS",
     base_url: "https://rapiddns.io/subdomain",
     user_agent: "",
     max_rounds: 10,
+    pagination: PaginationKind::OffsetPages,
+    passive: true,
+    priority: 60,
+    per_page: 0,
+    max_per_page: 0,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 0,
 };
 
+static SAMEIP_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(&format!(r"<td>(?<host>{SUBDOMAIN_RE_STR})<\/td>"))
+        .expect("failed to compile regex for RapidDNS sameip")
+});
+
 #[derive(Extract)]
 #[extract(pattern = r#"<td>(?<subdomain>.*?\.{domain})<\/td>"#)]
 pub struct RapidDNS {
@@ -25,6 +45,37 @@ impl RapidDNS {
             domain: domain.into(),
         }
     }
+
+    /// Find other hosts that share the given IP address
+    ///
+    /// # Errors
+    ///
+    /// If the request fails or gets an error status
+    pub async fn sameip(client: Client, ip: IpAddr) -> Result<HashSet<String>, reqwest::Error> {
+        let body = client
+            .get(format!("{SAMEIP_URL}/{ip}"))
+            .query(&[("full", 1)])
+            .header(header::ACCEPT, "text/html")
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        Ok(Self::extract_sameip(&body))
+    }
+
+    /// Extract hostnames from the sameip result table
+    ///
+    /// The table also holds IP addresses, record types and dates,
+    /// so only cells that look like a hostname with at least one dot are kept.
+    fn extract_sameip(input: &str) -> HashSet<String> {
+        SAMEIP_RE
+            .captures_iter(input)
+            .map(|c| c["host"].to_ascii_lowercase())
+            .filter(|h| h.contains('.') && h.parse::<IpAddr>().is_err())
+            .collect()
+    }
 }
 
 impl Search for RapidDNS {
@@ -32,7 +83,7 @@ impl Search for RapidDNS {
         SETTINGS
     }
 
-    fn next_query(&self, _: &HashSet<String>) -> Option<Cow<'_, str>> {
+    fn next_query<S: BuildHasher>(&self, _: &HashSet<String, S>) -> Option<Cow<'_, str>> {
         let query = format!("{}/{}", SETTINGS.base_url, self.domain);
         Some(Cow::Owned(query))
     }
@@ -43,16 +94,55 @@ impl Search for RapidDNS {
         client: Client,
         url: &str,
         page: usize,
+        headers: &HeaderMap,
     ) -> Result<Response, reqwest::Error> {
         client
             .get(url)
             .query(&[("page", page)])
             .query(&[("full", 1)])
             .header(header::ACCEPT, "text/html")
+            .headers(headers.clone())
             .send()
             .await
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_sameip() {
+        let input = r#"
+        <table class="table table-striped table-bordered">
+            <thead>
+                <tr><th>#</th><th>Domain</th><th>Address</th><th>Type</th><th>Date</th></tr>
+            </thead>
+            <tbody>
+                <tr>
+                    <th scope="row">1</th>
+                    <td>app.example.com</td>
+                    <td><a href="/sameip/93.184.216.34#result">93.184.216.34</a></td>
+                    <td>A</td>
+                    <td>2024-05-01</td>
+                </tr>
+                <tr>
+                    <th scope="row">2</th>
+                    <td>Mail.Example.org</td>
+                    <td>93.184.216.34</td>
+                    <td>A</td>
+                    <td>2024-04-12</td>
+                </tr>
+            </tbody>
+        </table>
+        "#;
+
+        let expected = HashSet::from(["app.example.com".to_owned(), "mail.example.org".to_owned()]);
+        assert_eq!(RapidDNS::extract_sameip(input), expected);
+    }
 
-    /// `DNSDumpster` only runs once, no need to delay
-    async fn delay(&self) {}
+    #[test]
+    fn test_extract_sameip_empty() {
+        assert!(RapidDNS::extract_sameip("<table></table>").is_empty());
+    }
 }
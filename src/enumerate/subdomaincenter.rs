@@ -0,0 +1,94 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+
+use enumerate_derive::ApiExtract;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Response};
+
+use super::{Extract, PaginationKind, Search, Settings};
+
+pub(super) const SETTINGS: Settings = Settings {
+    name: "SubdomainCenter",
+    base_url: "https://api.subdomain.center",
+    user_agent: "", // not used
+    max_rounds: 1,
+    pagination: PaginationKind::Single,
+    passive: true,
+    priority: 70,
+    per_page: 0,
+    max_per_page: 0,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 0,
+};
+
+// the response is a plain array of names, which can include other domains
+#[derive(ApiExtract)]
+#[api_extract(path = "")]
+pub struct SubdomainCenter {
+    #[api_extract(domain)]
+    domain: String,
+}
+
+impl SubdomainCenter {
+    pub fn new(domain: impl Into<String>) -> Self {
+        Self {
+            domain: domain.into(),
+        }
+    }
+}
+
+impl Search for SubdomainCenter {
+    fn settings(&self) -> Settings {
+        SETTINGS
+    }
+
+    fn next_query<S: BuildHasher>(&self, _: &HashSet<String, S>) -> Option<Cow<'_, str>> {
+        let domain = &self.domain;
+        let base_url = SETTINGS.base_url;
+        let query = format!("{base_url}/?domain={domain}");
+        Some(Cow::Owned(query))
+    }
+
+    async fn search(
+        &self,
+        client: Client,
+        url: &str,
+        _: usize,
+        headers: &HeaderMap,
+    ) -> Result<Response, reqwest::Error> {
+        client.get(url).headers(headers.clone()).send().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let input = r#"[
+            "app.example.com",
+            "api.dev.example.com",
+            "cdn.example.net",
+            "notexample.com"
+        ]"#;
+
+        let mut sc = SubdomainCenter::new("example.com");
+
+        let expected = HashSet::from([
+            "app.example.com".to_owned(),
+            "api.dev.example.com".to_owned(),
+        ]);
+        assert_eq!(sc.extract(input), expected);
+    }
+
+    #[test]
+    fn test_extract_invalid() {
+        let mut sc = SubdomainCenter::new("example.com");
+
+        assert!(sc.extract(r#"{"error": "rate limited"}"#).is_empty());
+        assert!(sc.extract("not json").is_empty());
+    }
+}
@@ -1,22 +1,32 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::hash::BuildHasher;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use base64::Engine;
 use base64::prelude::BASE64_STANDARD;
-use reqwest::{Client, Response, header};
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, Response};
 use serde::{Deserialize, Deserializer};
 
-use super::{DEFAULT_USER_AGENT, Extract, Search, Settings};
+use super::{DEFAULT_USER_AGENT, Extract, PaginationKind, Search, Settings};
 
 const PER_PAGE: usize = 10;
-const SETTINGS: Settings = Settings {
+pub(super) const SETTINGS: Settings = Settings {
     name: "VirusTotal",
     // the complete url is:
     // https://www.virustotal.com/ui/domains/{domain}/relationships/subdomains
     base_url: "https://www.virustotal.com/ui/domains",
     user_agent: DEFAULT_USER_AGENT,
     max_rounds: 15,
+    pagination: PaginationKind::Cursor,
+    passive: true,
+    priority: 90,
+    per_page: 0,
+    max_per_page: 0,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 1000,
 };
 
 pub struct VirusTotal {
@@ -77,6 +87,10 @@ impl Extract for VirusTotal {
             Err(_) => HashSet::new(),
         }
     }
+
+    fn domain(&self) -> Option<&str> {
+        Some(&self.domain)
+    }
 }
 
 impl Search for VirusTotal {
@@ -84,7 +98,7 @@ impl Search for VirusTotal {
         SETTINGS
     }
 
-    fn next_query(&self, _: &HashSet<String>) -> Option<Cow<'_, str>> {
+    fn next_query<S: BuildHasher>(&self, _: &HashSet<String, S>) -> Option<Cow<'_, str>> {
         let Self { domain, meta } = self;
 
         let base_url = SETTINGS.base_url;
@@ -106,6 +120,7 @@ impl Search for VirusTotal {
         client: Client,
         url: &str,
         _: usize,
+        headers: &HeaderMap,
     ) -> Result<Response, reqwest::Error> {
         client
             .get(url)
@@ -120,13 +135,10 @@ impl Search for VirusTotal {
                 "X-VT-Anti-Abuse-Header",
                 VirusTotal::compute_anti_abuse_header(),
             )
+            .headers(headers.clone())
             .send()
             .await
     }
-
-    async fn delay(&self) {
-        tokio::time::sleep(Duration::from_secs(1)).await;
-    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -1,19 +1,32 @@
 use std::borrow::Cow;
 use std::collections::HashSet;
+use std::hash::BuildHasher;
 
-use reqwest::{Client, Response, header};
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, Response};
 
-use super::{DEFAULT_USER_AGENT, Extract, SUBDOMAIN_RE_STR, Search, Settings};
+use super::{DEFAULT_USER_AGENT, Extract, MAX_REGEX_INPUT_LEN, PaginationKind, SUBDOMAIN_RE_STR,
+            Search, Settings, exclusions};
 
 // Yahoo seems to always return 7 results per page.
 // Until we find a way to configure the number of results per page,
-// don't change this value.
+// don't change this value, it's also the largest `--per-page` allows.
 const PER_PAGE: usize = 7;
-const SETTINGS: Settings = Settings {
+/// Most subdomains excluded from a query
+const MAX_EXCLUDED: usize = 15;
+pub(super) const SETTINGS: Settings = Settings {
     name: "Yahoo",
     base_url: "https://search.yahoo.com/search",
     user_agent: DEFAULT_USER_AGENT,
     max_rounds: 50,
+    pagination: PaginationKind::OffsetPages,
+    passive: true,
+    priority: 20,
+    per_page: PER_PAGE,
+    max_per_page: 7,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 200,
 };
 
 #[derive(Extract)]
@@ -21,21 +34,32 @@ const SETTINGS: Settings = Settings {
 pub struct Yahoo {
     #[extract(domain)]
     domain: String,
+    per_page: usize,
 }
 
 impl Yahoo {
     pub fn new(domain: impl Into<String>) -> Self {
         Self {
             domain: domain.into(),
+            per_page: PER_PAGE,
         }
     }
+
+    /// Index of the first result of `page`
+    fn offset(&self, page: usize) -> usize {
+        // Yahoo's search results use a 1-based index.
+        // For a page size of 7, the pagination is as follows:
+        // `page` = 0 (1st page): results 1-7
+        // `page` = 1 (2nd page): results 8-14
+        // `page` = 2 (3rd page): results 15-21, etc.
+        (page * self.settings().per_page) + 1
+    }
 }
 
 impl Search for Yahoo {
-    fn next_query(&self, subdomains: &HashSet<String>) -> Option<Cow<'_, str>> {
-        let found = subdomains
-            .iter()
-            .take(15)
+    fn next_query<S: BuildHasher>(&self, subdomains: &HashSet<String, S>) -> Option<Cow<'_, str>> {
+        let found = exclusions(subdomains, MAX_EXCLUDED)
+            .into_iter()
             .fold(String::new(), |acc, d| format!("{acc} -domain:{d}"));
 
         let query = format!("site:{0} -domain:www.{0}{1}", self.domain, found);
@@ -43,7 +67,14 @@ impl Search for Yahoo {
     }
 
     fn settings(&self) -> Settings {
-        SETTINGS
+        Settings {
+            per_page: self.per_page,
+            ..SETTINGS
+        }
+    }
+
+    fn set_per_page(&mut self, per_page: usize) {
+        self.per_page = per_page;
     }
 
     async fn search(
@@ -51,19 +82,16 @@ impl Search for Yahoo {
         client: Client,
         query: &str,
         page: usize,
+        headers: &HeaderMap,
     ) -> Result<Response, reqwest::Error> {
-        // Yahoo's search results use a 1-based index.
-        // For `PER_PAGE` = 7, the pagination is as follows:
-        // `page` = 0 (1st page): results 1-7
-        // `page` = 1 (2nd page): results 8-14
-        // `page` = 2 (3rd page): results 15-21, etc.
-        let b = (page * PER_PAGE) + 1;
+        let b = self.offset(page);
 
         client
             .get(SETTINGS.base_url)
             .query(&[("p", query)])
             .query(&[("b", b)])
             .header(header::USER_AGENT, SETTINGS.user_agent)
+            .headers(headers.clone())
             .send()
             .await
     }
@@ -75,6 +103,18 @@ mod tests {
 
     use super::*;
 
+    #[rstest]
+    #[case::default(None, 2, 15)]
+    #[case::smaller(Some(5), 2, 11)]
+    #[case::first_page(Some(5), 0, 1)]
+    fn test_offset(#[case] per_page: Option<usize>, #[case] page: usize, #[case] expected: usize) {
+        let mut yahoo = Yahoo::new("example.com");
+        if let Some(n) = per_page {
+            yahoo.set_per_page(n);
+        }
+        assert_eq!(yahoo.offset(page), expected);
+    }
+
     #[rstest]
     #[case::empty(HashSet::new(), "site:example.com -domain:www.example.com")]
     #[case::single(
@@ -99,12 +139,10 @@ mod tests {
         let google = Yahoo::new(domain);
         let query = google.next_query(&subdomains).unwrap();
 
-        let (expected1, expected2) = (
-            "site:example.com -domain:www.example.com -domain:first.example.com -domain:second.example.com",
-            "site:example.com -domain:www.example.com -domain:second.example.com -domain:first.example.com",
+        assert_eq!(
+            query,
+            "site:example.com -domain:www.example.com -domain:first.example.com -domain:second.example.com"
         );
-
-        assert!(query == expected1 || query == expected2);
     }
 
     #[rstest]
@@ -0,0 +1,220 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+
+use reqwest::header::{self, HeaderMap};
+use reqwest::{Client, Response};
+use serde::Deserialize;
+
+use super::{DEFAULT_USER_AGENT, Extract, PaginationKind, Search, Settings};
+use crate::normalize;
+
+/// Results ZoomEye returns per page, it can't be changed
+const PER_PAGE: usize = 30;
+pub(super) const SETTINGS: Settings = Settings {
+    name: "ZoomEye",
+    base_url: "https://api.zoomeye.org/domain/search",
+    user_agent: DEFAULT_USER_AGENT,
+    max_rounds: 10,
+    pagination: PaginationKind::Cursor,
+    passive: true,
+    priority: 50,
+    per_page: 0,
+    max_per_page: 0,
+    backoff_base_secs: 1,
+    backoff_factor: 2,
+    delay_ms: 500,
+};
+/// Environment variable holding the ZoomEye API key
+const KEY_ENV: &str = "ZOOMEYE_API_KEY";
+/// Environment variables ZoomEye can't search without
+pub(super) const REQUIRED_ENV: &[&str] = &[KEY_ENV];
+
+pub struct ZoomEye {
+    domain: String,
+    api_key: Option<String>,
+    /// 1-based index of the next page to fetch
+    page: usize,
+    /// Total number of results, known after the first page
+    total: Option<usize>,
+}
+
+impl ZoomEye {
+    pub fn new(domain: impl Into<String>) -> Self {
+        Self {
+            domain: domain.into(),
+            api_key: std::env::var(KEY_ENV).ok(),
+            page: 1,
+            total: None,
+        }
+    }
+}
+
+impl Extract for ZoomEye {
+    fn extract(&mut self, input: &str) -> HashSet<String> {
+        match serde_json::from_str::<ZoomEyeResponse>(input) {
+            Ok(r) if !r.list.is_empty() => {
+                self.page += 1;
+                self.total = Some(r.total);
+                r.list.into_iter().map(|e| e.name).collect()
+            }
+            // an empty page or an error (e.g. bad key or no credits left) means there's nothing more
+            _ => {
+                self.total = Some(0);
+                HashSet::new()
+            }
+        }
+    }
+
+    /// Names may belong to other domains
+    fn clean(&self, raw: HashSet<String>) -> HashSet<String> {
+        raw.iter()
+            .map(|h| normalize::clean(h).to_ascii_lowercase())
+            .filter(|h| normalize::is_subdomain_of(h, &self.domain))
+            .collect()
+    }
+
+    fn domain(&self) -> Option<&str> {
+        Some(&self.domain)
+    }
+}
+
+impl Search for ZoomEye {
+    fn settings(&self) -> Settings {
+        SETTINGS
+    }
+
+    /// Returns `None` without an API key, though such an engine is never selected to run
+    fn next_query<S: BuildHasher>(&self, _: &HashSet<String, S>) -> Option<Cow<'_, str>> {
+        self.api_key.as_ref()?;
+
+        if self.total.is_some_and(|t| (self.page - 1) * PER_PAGE >= t) {
+            return None;
+        }
+
+        let base_url = SETTINGS.base_url;
+        let domain = &self.domain;
+        let page = self.page;
+        // type 1 searches subdomains, 0 would be associated domains
+        let query = format!("{base_url}?q={domain}&type=1&page={page}");
+
+        Some(Cow::Owned(query))
+    }
+
+    async fn search(
+        &self,
+        client: Client,
+        url: &str,
+        _: usize,
+        headers: &HeaderMap,
+    ) -> Result<Response, reqwest::Error> {
+        let key = self.api_key.clone().unwrap_or_default();
+        client
+            .get(url)
+            .header("API-KEY", key)
+            .header(header::USER_AGENT, SETTINGS.user_agent)
+            .header(header::ACCEPT, "application/json")
+            .headers(headers.clone())
+            .send()
+            .await
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ZoomEyeResponse {
+    /// Total number of results across all pages
+    #[serde(default)]
+    total: usize,
+    #[serde(default)]
+    list: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Entry {
+    name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> ZoomEye {
+        ZoomEye {
+            domain: "example.com".to_owned(),
+            api_key: Some("secret".to_owned()),
+            page: 1,
+            total: None,
+        }
+    }
+
+    #[test]
+    fn test_first_query() {
+        let zoomeye = engine();
+        let query = zoomeye.next_query(&HashSet::new()).unwrap();
+
+        assert_eq!(
+            query,
+            "https://api.zoomeye.org/domain/search?q=example.com&type=1&page=1"
+        );
+        assert!(!query.contains("secret"));
+    }
+
+    #[test]
+    fn test_extract() {
+        let input = r#"{
+            "status": 200,
+            "total": 45,
+            "type": 1,
+            "list": [
+                {"name": "app.example.com", "timestamp": "2024-01-01", "ip": ["93.184.216.34"]},
+                {"name": "API.example.com", "timestamp": "2024-01-01"},
+                {"name": "example.org"}
+            ],
+            "msg": "success"
+        }"#;
+
+        let mut zoomeye = engine();
+        let results = zoomeye.extract(input);
+        let results = zoomeye.clean(results);
+
+        let expected = HashSet::from(["app.example.com".to_owned(), "api.example.com".to_owned()]);
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn test_pagination_stops_after_total() {
+        let mut zoomeye = engine();
+        zoomeye.extract(r#"{"total": 45, "list": [{"name": "a.example.com"}]}"#);
+
+        // 45 results over pages of 30, so there's exactly one more page
+        let query = zoomeye.next_query(&HashSet::new()).unwrap();
+        assert!(query.ends_with("&page=2"));
+        zoomeye.extract(r#"{"total": 45, "list": [{"name": "b.example.com"}]}"#);
+        assert!(zoomeye.next_query(&HashSet::new()).is_none());
+    }
+
+    #[test]
+    fn test_pagination_stops_on_empty_page() {
+        let mut zoomeye = engine();
+        zoomeye.extract(r#"{"total": 1000, "list": []}"#);
+        assert!(zoomeye.next_query(&HashSet::new()).is_none());
+    }
+
+    #[test]
+    fn test_extract_error() {
+        let mut zoomeye = engine();
+        let results = zoomeye.extract(r#"{"error": "bad_request", "message": "invalid api key"}"#);
+
+        assert!(results.is_empty());
+        assert!(zoomeye.next_query(&HashSet::new()).is_none());
+    }
+
+    #[test]
+    fn test_no_credentials() {
+        let zoomeye = ZoomEye {
+            api_key: None,
+            ..engine()
+        };
+        assert!(zoomeye.next_query(&HashSet::new()).is_none());
+    }
+}
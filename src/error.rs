@@ -0,0 +1,48 @@
+use std::fmt;
+
+/// Process exit codes, part of the CLI's contract with scripts and CI
+pub mod exit_code {
+    /// At least one subdomain was found
+    pub const SUCCESS: i32 = 0;
+    /// The run completed but no subdomain was found
+    pub const NO_RESULTS: i32 = 1;
+    /// Invalid arguments, domain or configuration
+    pub const USAGE: i32 = 2;
+    /// Every engine failed to get a response
+    pub const ENGINES_FAILED: i32 = 3;
+    /// Interrupted by Ctrl-C
+    pub const INTERRUPTED: i32 = 130;
+}
+
+/// Errors that map to a specific exit code
+#[derive(Debug)]
+pub enum Error {
+    InvalidDomain,
+    AllEnginesFailed,
+    /// A proxy was given while proxies are turned off
+    ConflictingProxy,
+}
+
+impl Error {
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::InvalidDomain | Error::ConflictingProxy => exit_code::USAGE,
+            Error::AllEnginesFailed => exit_code::ENGINES_FAILED,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidDomain => f.write_str("Invalid domain"),
+            Error::AllEnginesFailed => f.write_str("every engine failed to get a response"),
+            Error::ConflictingProxy => f.write_str(
+                "a proxy can't be used with proxies turned off, drop --proxy or --no-proxy",
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -1,48 +1,1760 @@
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use enumerate::{Engine, EngineChoice, Enumerator, defaults_headers};
-use reqwest::Client;
+use anyhow::Context;
+use enumerate::cache::ResponseCache;
+use enumerate::delay::DelayKind;
+use enumerate::events::{EngineDoneCallback, ProgressCallback};
+use enumerate::hosts::{DEFAULT_PER_HOST_CONCURRENCY, HostLimiter};
+use enumerate::pacer::Pacer;
+use enumerate::{EXPECTED_PER_ENGINE, Engine, EngineChoice, EngineInfo, Enumerator, FirstSeen,
+                Found, defaults_headers};
+use output::{Format, Metadata, Report, ReportEntry};
+use owo_colors::{OwoColorize, Stream};
+use probe::Probe;
+use regex::Regex;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Proxy, Url};
+use resolve::{Resolved, SystemResolver};
+use serde::Serialize;
+use sources::{Contribution, Discovered, Source, Sources};
+use state::LastRun;
+use stats::RunStats;
 use strum::VariantArray;
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::{Id, JoinSet};
+use tokio::time::Instant;
+use tracing::{error, info, warn};
 
+pub mod apex;
+pub mod checkpoint;
 pub mod cli;
-mod enumerate;
+pub mod config;
+mod date;
+pub mod enumerate;
+mod error;
+pub mod merge;
+pub mod ndjson;
+mod normalize;
+pub mod output;
+mod pivot;
+mod probe;
+pub mod recurse;
+mod resolve;
+mod sources;
+pub mod state;
+pub mod stats;
+mod subdomain;
+pub mod tld;
+mod webhook;
 
-#[tracing::instrument(skip_all)]
-pub async fn run(domain: &str, choices: Vec<EngineChoice>) -> anyhow::Result<()> {
-    let client = Client::builder()
+pub use enumerate::stale_user_agent;
+pub use error::{Error, exit_code};
+pub use pivot::PivotScope;
+pub use subdomain::{SortBy, Subdomain};
+
+/// Options that control how a run is performed
+// mostly the CLI flags, which switch independent behaviors on and off
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Strip ports, paths and query strings from results
+    pub clean: bool,
+    /// Restrict the client to HTTP/1.1 instead of negotiating HTTP/2
+    pub http1_only: bool,
+    /// Send every request through this proxy instead of the ones set in the environment
+    pub proxy: Option<Url>,
+    /// Ignore the proxies set in the environment, e.g. `HTTPS_PROXY`
+    pub no_proxy: bool,
+    /// Look up other hosts sharing an IP with discovered subdomains via RapidDNS
+    pub pivot_sameip: bool,
+    /// Which hosts discovered by pivoting are kept
+    pub pivot_scope: PivotScope,
+    /// Only keep subdomains reported by at least this many engines
+    pub min_sources: usize,
+    /// Only keep subdomains this engine found
+    pub found_by: Option<EngineChoice>,
+    /// Print how many subdomains each engine found that no other engine did
+    pub sources_report: bool,
+    /// Global cap on each engine's number of rounds, never raises an engine's own limit
+    pub max_rounds: Option<usize>,
+    /// Overrides every engine's backoff after the first failed search
+    pub backoff_base_secs: Option<u64>,
+    /// Overrides every engine's backoff multiplier
+    pub backoff_factor: Option<u64>,
+    /// Maximum number of engines running at once, unlimited if `None`
+    pub concurrency: Option<usize>,
+    /// Run engines one at a time in a fixed order, so logs and progress are reproducible
+    pub sequential: bool,
+    /// Start engines that usually find the most first, which matters when `concurrency` is limited
+    pub weighted: bool,
+    /// Per-engine overrides of the priority used when `weighted`
+    pub priorities: HashMap<EngineChoice, u8>,
+    /// Maximum number of requests in flight to the same host, across engines
+    pub per_host_concurrency: usize,
+    /// Shortest time between any two requests, across engines
+    pub min_interval: Option<Duration>,
+    /// Once elapsed, engines that haven't started yet are skipped
+    pub engines_budget: Option<Duration>,
+    /// Per-engine overrides of the delay between pages
+    pub engine_delays: HashMap<EngineChoice, Duration>,
+    /// Per-engine overrides of the number of results asked for per page
+    pub per_page: HashMap<EngineChoice, usize>,
+    /// Also report wildcard certificate names, e.g. `*.example.com`, as found by crt.sh
+    pub keep_wildcards: bool,
+    /// How the delay between pages changes, each engine's default if `None`
+    pub delay_strategy: Option<DelayKind>,
+    /// Per-engine overrides of how the delay between pages changes
+    pub engine_delay_strategies: HashMap<EngineChoice, DelayKind>,
+    /// Per-engine extra headers sent with every search
+    pub engine_headers: HashMap<EngineChoice, HeaderMap>,
+    /// Seed for the jitter between pages, for reproducible timing
+    pub jitter_seed: Option<u64>,
+    /// Largest response body read, in bytes, larger ones count as failed searches
+    pub max_body_size: Option<usize>,
+    /// Failed searches in a row, before any success, after which an engine is given up on
+    pub circuit_breaker: Option<usize>,
+    /// Only keep subdomains that resolve to an IP address
+    pub resolve: bool,
+    /// Reverse resolve IP addresses to discover more subdomains
+    pub resolve_ptr: bool,
+    /// How long a single DNS lookup may take before the name counts as not resolving
+    pub resolve_timeout: Duration,
+    /// Keep subdomains that only resolve to private or loopback addresses
+    pub allow_private: bool,
+    /// Only keep subdomains that answer over HTTP(S)
+    pub probe: bool,
+    /// After probing, only keep subdomains that answered over HTTPS
+    pub https_only: bool,
+    /// Drop `www.` hosts whose bare form was also found
+    pub collapse_www: bool,
+    /// Only keep results matching any of these patterns
+    pub include: Vec<Regex>,
+    /// Drop results matching any of these patterns
+    pub exclude: Vec<Regex>,
+    /// File to write results to instead of stdout, `-` for stdout
+    pub output: Option<PathBuf>,
+    /// Print results to stdout as well as writing them to the output file
+    pub also_stdout: bool,
+    /// How results are printed to the terminal
+    pub format: Format,
+    /// Gzip the output file, implied when its name ends in `.gz`
+    pub compress: bool,
+    /// Field results are sorted by
+    pub sort_by: SortBy,
+    /// Mark subdomains whose first certificate was issued within this window as new
+    pub new_within: Option<Duration>,
+    /// Template rendered once with every result, instead of writing one subdomain per line
+    pub template: Option<String>,
+    /// Append subdomains to the output file as engines find them, see [`output::Incremental`]
+    pub incremental: bool,
+    /// Like `incremental`, but only append new subdomains this often
+    pub flush_interval: Option<Duration>,
+    /// Start the results with a comment describing the run, see [`output::Metadata`]
+    pub metadata_header: bool,
+    /// Print and save names without the domain, see [`output::strip_domain`]
+    pub strip_domain: bool,
+    /// Start CSV results with their header, only the first target of a run does
+    pub csv_header: bool,
+    /// Told about every engine's progress, for embedders rendering their own
+    pub progress: Option<ProgressCallback>,
+    /// Also run engines that contact the target's own servers, e.g. zone transfers
+    pub active: bool,
+    /// URL the JSON report is posted to once the run finishes
+    pub webhook: Option<Url>,
+    /// Also POST each subdomain to the webhook as soon as an engine finds it
+    pub webhook_stream: bool,
+    /// File recording how many subdomains each engine found, see [`state::LastRun`]
+    pub state: Option<PathBuf>,
+    /// Don't print progress to stdout, so it only carries results
+    pub quiet: bool,
+    /// Known subdomains to post-process, enumeration is skipped if no engines are selected
+    pub input: Option<Vec<String>>,
+    /// What engines found before the run was interrupted, those engines aren't run again
+    pub finished: HashMap<EngineChoice, Vec<String>>,
+    /// Told what each engine found once it's done, see [`checkpoint::Checkpoint`]
+    pub on_engine_done: Option<EngineDoneCallback>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            clean: true,
+            http1_only: false,
+            proxy: None,
+            no_proxy: false,
+            pivot_sameip: false,
+            pivot_scope: PivotScope::default(),
+            min_sources: 1,
+            found_by: None,
+            sources_report: false,
+            max_rounds: None,
+            backoff_base_secs: None,
+            backoff_factor: None,
+            concurrency: None,
+            sequential: false,
+            weighted: false,
+            priorities: HashMap::new(),
+            per_host_concurrency: DEFAULT_PER_HOST_CONCURRENCY,
+            min_interval: None,
+            engines_budget: None,
+            engine_delays: HashMap::new(),
+            per_page: HashMap::new(),
+            keep_wildcards: false,
+            delay_strategy: None,
+            engine_delay_strategies: HashMap::new(),
+            engine_headers: HashMap::new(),
+            jitter_seed: None,
+            max_body_size: None,
+            circuit_breaker: None,
+            resolve: false,
+            resolve_ptr: false,
+            resolve_timeout: resolve::DEFAULT_TIMEOUT,
+            allow_private: false,
+            probe: false,
+            https_only: false,
+            collapse_www: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            output: None,
+            also_stdout: false,
+            format: Format::default(),
+            compress: false,
+            sort_by: SortBy::default(),
+            new_within: None,
+            template: None,
+            flush_interval: None,
+            metadata_header: false,
+            strip_domain: false,
+            csv_header: true,
+            progress: None,
+            active: false,
+            incremental: false,
+            webhook: None,
+            webhook_stream: false,
+            state: None,
+            quiet: false,
+            input: None,
+            finished: HashMap::new(),
+            on_engine_done: None,
+        }
+    }
+}
+
+/// Build and engine metadata, reported by `--version-json`
+#[derive(Debug, Serialize)]
+struct VersionInfo {
+    version: &'static str,
+    commit: &'static str,
+    engines: Vec<EngineInfo>,
+}
+
+/// Build and engine metadata as pretty-printed JSON
+///
+/// # Errors
+///
+/// If serializing fails
+pub fn version_json() -> serde_json::Result<String> {
+    let info = VersionInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        commit: env!("S7R_GIT_COMMIT"),
+        engines: EngineChoice::VARIANTS
+            .iter()
+            .map(|&c| EngineInfo::from(Engine::settings_for(c)))
+            .collect(),
+    };
+
+    serde_json::to_string_pretty(&info)
+}
+
+/// Longest a connection to any host may take to open, however long the request then takes
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Build the HTTP client shared by all engines
+fn build_client(opts: &Options) -> anyhow::Result<Client> {
+    // checked first, so a misconfigured run fails before anything is sent
+    if opts.proxy.is_some() && opts.no_proxy {
+        return Err(Error::ConflictingProxy.into());
+    }
+
+    let mut builder = Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
         .default_headers(defaults_headers())
         .cookie_store(true)
-        .gzip(true) // enable gzip compression
-        .build()?;
+        // decode every encoding advertised in `defaults_headers`
+        .gzip(true)
+        .deflate(true)
+        .brotli(true);
 
-    let engines: Vec<Engine> = if choices.is_empty() {
-        Engine::from_iter(EngineChoice::VARIANTS.to_owned(), domain)
-    } else {
-        Engine::from_iter(choices, domain)
+    // Bing occasionally stalls when requests are multiplexed over HTTP/2
+    if opts.http1_only {
+        builder = builder.http1_only();
+    }
+    if opts.no_proxy {
+        builder = builder.no_proxy();
+    }
+    if let Some(proxy) = &opts.proxy {
+        let proxy = Proxy::all(proxy.as_str()).with_context(|| format!("invalid proxy {proxy}"))?;
+        builder = builder.proxy(proxy);
+    }
+
+    builder.build().context("failed to build HTTP client")
+}
+
+/// Engines to enumerate with, every engine if none are chosen
+///
+/// When a list of known subdomains is given and no engines are chosen,
+/// enumeration is skipped entirely and only post-processing runs.
+/// An engine chosen more than once only runs once.
+/// Engines that contact the target itself are left out unless `active` is set.
+fn select_engines(
+    choices: Vec<EngineChoice>,
+    domain: &str,
+    has_input: bool,
+    active: bool,
+) -> Result<Vec<Engine>, Error> {
+    let is_allowed = |c: &EngineChoice| active || c.is_passive();
+    let choices = dedup_engines(choices);
+    match (choices.is_empty(), has_input) {
+        (true, true) => Ok(Vec::new()),
+        (true, false) => Engine::from_iter(
+            EngineChoice::VARIANTS
+                .iter()
+                .copied()
+                .filter(is_allowed)
+                .filter(|&c| has_credentials(c)),
+            domain,
+        ),
+        (false, _) => {
+            let (allowed, skipped): (Vec<_>, Vec<_>) = choices.into_iter().partition(is_allowed);
+            for c in skipped {
+                warn!(engine = c.name(), "active engines only run with --active");
+            }
+            Engine::from_iter(allowed.into_iter().filter(|&c| has_credentials(c)), domain)
+        }
+    }
+}
+
+/// Engines already warned about for missing credentials, once per process rather than per target
+static WARNED_MISSING_ENV: LazyLock<Mutex<HashSet<EngineChoice>>> = LazyLock::new(Mutex::default);
+
+/// Whether the engine has the credentials it needs, warning once about those it doesn't
+fn has_credentials(choice: EngineChoice) -> bool {
+    let missing = choice.missing_env();
+    if missing.is_empty() {
+        return true;
+    }
+    if WARNED_MISSING_ENV.lock().unwrap().insert(choice) {
+        warn!(
+            engine = choice.name(),
+            missing = missing.join(", "),
+            "skipping the engine, its credentials aren't set"
+        );
+    }
+    false
+}
+
+/// Put engines in the order they're declared in, regardless of the order they were chosen in
+fn sort_engines(engines: &mut [Engine]) {
+    engines.sort_by_key(|e| EngineChoice::from(e));
+}
+
+/// Put engines with a higher priority first, as they're spawned and get a slot in that order
+///
+/// `overrides` replace engines' own priorities, engines with the same one keep the order they
+/// were chosen in.
+fn prioritize_engines(engines: &mut [Engine], overrides: &HashMap<EngineChoice, u8>) {
+    engines.sort_by_key(|e| {
+        let choice = EngineChoice::from(e);
+        let priority = overrides.get(&choice).copied();
+        std::cmp::Reverse(priority.unwrap_or_else(|| choice.priority()))
+    });
+}
+
+/// Drop repeated engines, keeping the first of each in order
+fn dedup_engines(choices: Vec<EngineChoice>) -> Vec<EngineChoice> {
+    let mut seen = HashSet::with_capacity(choices.len());
+    let total = choices.len();
+    let unique: Vec<_> = choices.into_iter().filter(|c| seen.insert(*c)).collect();
+    if unique.len() < total {
+        warn!(
+            removed = total - unique.len(),
+            "engines chosen more than once only run once"
+        );
+    }
+    unique
+}
+
+/// Enumerate subdomains of `domain` and print them, returning what was found
+#[tracing::instrument(skip_all)]
+pub async fn run(
+    domain: &str,
+    choices: Vec<EngineChoice>,
+    opts: Options,
+) -> anyhow::Result<RunStats> {
+    // open the output early so a bad path fails before spending time on enumeration
+    let mut out = output::writer(
+        opts.output.as_deref(),
+        opts.also_stdout,
+        opts.format,
+        opts.compress,
+    )?;
+    let started = Instant::now();
+
+    let client = build_client(&opts)?;
+
+    let mut progress = Vec::new();
+    let incremental = opts.incremental || opts.flush_interval.is_some();
+    let journal = match opts.output.as_deref() {
+        Some(path) if incremental && !output::is_stdout(path) => {
+            let journal = output::Incremental::open(path)?;
+            let (tx, rx) = mpsc::unbounded_channel();
+            progress.push(tx);
+            Some(match opts.flush_interval {
+                Some(period) => tokio::spawn(journal.consume_every(rx, period)),
+                None => tokio::task::spawn_blocking(move || journal.consume(rx)),
+            })
+        }
+        _ => None,
+    };
+    let stream = match &opts.webhook {
+        Some(url) if opts.webhook_stream => {
+            let (tx, rx) = mpsc::unbounded_channel();
+            progress.push(tx);
+            let (client, url) = (client.clone(), url.clone());
+            Some(tokio::spawn(webhook::stream(
+                client,
+                url,
+                domain.to_owned(),
+                rx,
+            )))
+        }
+        _ => None,
     };
 
-    let subdomains = Arc::new(Mutex::new(HashSet::<String>::new()));
+    // senders are dropped once collected, which lets the consumers finish
+    let subdomains = collect_with(domain, choices, &opts, &client, progress).await;
+    if let Some(stream) = stream {
+        stream.await?;
+    }
+    let Collected {
+        subdomains,
+        per_engine,
+        contributions,
+    } = match journal {
+        Some(journal) => {
+            let journal = journal.await??;
+            // keep the partial results if the run failed, there's nothing to replace them with
+            let collected = subdomains?;
+            journal.finish()?;
+            collected
+        }
+        None => subdomains?,
+    };
+    if opts.sources_report {
+        print_contributions(&contributions, opts.quiet);
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| i64::try_from(d.as_secs()).unwrap_or(i64::MAX));
+    let is_new = |s: &Subdomain| opts.new_within.is_some_and(|w| s.is_new(now, w));
+
+    if !opts.quiet {
+        println!();
+    }
 
-    let mut join_set = tokio::task::JoinSet::new();
+    let report = Report {
+        domain,
+        count: subdomains.len(),
+        elapsed_secs: started.elapsed().as_secs_f64(),
+        subdomains: subdomains
+            .into_iter()
+            .map(|s| {
+                let new = is_new(&s);
+                report_entry(s, started, new)
+            })
+            .collect(),
+    };
+    if let Some(template) = opts.template.as_deref() {
+        let rendered = output::render(template, &report)?;
+        out.write_results(&[rendered.trim_end_matches('\n').to_owned()])?;
+    } else {
+        // a comment line would make the structured formats unparsable
+        if opts.metadata_header && !opts.format.is_structured() {
+            out.write_metadata(&Metadata {
+                domain,
+                timestamp: now,
+                engines: per_engine.len(),
+                found: report.count,
+            })?;
+        }
+        // names are printed and saved without the domain, the webhook still gets them whole
+        let stripped;
+        let shown = if opts.strip_domain {
+            stripped = Report {
+                subdomains: report
+                    .subdomains
+                    .iter()
+                    .map(|s| ReportEntry {
+                        name: output::strip_domain(&s.name, domain).to_owned(),
+                        ..s.clone()
+                    })
+                    .collect(),
+                ..report
+            };
+            &stripped
+        } else {
+            &report
+        };
+        match opts.format {
+            Format::Dnsx => {
+                let lines: Vec<_> = shown.subdomains.iter().flat_map(output::dnsx).collect();
+                out.write_results(&lines)?;
+            }
+            Format::Json => out.write_results(&[output::json(shown)?])?,
+            Format::Csv => {
+                out.write_results(&output::csv(&shown.subdomains, opts.csv_header))?;
+            }
+            Format::Text | Format::None => {
+                let lines: Vec<String> = shown.subdomains.iter().map(|s| s.name.clone()).collect();
+                let new: Vec<bool> = shown.subdomains.iter().map(|s| s.new).collect();
+                out.write_marked(&lines, &new)?;
+            }
+        }
+    }
+
+    if let Some(url) = &opts.webhook {
+        webhook::post(&client, url, &report).await;
+    }
+
+    Ok(RunStats {
+        domain: domain.to_owned(),
+        subdomains: report.subdomains.into_iter().map(|s| s.name).collect(),
+        engines: per_engine,
+        elapsed: started.elapsed(),
+    })
+}
+
+/// Enumerate subdomains of `domain` and post-process them, without writing them anywhere
+///
+/// # Errors
+///
+/// If `domain` is invalid, the HTTP client can't be built or every engine fails
+pub async fn collect(
+    domain: &str,
+    choices: Vec<EngineChoice>,
+    opts: &Options,
+) -> anyhow::Result<Vec<Subdomain>> {
+    let client = build_client(opts)?;
+    let collected = collect_with(domain, choices, opts, &client, Vec::new()).await?;
+    Ok(collected.subdomains)
+}
+
+/// What [`collect_with`] found, and what each engine contributed to it
+struct Collected {
+    subdomains: Vec<Subdomain>,
+    /// How many names each engine that ran found, 0 for those that found nothing
+    per_engine: BTreeMap<EngineChoice, usize>,
+    contributions: Vec<Contribution>,
+}
+
+/// Like [`collect`], also sending each subdomain to every `progress` channel as soon as
+/// an engine finds it
+///
+/// Only names an engine found are sent, before any post-processing.
+async fn collect_with(
+    domain: &str,
+    choices: Vec<EngineChoice>,
+    opts: &Options,
+    client: &Client,
+    progress: Vec<UnboundedSender<String>>,
+) -> anyhow::Result<Collected> {
+    let mut engines = select_engines(choices, domain, opts.input.is_some(), opts.active)?;
+    if opts.sequential {
+        sort_engines(&mut engines);
+    } else if opts.weighted {
+        prioritize_engines(&mut engines, &opts.priorities);
+    }
+    let enumerating = !engines.is_empty();
+    if let Some(found_by) = opts.found_by
+        && !engines.iter().any(|e| EngineChoice::from(e) == found_by)
+    {
+        warn!(
+            engine = found_by.name(),
+            "only listing results of an engine that isn't run"
+        );
+    }
+    // engines that find nothing still ran, they're counted with 0
+    let mut per_engine: BTreeMap<EngineChoice, usize> =
+        engines.iter().map(|e| (EngineChoice::from(e), 0)).collect();
+
+    let Enumerated {
+        mut found,
+        reached,
+        last_run,
+        first_seen,
+    } = enumerate_engines(engines, domain, opts, client, progress).await;
+
+    if let Some(path) = opts.state.as_deref().filter(|_| enumerating)
+        && let Err(e) = last_run.save(path)
+    {
+        warn!(err = %e, "failed to save the state of this run");
+    }
+
+    if enumerating && reached == 0 {
+        return Err(Error::AllEnginesFailed.into());
+    }
+
+    if let Some(input) = opts.input.clone() {
+        found.record(Source::Input, input, false);
+    }
+    let Aggregate {
+        mut sources,
+        discovered,
+    } = found;
+    // results are only comparable to the queried domain once cleaned to a bare hostname
+    if opts.clean {
+        sources.retain(|s, _| apex::same_apex(s, domain));
+    }
+    let contributions = sources::contributions(&sources);
+    per_engine.extend(contributions.iter().filter_map(|c| match c.engine {
+        Source::Engine(engine) => Some((engine, c.total)),
+        Source::Input => None,
+    }));
+    sources::retain_min(&mut sources, opts.min_sources);
+    if let Some(engine) = opts.found_by {
+        sources::retain_found_by(&mut sources, engine);
+    }
+
+    let Processed {
+        subdomains,
+        resolved,
+        probes,
+    } = post_process(sources.into_keys().collect(), domain, opts, client).await?;
+    let mut subdomains: Vec<_> = subdomains
+        .into_iter()
+        .map(|name| {
+            Subdomain::enriched(
+                name,
+                resolved.as_ref(),
+                probes.as_ref(),
+                &first_seen,
+                &discovered,
+            )
+        })
+        .collect();
+    subdomain::sort(&mut subdomains, opts.sort_by);
+    Ok(Collected {
+        subdomains,
+        per_engine,
+        contributions,
+    })
+}
+
+/// What the engines of a run found, before any post-processing
+struct Enumerated {
+    found: Aggregate,
+    /// Number of engines that got at least one successful response
+    reached: usize,
+    /// Engines that fail or panic are left at 0
+    last_run: LastRun,
+    /// Only gathered when it's going to be shown
+    first_seen: FirstSeen,
+}
+
+/// Run the engines, as many at once as the options allow, and gather what they found
+///
+/// Those that ran before the run was interrupted only have their results merged.
+async fn enumerate_engines(
+    engines: Vec<Engine>,
+    domain: &str,
+    opts: &Options,
+    client: &Client,
+    progress: Vec<UnboundedSender<String>>,
+) -> Enumerated {
+    // engines overlap heavily, so this overestimates the final size,
+    // but it's cheap and avoids rehashing as results from each engine come in
+    let capacity = engines.len() * EXPECTED_PER_ENGINE;
+    let sources = Arc::new(Mutex::new(Aggregate::with_capacity(capacity)));
+    let Options {
+        clean,
+        quiet,
+        sequential,
+        ..
+    } = *opts;
+    let reached = Arc::new(AtomicUsize::new(0));
+    let last_run = Arc::new(Mutex::new(LastRun::default()));
+    let state = RunState::new(opts);
+    let schedule = Schedule::new(opts.concurrency, opts.engines_budget);
+
+    let (finished, engines): (Vec<_>, Vec<_>) = engines
+        .into_iter()
+        .partition(|e| opts.finished.contains_key(&EngineChoice::from(e)));
+    for choice in finished.iter().map(EngineChoice::from) {
+        let found = &opts.finished[&choice];
+        reached.fetch_add(1, Ordering::Relaxed);
+        last_run.lock().unwrap().engines.insert(choice, found.len());
+        sources
+            .lock()
+            .unwrap()
+            .record(Source::Engine(choice), found.iter().cloned(), clean);
+    }
+
+    let total = engines.len();
+    let mut join_set = JoinSet::new();
+    let mut names = HashMap::with_capacity(engines.len());
     for ng in engines {
-        let r = subdomains.clone();
+        let choice = EngineChoice::from(&ng);
+        let e = state.enumerator(ng, opts);
+        let name = e.name();
+        let Some(slot) = schedule.acquire().await else {
+            warn!(
+                skipped = total - names.len(),
+                "engines time budget elapsed, not starting the remaining engines"
+            );
+            break;
+        };
+        let r = sources.clone();
         let c = client.clone();
-        join_set.spawn(async move {
+        let reached = reached.clone();
+        let l = last_run.clone();
+        l.lock().unwrap().engines.insert(choice, 0);
+        let progress = progress.clone();
+        let on_done = opts.on_engine_done.clone();
+        let target = domain.to_owned();
+        let handle = join_set.spawn(async move {
+            let _slot = slot;
+            if !quiet {
+                e.print_banner();
+            }
+            let Some(found) = e.enumerate(c).await else {
+                return;
+            };
+            reached.fetch_add(1, Ordering::Relaxed);
+            let found = normalize::normalize(found, clean);
+            l.lock().unwrap().engines.insert(choice, found.len());
+            send_progress(&progress, &found, &target, clean, sequential);
+            if let Some(on_done) = &on_done {
+                on_done.call(choice, &found);
+            }
+            r.lock()
+                .unwrap()
+                .record(Source::Engine(choice), found, clean);
+        });
+        names.insert(handle.id(), name);
+        if sequential {
+            // wait for the engine before starting the next one, so nothing interleaves
+            join_engines(std::mem::take(&mut join_set), &names).await;
+        }
+    }
+
+    join_engines(join_set, &names).await;
+
+    Enumerated {
+        found: std::mem::take(&mut *sources.lock().unwrap()),
+        reached: reached.load(Ordering::Relaxed),
+        last_run: std::mem::take(&mut *last_run.lock().unwrap()),
+        first_seen: state
+            .first_seen
+            .map(|f| std::mem::take(&mut *f.lock().unwrap()))
+            .unwrap_or_default(),
+    }
+}
+
+/// What the engines of a run share
+struct RunState {
+    /// Only gathered when it's going to be shown
+    first_seen: Option<Arc<Mutex<FirstSeen>>>,
+    host_limiter: HostLimiter,
+    pacer: Option<Pacer>,
+    /// Engines building the same request within the run only send it once
+    cache: ResponseCache,
+    /// Search engines exclude what any engine found so far from their queries
+    found: Found,
+}
+
+impl RunState {
+    fn new(opts: &Options) -> Self {
+        Self {
+            first_seen: opts
+                .new_within
+                .map(|_| Arc::new(Mutex::new(FirstSeen::new()))),
+            host_limiter: HostLimiter::new(opts.per_host_concurrency),
+            pacer: opts.min_interval.map(Pacer::new),
+            cache: ResponseCache::default(),
+            found: Found::default(),
+        }
+    }
+
+    /// An enumerator for `engine` set up from `opts`, sharing this state with the others
+    fn enumerator(&self, engine: Engine, opts: &Options) -> Enumerator<Engine> {
+        let choice = EngineChoice::from(&engine);
+        let delay = opts.engine_delays.get(&choice).copied();
+        let headers = opts
+            .engine_headers
+            .get(&choice)
+            .cloned()
+            .unwrap_or_default();
+        Enumerator::new(engine)
+            .with_max_rounds(opts.max_rounds)
+            .with_backoff(opts.backoff_base_secs, opts.backoff_factor)
+            .with_delay(delay)
+            .with_per_page(opts.per_page.get(&choice).copied())
+            .with_keep_wildcards(opts.keep_wildcards)
+            .with_delay_strategy(
+                opts.engine_delay_strategies
+                    .get(&choice)
+                    .copied()
+                    .or(opts.delay_strategy),
+            )
+            .with_jitter_seed(opts.jitter_seed)
+            .with_max_body_size(opts.max_body_size)
+            .with_circuit_breaker(opts.circuit_breaker)
+            .with_first_seen(self.first_seen.clone())
+            .with_headers(headers)
+            .with_host_limiter(Some(self.host_limiter.clone()))
+            .with_pacer(self.pacer.clone())
+            .with_cache(Some(self.cache.clone()))
+            .with_progress(opts.progress.clone())
+            .with_shared(Some(self.found.clone()))
+    }
+}
+
+/// Send the names an engine found to every `progress` channel, sorted if `sorted`
+///
+/// With `clean`, names are sent as the final results will have them, out of scope ones aren't.
+fn send_progress(
+    progress: &[UnboundedSender<String>],
+    found: &HashSet<String>,
+    target: &str,
+    clean: bool,
+    sorted: bool,
+) {
+    if progress.is_empty() {
+        return;
+    }
+
+    let mut names: Vec<_> = found
+        .iter()
+        .map(|s| {
+            if clean {
+                sources::canonical(s)
+            } else {
+                s.clone()
+            }
+        })
+        .filter(|s| !clean || apex::same_apex(s, target))
+        .collect();
+    if sorted {
+        names.sort_unstable();
+    }
+    for (s, tx) in names
+        .iter()
+        .flat_map(|s| progress.iter().map(move |tx| (s, tx)))
+    {
+        // consumers only stop early if they failed, which they report themselves
+        let _ = tx.send(s.clone());
+    }
+}
+
+/// Subdomains left once post-processed, with what resolving and probing them found
+struct Processed {
+    subdomains: HashSet<String>,
+    resolved: Option<Resolved>,
+    probes: Option<HashMap<String, Probe>>,
+}
+
+/// Pivot, resolve, probe and filter the subdomains found, as the options ask
+async fn post_process(
+    mut subdomains: HashSet<String>,
+    domain: &str,
+    opts: &Options,
+    client: &Client,
+) -> anyhow::Result<Processed> {
+    if opts.pivot_sameip {
+        let pivoted = pivot::sameip(client.clone(), domain, &subdomains, opts.pivot_scope).await;
+        subdomains.extend(pivoted);
+    }
+
+    if opts.collapse_www {
+        normalize::collapse_www(&mut subdomains, domain);
+    }
+
+    let mut resolved = None;
+    if opts.resolve {
+        let dns = Arc::new(SystemResolver::new()?);
+        let mut ips = resolve::resolve(dns.clone(), subdomains, opts.resolve_timeout).await;
+
+        if opts.resolve_ptr {
+            let found = resolve::reverse(dns.clone(), domain, &ips, opts.resolve_timeout).await;
+            ips.extend(resolve::resolve(dns, found, opts.resolve_timeout).await);
+        }
+
+        if !opts.allow_private {
+            resolve::retain_public(&mut ips);
+        }
+
+        subdomains = ips.keys().cloned().collect();
+        resolved = Some(ips);
+    }
+
+    let mut probes = None;
+    if opts.probe {
+        let mut found = probe::probe_all(client.clone(), subdomains).await;
+        if opts.https_only {
+            probe::retain_https(&mut found);
+        }
+        subdomains = found.keys().cloned().collect();
+        probes = Some(found);
+    }
+
+    normalize::filter(&mut subdomains, &opts.include, &opts.exclude);
+
+    Ok(Processed {
+        subdomains,
+        resolved,
+        probes,
+    })
+}
+
+/// Print how many names each engine found, and how many only it found
+///
+/// Goes to stderr when `quiet`, as stdout then only carries the results.
+fn print_contributions(contributions: &[Contribution], quiet: bool) {
+    let stream = if quiet {
+        Stream::Stderr
+    } else {
+        Stream::Stdout
+    };
+    let mut report = String::from("\n");
+    for c in contributions {
+        let _ = writeln!(
+            report,
+            "{} {}: {} unique / {} total",
+            "[-]".if_supports_color(stream, |t| t.blue()),
+            c.engine,
+            c.unique,
+            c.total
+        );
+    }
+    if quiet {
+        eprint!("{report}");
+    } else {
+        print!("{report}");
+    }
+}
+
+/// The first query each selected engine would send for `domain`, without sending anything
+///
+/// # Errors
+///
+/// If `domain` is invalid
+pub fn dry_run(
+    domain: &str,
+    choices: Vec<EngineChoice>,
+    active: bool,
+) -> Result<Vec<(&'static str, Option<String>)>, Error> {
+    let planned = select_engines(choices, domain, false, active)?
+        .into_iter()
+        .map(|ng| {
             let e = Enumerator::new(ng);
-            e.print_banner();
-            let found = e.enumerate(c).await;
-            let mut guard = r.lock().unwrap();
-            guard.extend(found.into_iter());
+            (e.name(), e.first_query())
+        })
+        .collect();
+    Ok(planned)
+}
+
+/// Domain used to check that engines are working
+const SELFTEST_DOMAIN: &str = "example.com";
+
+/// Run every engine's healthcheck concurrently and report which are working
+///
+/// Active engines are left out, they'd contact the test domain's own servers.
+///
+/// # Errors
+///
+/// If the HTTP client can't be built
+pub async fn selftest(opts: Options) -> anyhow::Result<Vec<(&'static str, Result<(), String>)>> {
+    let client = build_client(&opts)?;
+    let engines = select_engines(Vec::new(), SELFTEST_DOMAIN, false, false)?;
+
+    let mut join_set = JoinSet::new();
+    for ng in engines {
+        let e = Enumerator::new(ng);
+        let c = client.clone();
+        join_set.spawn(async move { (e.name(), e.healthcheck(c).await) });
+    }
+
+    let mut report = join_set.join_all().await;
+    report.sort_unstable_by_key(|(name, _)| *name);
+
+    Ok(report)
+}
+
+/// Limits how many engines run at once and until when new ones may start
+struct Schedule {
+    permits: Option<Arc<Semaphore>>,
+    deadline: Option<Instant>,
+}
+
+/// A running engine's place in the schedule, freed when dropped
+struct Slot {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Schedule {
+    fn new(concurrency: Option<usize>, budget: Option<Duration>) -> Self {
+        Self {
+            permits: concurrency.map(|n| Arc::new(Semaphore::new(n))),
+            deadline: budget.map(|b| Instant::now() + b),
+        }
+    }
+
+    /// Wait for a slot to start another engine, `None` once the budget has elapsed
+    async fn acquire(&self) -> Option<Slot> {
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            return None;
+        }
+
+        let Some(permits) = &self.permits else {
+            return Some(Slot { _permit: None });
+        };
+
+        let permit = permits.clone().acquire_owned();
+        let permit = match self.deadline {
+            Some(d) => tokio::time::timeout_at(d, permit).await.ok()?,
+            None => permit.await,
+        };
+
+        let permit = permit.expect("semaphore is never closed");
+        Some(Slot {
+            _permit: Some(permit),
+        })
+    }
+}
+
+/// `subdomain` as reports show it, with when it was discovered relative to `started`
+fn report_entry(subdomain: Subdomain, started: Instant, new: bool) -> ReportEntry {
+    let discovered_ms = subdomain.discovered.map(|t| {
+        let elapsed = t.saturating_duration_since(started);
+        u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+    });
+    ReportEntry {
+        new,
+        ips: subdomain.ips.iter().map(ToString::to_string).collect(),
+        status: subdomain.status.map(|c| c.as_u16()),
+        discovered_ms,
+        name: subdomain.name,
+    }
+}
+
+/// What every source reported, merged as each one finishes
+#[derive(Debug, Default)]
+struct Aggregate {
+    sources: Sources,
+    discovered: Discovered,
+}
+
+impl Aggregate {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            sources: Sources::with_capacity(capacity),
+            discovered: Discovered::with_capacity(capacity),
+        }
+    }
+
+    /// Record what `source` found, under their canonical form if `clean`, noting when names
+    /// no source reported before came in
+    fn record<I>(&mut self, source: Source, found: I, clean: bool)
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let found: Vec<String> = found.into_iter().collect();
+        let now = Instant::now();
+        for name in &found {
+            let name = if clean {
+                sources::canonical(name)
+            } else {
+                name.clone()
+            };
+            self.discovered.entry(name).or_insert(now);
+        }
+
+        if clean {
+            let noncanonical = sources::record_canonical(&mut self.sources, source, found);
+            if noncanonical > 0 {
+                info!(engine = %source, noncanonical, "engine reported names in a noncanonical form");
+            }
+        } else {
+            sources::record(&mut self.sources, source, found);
+        }
+    }
+}
+
+/// Wait for every engine task to finish
+///
+/// A panicking engine is logged and skipped, so results from the other engines are kept.
+async fn join_engines(mut join_set: JoinSet<()>, names: &HashMap<Id, &'static str>) {
+    while let Some(res) = join_set.join_next().await {
+        if let Err(e) = res {
+            let name = names.get(&e.id()).copied().unwrap_or("unknown");
+            if e.is_panic() {
+                error!(engine = name, "engine panicked, its results are lost");
+            } else {
+                error!(engine = name, err = ?e, "engine task failed");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::ACCEPT_ENCODING;
+    use tokio::net::TcpListener;
+    use tokio_rustls::LazyConfigAcceptor;
+    use tokio_rustls::rustls::server::Acceptor;
+    use wiremock::matchers::{headers, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::enumerate::Search;
+    use crate::enumerate::events::ProgressEvent;
+    use crate::enumerate::mock::Mock as MockEngine;
+    use crate::enumerate::tests::Logs;
+
+    /// Protocols a client built with `http1_only` offers in its TLS handshake
+    ///
+    /// HTTP/2 is only spoken over TLS when both sides agree to it with ALPN, so what's offered
+    /// is what an HTTP/2 capable server could pick.
+    async fn offered_protocols(http1_only: bool) -> Vec<String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let start = LazyConfigAcceptor::new(Acceptor::default(), stream)
+                .await
+                .unwrap();
+            let alpn = start.client_hello().alpn().into_iter().flatten();
+            alpn.map(|p| String::from_utf8_lossy(p).into_owned())
+                .collect()
         });
+
+        let opts = Options {
+            http1_only,
+            ..Options::default()
+        };
+        let client = build_client(&opts).unwrap();
+        // the handshake is dropped once the client's hello is read
+        let _ = client.get(format!("https://{addr}/")).send().await;
+
+        server.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_build_client_http1_only() {
+        assert!(offered_protocols(false).await.contains(&"h2".to_owned()));
+        assert_eq!(offered_protocols(true).await, ["http/1.1"]);
     }
 
-    join_set.join_all().await;
+    #[tokio::test]
+    async fn test_run_conflicting_proxy() {
+        let server = MockServer::start().await;
+        let opts = Options {
+            proxy: Some(Url::parse(&server.uri()).unwrap()),
+            no_proxy: true,
+            format: Format::None,
+            ..Options::default()
+        };
+
+        let err = run("example.com", vec![EngineChoice::CrtSh], opts)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::ConflictingProxy)
+        ));
+        assert!(err.to_string().contains("--proxy or --no-proxy"), "{err}");
+        // failed before any engine sent a request, through the proxy or not
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_build_client_proxy() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let opts = Options {
+            proxy: Some(Url::parse(&server.uri()).unwrap()),
+            ..Options::default()
+        };
+        let client = build_client(&opts).unwrap();
+
+        // the proxy gets requests for any host
+        let resp = client.get("http://app.example.com/").send().await.unwrap();
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_healthcheck() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("app.example.com"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut engine = MockEngine::new("example.com", "");
+        engine.server = Some(server.uri());
+
+        assert_eq!(engine.healthcheck(Client::new()).await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_healthcheck_error_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let mut engine = MockEngine::new("example.com", "");
+        engine.server = Some(server.uri());
+
+        let err = engine.healthcheck(Client::new()).await.unwrap_err();
+        assert!(err.contains("503"));
+    }
+
+    #[tokio::test]
+    async fn test_healthcheck_blocked() {
+        let engine = MockEngine::new("example.com", "unusual traffic");
+        assert_eq!(
+            engine.healthcheck(Client::new()).await,
+            Err("blocked".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_client_identity_encoding() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(headers(
+                ACCEPT_ENCODING,
+                vec!["gzip", "deflate", "br", "identity"],
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Encoding", "identity")
+                    .set_body_string("app.example.com"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = build_client(&Options::default()).unwrap();
+        let body = client
+            .get(server.uri())
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        assert_eq!(body, "app.example.com");
+    }
+
+    #[test]
+    fn test_version_json() {
+        let json: serde_json::Value = serde_json::from_str(&version_json().unwrap()).unwrap();
+
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert!(json["commit"].is_string());
+
+        let engines = json["engines"].as_array().unwrap();
+        assert_eq!(engines.len(), EngineChoice::VARIANTS.len());
+        assert!(
+            engines
+                .iter()
+                .any(|e| e["name"] == "CrtSh" && e["max_rounds"] == 1)
+        );
+        assert!(engines.iter().all(|e| e["passive"].is_boolean()));
+    }
+
+    /// Engines left out for their credentials missing from the environment the tests run in
+    fn without_credentials() -> usize {
+        EngineChoice::VARIANTS
+            .iter()
+            .filter(|c| !c.missing_env().is_empty())
+            .count()
+    }
+
+    #[rstest::rstest]
+    #[case::input_only(vec![], true, false, 0)]
+    // every engine but the active AXFR
+    #[case::all(vec![], false, false, EngineChoice::VARIANTS.len() - 1 - without_credentials())]
+    #[case::all_active(vec![], false, true, EngineChoice::VARIANTS.len() - without_credentials())]
+    #[case::chosen_with_input(vec![EngineChoice::CrtSh], true, false, 1)]
+    #[case::repeated(
+        vec![EngineChoice::Google, EngineChoice::Google, EngineChoice::Bing, EngineChoice::Google],
+        false,
+        false,
+        2
+    )]
+    #[case::active_without_flag(vec![EngineChoice::Axfr, EngineChoice::CrtSh], false, false, 1)]
+    #[case::active_with_flag(vec![EngineChoice::Axfr, EngineChoice::CrtSh], false, true, 2)]
+    fn test_select_engines(
+        #[case] choices: Vec<EngineChoice>,
+        #[case] has_input: bool,
+        #[case] active: bool,
+        #[case] expected: usize,
+    ) {
+        assert_eq!(
+            select_engines(choices, "example.com", has_input, active)
+                .unwrap()
+                .len(),
+            expected
+        );
+    }
+
+    #[rstest::rstest]
+    #[case(vec![EngineChoice::Yahoo, EngineChoice::Bing, EngineChoice::CrtSh])]
+    #[case(vec![EngineChoice::CrtSh, EngineChoice::Yahoo, EngineChoice::Bing])]
+    #[case(vec![EngineChoice::Bing, EngineChoice::CrtSh, EngineChoice::Yahoo])]
+    fn test_sort_engines(#[case] choices: Vec<EngineChoice>) {
+        let mut engines = select_engines(choices, "example.com", false, false).unwrap();
+        sort_engines(&mut engines);
+
+        // however they were chosen, engines run in the same order
+        let order: Vec<_> = engines.iter().map(EngineChoice::from).collect();
+        assert_eq!(
+            order,
+            [EngineChoice::Bing, EngineChoice::CrtSh, EngineChoice::Yahoo]
+        );
+    }
+
+    #[rstest::rstest]
+    #[case(vec![EngineChoice::Yahoo, EngineChoice::Bing, EngineChoice::CrtSh])]
+    #[case(vec![EngineChoice::CrtSh, EngineChoice::Bing, EngineChoice::Yahoo])]
+    fn test_prioritize_engines(#[case] choices: Vec<EngineChoice>) {
+        let mut engines = select_engines(choices.clone(), "example.com", false, false).unwrap();
+        prioritize_engines(&mut engines, &HashMap::new());
+
+        // with a single slot, engines start one after another in this order
+        let order: Vec<_> = engines.iter().map(EngineChoice::from).collect();
+        assert_eq!(
+            order,
+            [EngineChoice::CrtSh, EngineChoice::Bing, EngineChoice::Yahoo]
+        );
 
-    println!();
-    for sub in subdomains.lock().unwrap().iter() {
-        println!("{sub}");
+        let mut engines = select_engines(choices, "example.com", false, false).unwrap();
+        prioritize_engines(&mut engines, &HashMap::from([(EngineChoice::Yahoo, 100)]));
+        let order: Vec<_> = engines.iter().map(EngineChoice::from).collect();
+        assert_eq!(
+            order,
+            [EngineChoice::Yahoo, EngineChoice::CrtSh, EngineChoice::Bing]
+        );
     }
 
-    Ok(())
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_weighted_spawn_order() {
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let events = started.clone();
+        let opts = Options {
+            weighted: true,
+            concurrency: Some(1),
+            // every engine fails its first search and gives up at once
+            proxy: Some(Url::parse("http://127.0.0.1:9").unwrap()),
+            circuit_breaker: Some(1),
+            quiet: true,
+            progress: Some(ProgressCallback::new(move |event| {
+                if let ProgressEvent::EngineStarted { engine } = event {
+                    events.lock().unwrap().push(engine);
+                }
+            })),
+            ..Options::default()
+        };
+        let choices = vec![EngineChoice::Yahoo, EngineChoice::Bing, EngineChoice::CrtSh];
+
+        // none of them reached, so the run fails, after starting each engine in turn
+        assert!(collect("example.com", choices, &opts).await.is_err());
+        assert_eq!(
+            *started.lock().unwrap(),
+            [
+                EngineChoice::CrtSh.name(),
+                EngineChoice::Bing.name(),
+                EngineChoice::Yahoo.name()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_budget_skips_only_unstarted_engines() {
+        let logs = Logs::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(logs.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let opts = Options {
+            engines_budget: Some(Duration::ZERO),
+            finished: HashMap::from([(EngineChoice::CrtSh, vec!["app.example.com".to_owned()])]),
+            quiet: true,
+            ..Options::default()
+        };
+        let choices = vec![EngineChoice::CrtSh, EngineChoice::Bing, EngineChoice::Yahoo];
+
+        let subdomains = collect("example.com", choices, &opts).await.unwrap();
+
+        assert_eq!(subdomains.len(), 1);
+        // crt.sh's results were restored, it wasn't skipped
+        assert!(logs.contents().contains("skipped=2"), "{}", logs.contents());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_discovered_ms() {
+        let started = Instant::now();
+        let mut found = Aggregate::default();
+        let crtsh = Source::Engine(EngineChoice::CrtSh);
+        found.record(crtsh, ["app.example.com".to_owned()], true);
+        tokio::time::advance(Duration::from_millis(40)).await;
+        // reported again, in another form, it keeps the time it first came in
+        let names = ["App.example.com.".to_owned(), "api.example.com".to_owned()];
+        found.record(Source::Engine(EngineChoice::Bing), names, true);
+        tokio::time::advance(Duration::from_millis(25)).await;
+        found.record(Source::Input, ["www.example.com".to_owned()], false);
+
+        let order = ["app.example.com", "api.example.com", "www.example.com"];
+        let subdomains = order
+            .iter()
+            .map(|name| {
+                let s = Subdomain::enriched(
+                    (*name).to_owned(),
+                    None,
+                    None,
+                    &FirstSeen::new(),
+                    &found.discovered,
+                );
+                report_entry(s, started, false)
+            })
+            .collect();
+        let report = Report {
+            domain: "example.com",
+            subdomains,
+            count: order.len(),
+            elapsed_secs: 0.0,
+        };
+        let json: serde_json::Value =
+            serde_json::from_str(&output::json(&report).unwrap()).unwrap();
+
+        let discovered: Vec<_> = order
+            .iter()
+            .map(|name| json["discovered_ms"][name].as_u64())
+            .collect();
+        // in the order they were first reported
+        assert_eq!(discovered, [Some(0), Some(40), Some(65)]);
+    }
+
+    #[test]
+    fn test_dedup_engines() {
+        let choices = vec![
+            EngineChoice::Google,
+            EngineChoice::Google,
+            EngineChoice::Bing,
+            EngineChoice::CrtSh,
+            EngineChoice::Bing,
+        ];
+
+        assert_eq!(
+            dedup_engines(choices),
+            [
+                EngineChoice::Google,
+                EngineChoice::Bing,
+                EngineChoice::CrtSh
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let planned = dry_run(
+            "example.com",
+            vec![EngineChoice::CrtSh, EngineChoice::Bing],
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(planned.len(), 2);
+        let (name, query) = &planned[0];
+        assert_eq!(*name, "CrtSh");
+        assert!(query.as_deref().unwrap().contains("example.com"));
+    }
+
+    #[tokio::test]
+    async fn test_run_writes_output_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let opts = Options {
+            input: Some(vec![
+                "app.example.com".to_owned(),
+                "api.example.com".to_owned(),
+            ]),
+            output: Some(path.clone()),
+            also_stdout: true,
+            ..Options::default()
+        };
+
+        run("example.com", vec![], opts).await.unwrap();
+
+        let content = std::fs::read_to_string(path).unwrap();
+        assert_eq!(content, "api.example.com\napp.example.com\n");
+    }
+
+    #[tokio::test]
+    async fn test_run_writes_metadata_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let opts = Options {
+            input: Some(vec!["app.example.com".to_owned()]),
+            output: Some(path.clone()),
+            metadata_header: true,
+            ..Options::default()
+        };
+
+        run("example.com", vec![], opts).await.unwrap();
+
+        let content = std::fs::read_to_string(path).unwrap();
+        let (header, results) = content.split_once('\n').unwrap();
+        assert!(header.starts_with("# s7r example.com "), "{header}");
+        // known subdomains only, no engine ran
+        assert!(header.ends_with(" engines=0 found=1"), "{header}");
+        assert_eq!(results, "app.example.com\n");
+    }
+
+    #[tokio::test]
+    async fn test_run_skips_metadata_header_for_dnsx() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let opts = Options {
+            input: Some(vec!["app.example.com".to_owned()]),
+            output: Some(path.clone()),
+            format: Format::Dnsx,
+            metadata_header: true,
+            ..Options::default()
+        };
+
+        run("example.com", vec![], opts).await.unwrap();
+
+        // unresolved, so no line at all, not even a comment
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_run_writes_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.json");
+        let opts = Options {
+            input: Some(vec![
+                "www.example.com".to_owned(),
+                "app.example.com".to_owned(),
+            ]),
+            output: Some(path.clone()),
+            format: Format::Json,
+            metadata_header: true,
+            ..Options::default()
+        };
+
+        run("example.com", vec![], opts).await.unwrap();
+
+        let content = std::fs::read_to_string(path).unwrap();
+        let results: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(
+            results,
+            serde_json::json!({
+                "domain": "example.com",
+                "subdomains": ["app.example.com", "www.example.com"],
+                "count": 2,
+                // the clock is paused, they came in as the run started
+                "discovered_ms": {"app.example.com": 0, "www.example.com": 0},
+            })
+        );
+    }
+
+    #[rstest::rstest]
+    #[case::text(Format::Text, "app\nv2.api\n")]
+    #[case::json(
+        Format::Json,
+        "{\"domain\":\"example.com\",\"subdomains\":[\"app\",\"v2.api\"],\"count\":2,\
+         \"discovered_ms\":{\"app\":0,\"v2.api\":0}}\n"
+    )]
+    #[case::csv(Format::Csv, "name,ips,status,new\napp,,,false\nv2.api,,,false\n")]
+    #[tokio::test(start_paused = true)]
+    async fn test_run_strips_domain(#[case] format: Format, #[case] expected: &str) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let opts = Options {
+            input: Some(vec![
+                "app.example.com".to_owned(),
+                "v2.api.example.com".to_owned(),
+            ]),
+            output: Some(path.clone()),
+            format,
+            strip_domain: true,
+            ..Options::default()
+        };
+
+        let stats = run("example.com", vec![], opts).await.unwrap();
+
+        let content = std::fs::read_to_string(path).unwrap();
+        assert_eq!(content, expected);
+        // only the output is stripped
+        assert_eq!(stats.subdomains, ["app.example.com", "v2.api.example.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_posts_report_to_webhook() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let opts = Options {
+            input: Some(vec!["app.example.com".to_owned()]),
+            webhook: Some(Url::parse(&server.uri()).unwrap()),
+            format: Format::None,
+            ..Options::default()
+        };
+
+        run("example.com", vec![], opts).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let report: serde_json::Value = requests[0].body_json().unwrap();
+        assert_eq!(report["domain"], "example.com");
+        assert_eq!(report["count"], 1);
+        let mut subdomain = report["subdomains"][0].clone();
+        assert!(subdomain["discovered_ms"].is_u64(), "{subdomain}");
+        subdomain.as_object_mut().unwrap().remove("discovered_ms");
+        assert_eq!(
+            subdomain,
+            serde_json::json!({"name": "app.example.com", "ips": [], "status": null, "new": false})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_survives_unreachable_webhook() {
+        let opts = Options {
+            input: Some(vec!["app.example.com".to_owned()]),
+            // nothing listens on the discard port
+            webhook: Some(Url::parse("http://127.0.0.1:9/").unwrap()),
+            format: Format::None,
+            ..Options::default()
+        };
+
+        let found = tokio::time::timeout(Duration::from_secs(30), run("example.com", vec![], opts))
+            .await
+            .expect("webhook retries should be bounded");
+        assert_eq!(found.unwrap().subdomains, ["app.example.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_drops_other_apexes() {
+        let input = ["app.example.co.uk", "cdn.other.co.uk", "app.example.com"];
+        let opts = Options {
+            input: Some(input.map(String::from).to_vec()),
+            ..Options::default()
+        };
+
+        let found = run("example.co.uk", vec![], opts).await.unwrap();
+        assert_eq!(found.subdomains, ["app.example.co.uk"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_input_skips_enumeration() {
+        let opts = Options {
+            input: Some(vec![
+                "app.example.com".to_owned(),
+                "api.example.com".to_owned(),
+            ]),
+            ..Options::default()
+        };
+
+        // no engine is built, so nothing touches the network
+        let found = run("example.com", vec![], opts).await.unwrap();
+        assert_eq!(found.subdomains.len(), 2);
+        assert!(found.engines.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_schedule_skips_engines_after_budget() {
+        let schedule = Schedule::new(Some(1), Some(Duration::from_millis(1500)));
+        let mut join_set = JoinSet::new();
+
+        let mut started = 0;
+        for _ in 0..3 {
+            let Some(slot) = schedule.acquire().await else {
+                break;
+            };
+            started += 1;
+            join_set.spawn(async move {
+                let _slot = slot;
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            });
+        }
+
+        // the first engine starts right away and the second once it finishes after 1s,
+        // the third would have to wait until 2s, past the budget
+        assert_eq!(started, 2);
+        assert!(schedule.acquire().await.is_none());
+
+        // engines already running are left to finish
+        join_set.join_all().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_schedule_unlimited() {
+        let schedule = Schedule::new(None, None);
+        for _ in 0..3 {
+            assert!(schedule.acquire().await.is_some());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_join_engines_survives_panic() {
+        let subdomains = Arc::new(Mutex::new(HashSet::<String>::new()));
+        let mut join_set = JoinSet::new();
+        let mut names = HashMap::new();
+
+        let mut panicky = MockEngine::new("example.com", "bad.example.com");
+        panicky.panics = true;
+        let r = subdomains.clone();
+        let handle = join_set.spawn(async move {
+            let found = Enumerator::new(panicky).enumerate(Client::new()).await;
+            r.lock().unwrap().extend(found.unwrap_or_default());
+        });
+        names.insert(handle.id(), "Panicky");
+
+        let healthy = MockEngine::new("example.com", "app.example.com");
+        let r = subdomains.clone();
+        let handle = join_set.spawn(async move {
+            let found = Enumerator::new(healthy).enumerate(Client::new()).await;
+            r.lock().unwrap().extend(found.unwrap_or_default());
+        });
+        names.insert(handle.id(), "Healthy");
+
+        join_engines(join_set, &names).await;
+
+        let found = subdomains.lock().unwrap();
+        assert_eq!(*found, HashSet::from(["app.example.com".to_owned()]));
+    }
 }
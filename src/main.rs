@@ -1,47 +1,458 @@
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
 use clap::CommandFactory;
 pub use clap::Parser;
-use owo_colors::OwoColorize;
-use sublist3r_rs::cli::{Cli, banner, print_completions};
-use sublist3r_rs::run;
-use tracing::Level;
+use owo_colors::{OwoColorize, Stream};
+use reqwest::header::HeaderMap;
+use sublist3r_rs::checkpoint::Checkpoint;
+use sublist3r_rs::cli::{Cli, Commands, Domain, art, print_completions, warnings};
+use sublist3r_rs::config::Config;
+use sublist3r_rs::enumerate::events::EngineDoneCallback;
+use sublist3r_rs::enumerate::{EngineChoice, profile};
+use sublist3r_rs::recurse::{Frontier, MAX_RECURSIVE_TARGETS};
+use sublist3r_rs::state::LastRun;
+use sublist3r_rs::{Error, Options, exit_code, ndjson, output, run, stats, tld};
+use tracing::{Level, warn};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 
 // pub use crate::cli::{Cli, header, print_completions};
 // pub use crate::run;
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let Cli {
-        domain,
-        engines,
-        verbose,
-        completion,
-    } = Cli::parse();
-
-    if let Some(shell) = completion {
+async fn main() {
+    let code = tokio::select! {
+        res = try_main() => match res {
+            Ok(code) => code,
+            Err(e) => {
+                eprintln!("Error: {e:?}");
+                e.downcast_ref::<Error>()
+                    .map_or(exit_code::USAGE, Error::exit_code)
+            }
+        },
+        _ = tokio::signal::ctrl_c() => exit_code::INTERRUPTED,
+    };
+
+    std::process::exit(code);
+}
+
+/// Run the CLI and return the exit code
+async fn try_main() -> anyhow::Result<i32> {
+    let cli = Cli::parse();
+    let cli = match &cli.profile {
+        Some(name) => {
+            let path = cli
+                .config
+                .clone()
+                .or_else(Config::default_path)
+                .ok_or_else(|| anyhow::anyhow!("could not locate the config file"))?;
+            let profile = Config::load(&path)?.profile(name)?;
+            cli.with_profile(profile)
+        }
+        None => cli,
+    };
+
+    if let Some(shell) = cli.completion {
         print_completions(shell, &mut Cli::command());
-        return Ok(());
+        return Ok(exit_code::SUCCESS);
+    }
+
+    if let Some(Commands::Merge { files, output }) = &cli.command {
+        let merged = sublist3r_rs::merge::merge(files)?.join("\n");
+        match output {
+            Some(path) => std::fs::write(path, merged + "\n")?,
+            None => println!("{merged}"),
+        }
+        return Ok(exit_code::SUCCESS);
+    }
+
+    if cli.version_json {
+        println!("{}", sublist3r_rs::version_json()?);
+        return Ok(exit_code::SUCCESS);
+    }
+
+    init_tracing(&cli);
+
+    if cli.warn_on_stale_ua
+        && let Some(version) = sublist3r_rs::stale_user_agent()
+    {
+        eprintln!(
+            "{} built-in User-Agent (Chrome {version}) is outdated, engines may start blocking requests",
+            "[!]".if_supports_color(Stream::Stderr, |t| t.yellow())
+        );
+    }
+
+    if cli.only_extract {
+        return only_extract(&cli);
+    }
+
+    if let Some(dir) = &cli.profile_regex {
+        let domain = cli.domain.as_ref().and_then(Domain::domain);
+        let bodies = profile::load_bodies(dir)?;
+        let domain = domain.ok_or(Error::InvalidDomain)?;
+        for timing in profile::profile(&bodies, domain, cli.profile_iterations)? {
+            println!("{timing}");
+        }
+        return Ok(exit_code::SUCCESS);
+    }
+
+    let state = LastRun::default_path();
+    let engines = engines(&cli, state.as_deref())?;
+
+    let opts = options(&cli, state)?;
+
+    if cli.selftest {
+        return selftest(opts).await;
+    }
+
+    if cli.ndjson {
+        let found = ndjson::run(std::io::stdin().lock(), std::io::stdout(), engines, opts).await?;
+        return Ok(if found == 0 {
+            exit_code::NO_RESULTS
+        } else {
+            exit_code::SUCCESS
+        });
+    }
+
+    let targets = targets(&cli)?;
+
+    if cli.dry_run {
+        for domain in targets {
+            for (name, query) in sublist3r_rs::dry_run(&domain, engines.clone(), cli.active)? {
+                let query = query.unwrap_or_else(|| "skipped, nothing to query".to_owned());
+                println!(
+                    "{} {name}: {query}",
+                    "[~]".if_supports_color(Stream::Stdout, |t| t.yellow())
+                );
+            }
+        }
+        return Ok(exit_code::SUCCESS);
+    }
+
+    enumerate_targets(&cli, &targets, &engines, opts).await
+}
+
+/// Engines to run, those that found subdomains in the last run if asked to, `state` is where it's saved
+fn engines(cli: &Cli, state: Option<&Path>) -> anyhow::Result<Vec<EngineChoice>> {
+    if !cli.engines_from_last_run {
+        return Ok(cli.engines.clone());
+    }
+
+    let path = state.ok_or_else(|| anyhow::anyhow!("could not locate the state file"))?;
+    let successful = LastRun::load(path)?.successful();
+    if successful.is_empty() {
+        anyhow::bail!("no engine found subdomains in the last run");
+    }
+    Ok(successful)
+}
+
+/// Domains to enumerate, the ones `--tld-expand` makes up from a brand or the one given
+fn targets(cli: &Cli) -> anyhow::Result<Vec<String>> {
+    let targets = match &cli.tld_expand {
+        Some(brand) => match &cli.tld_list {
+            Some(path) => tld::expand(brand, &tld::load(path)?),
+            None => tld::expand(brand, &tld::COMMON_TLDS),
+        },
+        // resumed runs take their targets from the checkpoint
+        None if cli.resume.is_some() => Vec::new(),
+        // domain is None only if a subcommand, completion, selftest, version_json, tld_expand
+        // or resume is provided which is already handled before, so we can safely unwrap
+        None => {
+            let domain = cli.domain.as_ref().unwrap();
+            vec![domain.domain().ok_or(Error::InvalidDomain)?.to_owned()]
+        }
+    };
+    Ok(targets)
+}
+
+/// Check that every engine is reachable and print how each did
+async fn selftest(opts: Options) -> anyhow::Result<i32> {
+    for (name, result) in sublist3r_rs::selftest(opts).await? {
+        match result {
+            Ok(()) => println!(
+                "{} {name}",
+                "[+]".if_supports_color(Stream::Stdout, |t| t.green())
+            ),
+            Err(e) => println!(
+                "{} {name}: {e}",
+                "[!]".if_supports_color(Stream::Stdout, |t| t.red())
+            ),
+        }
     }
+    Ok(exit_code::SUCCESS)
+}
+
+/// Log to stdout, or to stderr when stdout carries NDJSON results
+fn init_tracing(cli: &Cli) {
+    cli.color.apply();
+    let level = if cli.verbose {
+        Level::INFO
+    } else {
+        Level::WARN
+    };
 
-    let level = if verbose { Level::INFO } else { Level::WARN };
+    // keep stdout for results only when it's read by another program
+    let (writer, is_terminal) = if cli.ndjson {
+        (
+            BoxMakeWriter::new(std::io::stderr),
+            std::io::stderr().is_terminal(),
+        )
+    } else {
+        (
+            BoxMakeWriter::new(std::io::stdout),
+            std::io::stdout().is_terminal(),
+        )
+    };
 
     // build a formatting subscriber with a max level of WARN
-    tracing_subscriber::fmt().with_max_level(level).init();
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(writer)
+        .with_ansi(cli.color.enabled(is_terminal))
+        .init();
+}
 
-    // domain is None only if completion is provided
-    // which is already handled above, so we can safely unwrap
-    let domain = domain.unwrap();
-    let domain = domain
-        .domain()
-        .ok_or_else(|| anyhow::anyhow!("Invalid domain"))?;
+/// Print the subdomains a single engine extracts from a saved response body
+fn only_extract(cli: &Cli) -> anyhow::Result<i32> {
+    // input_file, domain and engines are required by --only-extract
+    let path = cli.input_file.as_ref().unwrap();
+    let &[engine] = cli.engines.as_slice() else {
+        anyhow::bail!("--only-extract takes a single engine, e.g. --engines bing");
+    };
+    let domain = cli.domain.as_ref().and_then(Domain::domain);
+    let body = std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut found: Vec<_> = sublist3r_rs::enumerate::parse_subdomains(
+        &String::from_utf8_lossy(&body),
+        engine,
+        domain.ok_or(Error::InvalidDomain)?,
+    )?
+    .into_iter()
+    .collect();
+    found.sort_unstable();
+    for name in &found {
+        println!("{name}");
+    }
+    Ok(if found.is_empty() {
+        exit_code::NO_RESULTS
+    } else {
+        exit_code::SUCCESS
+    })
+}
 
-    println!("{}", banner());
-    println!(
-        "{} {}",
-        "[-] Enumerating subdomains now for".blue(),
-        domain.blue()
-    );
+/// Options shared by every target, `state` is where the last run is saved
+fn options(cli: &Cli, state: Option<PathBuf>) -> anyhow::Result<Options> {
+    // lines appended as they're found would leave a broken gzip stream behind if interrupted
+    if cli
+        .output
+        .as_deref()
+        .is_some_and(|p| output::is_compressed(p, cli.compress))
+    {
+        if cli.output_incremental {
+            anyhow::bail!("--output-incremental can't write to a compressed output file");
+        }
+        if cli.flush_interval.is_some() {
+            anyhow::bail!("--flush-interval can't write to a compressed output file");
+        }
+    }
+
+    Ok(Options {
+        clean: !cli.no_clean,
+        http1_only: cli.http1_only,
+        proxy: cli.proxy.clone(),
+        no_proxy: cli.no_proxy,
+        pivot_sameip: cli.pivot_sameip,
+        pivot_scope: cli.pivot_scope.unwrap_or_default(),
+        min_sources: cli.min_sources,
+        found_by: cli.list_found_by,
+        sources_report: cli.sources_report,
+        max_rounds: cli.max_rounds,
+        backoff_base_secs: cli.backoff_base,
+        backoff_factor: cli.backoff_factor,
+        concurrency: cli.concurrency,
+        sequential: cli.sequential,
+        weighted: cli.engines_weighted,
+        priorities: cli.engine_priority.iter().copied().collect(),
+        per_host_concurrency: cli.per_host_concurrency,
+        min_interval: cli.min_interval.map(Duration::from_millis),
+        engines_budget: cli.engines_timeout_budget.map(Duration::from_secs),
+        engine_delays: cli
+            .engine_delay
+            .iter()
+            .map(|&(e, ms)| (e, Duration::from_millis(ms)))
+            .collect(),
+        per_page: cli.per_page.iter().copied().collect(),
+        keep_wildcards: cli.keep_wildcards,
+        engine_headers: cli.engine_header.iter().fold(
+            HashMap::new(),
+            |mut acc: HashMap<_, HeaderMap>, (e, key, value)| {
+                acc.entry(*e).or_default().append(key, value.clone());
+                acc
+            },
+        ),
+        delay_strategy: cli.delay_strategy,
+        engine_delay_strategies: cli.engine_delay_strategy.iter().copied().collect(),
+        jitter_seed: cli.retry_jitter_seed,
+        max_body_size: cli.max_body_size,
+        circuit_breaker: Some(cli.circuit_breaker),
+        resolve: cli.resolve,
+        resolve_ptr: cli.resolve_ptr,
+        resolve_timeout: Duration::from_millis(cli.resolve_timeout),
+        allow_private: cli.allow_private,
+        probe: cli.probe,
+        https_only: cli.https_only,
+        collapse_www: cli.collapse_www,
+        include: cli.include_pattern.clone(),
+        exclude: cli.exclude_pattern.clone(),
+        output: cli.output.clone(),
+        also_stdout: cli.also_stdout,
+        format: cli.format,
+        compress: cli.compress,
+        sort_by: cli.output_sort_by,
+        new_within: cli
+            .new_within
+            .map(|days| Duration::from_secs(days.saturating_mul(86_400))),
+        template: cli
+            .output_template_file
+            .as_ref()
+            .map(|path| -> anyhow::Result<String> {
+                let template = std::fs::read_to_string(path)
+                    .with_context(|| format!("failed to read template {}", path.display()))?;
+                output::check_template(&template)
+                    .with_context(|| format!("invalid template {}", path.display()))?;
+                Ok(template)
+            })
+            .transpose()?,
+        incremental: cli.output_incremental,
+        flush_interval: cli.flush_interval.map(Duration::from_secs),
+        metadata_header: cli.metadata_header,
+        strip_domain: cli.strip_domain,
+        // set for each target, only the first writes it
+        csv_header: true,
+        // the CLI prints its own progress
+        progress: None,
+        active: cli.active,
+        webhook: cli.webhook.clone(),
+        webhook_stream: cli.webhook_stream,
+        // runs picking engines from the last one keep it up to date
+        state: state.filter(|_| cli.save_last_run || cli.engines_from_last_run),
+        // structured results are for other tools, keep progress off stdout
+        quiet: cli.ndjson || cli.format.is_structured(),
+        input: cli
+            .input_subdomains
+            .as_ref()
+            .map(|path| sublist3r_rs::merge::merge(std::slice::from_ref(path)))
+            .transpose()?,
+        // set for each target, from the checkpoint
+        finished: HashMap::new(),
+        on_engine_done: None,
+    })
+}
+
+/// Enumerate every target, and the subdomains found for them up to `--max-depth`
+///
+/// Progress is saved to the checkpoint as engines finish, if there's one.
+async fn enumerate_targets(
+    cli: &Cli,
+    targets: &[String],
+    engines: &[EngineChoice],
+    opts: Options,
+) -> anyhow::Result<i32> {
+    let progress = if let Some(path) = &cli.resume {
+        Checkpoint::load(path)?
+    } else {
+        let mut frontier = Frontier::new(cli.max_depth, MAX_RECURSIVE_TARGETS);
+        for domain in targets {
+            frontier.push(domain, 0);
+        }
+        Checkpoint::new(frontier)
+    };
 
-    run(domain, engines).await?;
+    // results of every target are appended to the output, start from an empty file,
+    // unless it already holds those of the run being resumed
+    if let Some(path) = opts.output.as_deref().filter(|p| !output::is_stdout(p))
+        && cli.resume.is_none()
+    {
+        std::fs::File::create(path)?;
+    }
+
+    if !opts.quiet {
+        if !cli.no_banner {
+            println!("{}", art());
+        }
+        println!("{}", warnings());
+    }
+
+    // one CSV header per run, a resumed one already wrote it with the targets it completed
+    let mut csv_header =
+        progress.runs.is_empty() || opts.output.as_deref().is_none_or(output::is_stdout);
+
+    // engines save their results to the checkpoint as they finish, while the target is running
+    let progress = Arc::new(Mutex::new(progress));
+    loop {
+        let next = progress.lock().unwrap().pop();
+        let Some((domain, depth)) = next else {
+            break;
+        };
+        if !opts.quiet {
+            println!(
+                "{} {}",
+                "[-] Enumerating subdomains now for"
+                    .if_supports_color(Stream::Stdout, |t| t.blue()),
+                domain.if_supports_color(Stream::Stdout, |t| t.blue())
+            );
+        }
+        let mut opts = opts.clone();
+        opts.csv_header = std::mem::replace(&mut csv_header, false);
+        opts.finished = progress.lock().unwrap().finished();
+        opts.on_engine_done = cli.checkpoint.clone().map(|path| {
+            let progress = progress.clone();
+            EngineDoneCallback::new(move |engine, found| {
+                let mut progress = progress.lock().unwrap();
+                progress.engine_done(engine, found);
+                if let Err(e) = progress.save(&path) {
+                    warn!(err = %e, "failed to save the checkpoint");
+                }
+            })
+        });
+        let stats = match run(&domain, engines.to_vec(), opts).await {
+            Ok(stats) => stats,
+            // a subdomain that can't be enumerated doesn't fail the targets it was found for
+            Err(e) if depth > 0 => {
+                warn!(domain, err = %e, "failed to enumerate subdomain recursively");
+                progress.lock().unwrap().skip();
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let mut progress = progress.lock().unwrap();
+        progress.complete(depth, stats);
+        if let Some(path) = &cli.checkpoint {
+            progress.save(path)?;
+        }
+    }
+    let mut progress = progress.lock().unwrap();
+    let skipped = progress.frontier.skipped();
+    if skipped > 0 {
+        warn!(
+            skipped,
+            limit = MAX_RECURSIVE_TARGETS,
+            "too many subdomains to enumerate recursively, skipped the rest"
+        );
+    }
+    let runs = std::mem::take(&mut progress.runs);
+
+    if let Some(path) = &cli.stats_prometheus {
+        stats::write_prometheus(path, &runs)
+            .with_context(|| format!("failed to write metrics to {}", path.display()))?;
+    }
 
-    Ok(())
+    Ok(if runs.iter().all(|r| r.subdomains.is_empty()) {
+        exit_code::NO_RESULTS
+    } else {
+        exit_code::SUCCESS
+    })
 }
@@ -0,0 +1,66 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::normalize;
+
+/// Read result files and return the normalized, deduplicated and sorted union of their lines
+///
+/// Blank lines and `#` comments are skipped.
+///
+/// # Errors
+///
+/// If one of the files can't be read
+pub fn merge<P: AsRef<Path>>(paths: &[P]) -> anyhow::Result<Vec<String>> {
+    let mut merged = HashSet::new();
+
+    for path in paths {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        let lines = content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(str::to_ascii_lowercase);
+
+        merged.extend(normalize::normalize(lines, true));
+    }
+
+    Ok(normalize::sorted(merged))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    fn file(content: &str) -> NamedTempFile {
+        let mut f = NamedTempFile::new().unwrap();
+        f.write_all(content.as_bytes()).unwrap();
+        f
+    }
+
+    #[test]
+    fn test_merge() {
+        let a = file("app.example.com\napi.example.com\n\n# comment\n");
+        let b = file("API.example.com\nmail.example.com:443\napp.example.com\n");
+
+        let merged = merge(&[a.path(), b.path()]).unwrap();
+
+        assert_eq!(
+            merged,
+            vec!["api.example.com", "app.example.com", "mail.example.com"]
+        );
+    }
+
+    #[test]
+    fn test_merge_missing_file() {
+        assert!(merge(&["/nonexistent/s7r/results.txt"]).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::cli::Domain;
+use crate::enumerate::EngineChoice;
+use crate::{Error, Options, collect};
+
+/// A target read from a line of input, e.g. `{"domain":"example.com","engines":["crtsh"]}`
+#[derive(Debug, Deserialize)]
+struct Target {
+    domain: String,
+    /// Engines for this target only, the default engines if empty
+    #[serde(default)]
+    engines: Vec<EngineChoice>,
+}
+
+/// A line of output, the results of a target or why it failed
+#[derive(Debug, Serialize)]
+struct Outcome<'a> {
+    domain: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subdomains: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Enumerate each target read as NDJSON from `input`, writing one JSON object per target to `output`
+///
+/// A target that fails is reported in its output line and doesn't stop the others,
+/// lines that aren't a valid target are skipped with a warning.
+/// Returns the total number of subdomains found.
+///
+/// # Errors
+///
+/// If `input` can't be read or `output` written
+pub async fn run<R, W>(
+    input: R,
+    mut output: W,
+    engines: Vec<EngineChoice>,
+    opts: Options,
+) -> anyhow::Result<usize>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut found = 0;
+
+    for (n, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let target = match serde_json::from_str::<Target>(&line) {
+            Ok(t) => t,
+            Err(e) => {
+                warn!(line = n + 1, err = %e, "skipping invalid target");
+                continue;
+            }
+        };
+
+        let choices = if target.engines.is_empty() {
+            engines.clone()
+        } else {
+            target.engines
+        };
+
+        let result = match Domain::from_str(&target.domain)
+            .ok()
+            .as_ref()
+            .and_then(Domain::domain)
+        {
+            Some(domain) => collect(domain, choices, &opts).await,
+            None => Err(Error::InvalidDomain.into()),
+        };
+
+        let outcome = match result {
+            Ok(subdomains) => {
+                found += subdomains.len();
+                Outcome {
+                    domain: &target.domain,
+                    subdomains: Some(subdomains.into_iter().map(|s| s.name).collect()),
+                    error: None,
+                }
+            }
+            Err(e) => Outcome {
+                domain: &target.domain,
+                subdomains: None,
+                error: Some(e.to_string()),
+            },
+        };
+
+        serde_json::to_writer(&mut output, &outcome)?;
+        writeln!(output)?;
+        output.flush()?;
+    }
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_two_targets() {
+        let input = concat!(
+            r#"{"domain":"example.com","engines":[]}"#,
+            "\n",
+            r#"{"domain":"example.org"}"#,
+            "\n",
+        );
+        let opts = Options {
+            input: Some(vec![
+                "app.example.com".to_owned(),
+                "api.example.org".to_owned(),
+                "www.example.org".to_owned(),
+            ]),
+            quiet: true,
+            ..Options::default()
+        };
+
+        let mut output = Vec::new();
+        let found = run(input.as_bytes(), &mut output, vec![], opts)
+            .await
+            .unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(found, 3);
+        assert_eq!(
+            lines,
+            [
+                serde_json::json!({"domain": "example.com", "subdomains": ["app.example.com"]}),
+                serde_json::json!({
+                    "domain": "example.org",
+                    "subdomains": ["api.example.org", "www.example.org"]
+                }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_reports_invalid_domain() {
+        let input = "not json\n{\"domain\":\"127.0.0.1\"}\n";
+        let opts = Options {
+            input: Some(vec![]),
+            quiet: true,
+            ..Options::default()
+        };
+
+        let mut output = Vec::new();
+        run(input.as_bytes(), &mut output, vec![], opts)
+            .await
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 1);
+        assert!(output.contains(r#""domain":"127.0.0.1","error""#));
+    }
+}
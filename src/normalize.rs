@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+
+use regex::Regex;
+
+/// Strip any scheme, userinfo, port, path, query or fragment from a raw result,
+/// keeping only the hostname
+///
+/// For example, `https://app.example.com:443/login?next=/` becomes `app.example.com`.
+pub fn clean(raw: &str) -> &str {
+    let s = raw.trim();
+    let s = s.split_once("://").map_or(s, |(_, rest)| rest);
+    let s = s.split(['/', '?', '#']).next().unwrap_or_default();
+    let s = s.rsplit_once('@').map_or(s, |(_, host)| host);
+    s.split(':').next().unwrap_or_default()
+}
+
+/// Normalize a batch of raw results into a set of hostnames
+///
+/// When `clean` is `false`, results are kept exactly as extracted.
+pub fn normalize<I>(raw: I, clean: bool) -> HashSet<String>
+where
+    I: IntoIterator<Item = String>,
+{
+    raw.into_iter()
+        .map(|s| if clean { self::clean(&s).to_owned() } else { s })
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Whether `host` is a subdomain of `domain`, the apex itself isn't included
+pub fn is_subdomain_of(host: &str, domain: &str) -> bool {
+    host.strip_suffix(domain)
+        .is_some_and(|rest| rest.len() > 1 && rest.ends_with('.'))
+}
+
+/// Drop `www.` hosts whose bare form was found too, keeping one host per asset
+///
+/// `www.app.example.com` goes when `app.example.com` is present, and `www.example.com`
+/// always goes since its bare form is the target `domain` itself.
+pub fn collapse_www(subdomains: &mut HashSet<String>, domain: &str) {
+    let duplicates: Vec<String> = subdomains
+        .iter()
+        .filter(|s| {
+            s.strip_prefix("www.")
+                .is_some_and(|bare| bare == domain || subdomains.contains(bare))
+        })
+        .cloned()
+        .collect();
+
+    for d in duplicates {
+        subdomains.remove(&d);
+    }
+}
+
+/// Keep results matching any `include` pattern (or all, if there are none)
+/// and drop results matching any `exclude` pattern
+pub fn filter(subdomains: &mut HashSet<String>, include: &[Regex], exclude: &[Regex]) {
+    subdomains.retain(|s| {
+        let included = include.is_empty() || include.iter().any(|re| re.is_match(s));
+        let excluded = exclude.iter().any(|re| re.is_match(s));
+        included && !excluded
+    });
+}
+
+/// Sort results alphabetically for stable output
+pub fn sorted(subdomains: HashSet<String>) -> Vec<String> {
+    let mut v: Vec<_> = subdomains.into_iter().collect();
+    v.sort_unstable();
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::bare("app.example.com", "app.example.com")]
+    #[case::port("app.example.com:443", "app.example.com")]
+    #[case::path("app.example.com/login", "app.example.com")]
+    #[case::query("app.example.com?next=/", "app.example.com")]
+    #[case::fragment("app.example.com#top", "app.example.com")]
+    #[case::port_and_path("app.example.com:8080/admin/index.html", "app.example.com")]
+    #[case::scheme("https://app.example.com", "app.example.com")]
+    #[case::everything("https://user@app.example.com:443/login?next=/#top", "app.example.com")]
+    #[case::whitespace("  app.example.com:443 ", "app.example.com")]
+    #[case::empty("", "")]
+    fn test_clean(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(clean(input), expected);
+    }
+
+    #[rstest]
+    #[case::subdomain("app.example.com", true)]
+    #[case::multi_level("a.b.example.com", true)]
+    #[case::apex("example.com", false)]
+    #[case::lone_dot(".example.com", false)]
+    #[case::suffix_only("notexample.com", false)]
+    #[case::other_domain("app.example.org", false)]
+    fn test_is_subdomain_of(#[case] host: &str, #[case] expected: bool) {
+        assert_eq!(is_subdomain_of(host, "example.com"), expected);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let raw = vec![
+            "app.example.com:443".to_owned(),
+            "app.example.com/login".to_owned(),
+            "api.example.com".to_owned(),
+            ":8080".to_owned(),
+        ];
+
+        let expected = HashSet::from(["app.example.com".to_owned(), "api.example.com".to_owned()]);
+        assert_eq!(normalize(raw, true), expected);
+    }
+
+    #[test]
+    fn test_normalize_no_clean() {
+        let raw = vec![
+            "app.example.com:443".to_owned(),
+            "app.example.com/login".to_owned(),
+        ];
+
+        let expected = HashSet::from([
+            "app.example.com:443".to_owned(),
+            "app.example.com/login".to_owned(),
+        ]);
+        assert_eq!(normalize(raw, false), expected);
+    }
+
+    fn subdomains() -> HashSet<String> {
+        [
+            "app.example.com",
+            "img1.cdn.example.com",
+            "img2.cdn.example.com",
+            "10-0-0-1.example.com",
+            "api.example.com",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect()
+    }
+
+    fn regexes(patterns: &[&str]) -> Vec<Regex> {
+        patterns.iter().map(|p| Regex::new(p).unwrap()).collect()
+    }
+
+    #[rstest]
+    #[case::no_patterns(
+        &[],
+        &[],
+        vec!["app.example.com", "img1.cdn.example.com", "img2.cdn.example.com", "10-0-0-1.example.com", "api.example.com"]
+    )]
+    #[case::exclude(
+        &[],
+        &[r"\.cdn\.", r"^\d+-\d+-\d+-\d+\."],
+        vec!["app.example.com", "api.example.com"]
+    )]
+    #[case::include(
+        &[r"\.cdn\."],
+        &[],
+        vec!["img1.cdn.example.com", "img2.cdn.example.com"]
+    )]
+    #[case::include_and_exclude(
+        &[r"\.cdn\.", r"^api\."],
+        &[r"^img2\."],
+        vec!["img1.cdn.example.com", "api.example.com"]
+    )]
+    fn test_filter(
+        #[case] include: &[&str],
+        #[case] exclude: &[&str],
+        #[case] expected: Vec<&str>,
+    ) {
+        let mut subdomains = subdomains();
+        filter(&mut subdomains, &regexes(include), &regexes(exclude));
+
+        let expected: HashSet<String> = expected.into_iter().map(String::from).collect();
+        assert_eq!(subdomains, expected);
+    }
+
+    #[test]
+    fn test_collapse_www() {
+        let mut subdomains: HashSet<String> = [
+            "www.example.com",
+            "app.example.com",
+            "www.app.example.com",
+            "api.v2.example.com",
+            "www.api.v2.example.com",
+            "www.blog.example.com",
+            "mail.www.example.com",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+        collapse_www(&mut subdomains, "example.com");
+
+        let expected: HashSet<String> = [
+            "app.example.com",
+            "api.v2.example.com",
+            // no bare form to collapse into
+            "www.blog.example.com",
+            // `www` isn't the leftmost label
+            "mail.www.example.com",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+        assert_eq!(subdomains, expected);
+    }
+}
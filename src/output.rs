@@ -0,0 +1,792 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use minijinja::Environment;
+use serde::Serialize;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::info;
+
+use crate::date;
+
+/// A destination for the final list of subdomains
+pub trait OutputWriter {
+    /// Write the final list of subdomains
+    ///
+    /// # Errors
+    ///
+    /// If writing fails
+    fn write_results(&mut self, subdomains: &[String]) -> io::Result<()>;
+
+    /// Write the results with those flagged by `--new-within` marked, `new` has a flag for each
+    ///
+    /// Only writers read by people show the marks, files are left one plain name per line.
+    ///
+    /// # Errors
+    ///
+    /// If writing fails
+    fn write_marked(&mut self, subdomains: &[String], _new: &[bool]) -> io::Result<()> {
+        self.write_results(subdomains)
+    }
+
+    /// Describe the run ahead of its results, writers that can't hold comments ignore it
+    ///
+    /// # Errors
+    ///
+    /// If writing fails
+    fn write_metadata(&mut self, _: &Metadata) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// What a run was, written as a `#` comment ahead of its results with `--metadata-header`
+///
+/// e.g. `# s7r example.com 2024-05-01T12:00:00Z engines=8 found=231`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata<'a> {
+    pub domain: &'a str,
+    /// When the run finished, in seconds since the Unix epoch
+    pub timestamp: i64,
+    /// Number of engines run
+    pub engines: usize,
+    /// Number of subdomains written
+    pub found: usize,
+}
+
+impl fmt::Display for Metadata<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "# s7r {} {} engines={} found={}",
+            self.domain,
+            date::rfc3339(self.timestamp),
+            self.engines,
+            self.found
+        )
+    }
+}
+
+/// Writes one subdomain per line
+pub struct Lines<W: Write>(pub W);
+
+impl<W: Write> OutputWriter for Lines<W> {
+    fn write_results(&mut self, subdomains: &[String]) -> io::Result<()> {
+        for sub in subdomains {
+            writeln!(self.0, "{sub}")?;
+        }
+        self.0.flush()
+    }
+
+    fn write_metadata(&mut self, metadata: &Metadata) -> io::Result<()> {
+        writeln!(self.0, "{metadata}")
+    }
+}
+
+/// Writes one subdomain per line to the terminal, new ones marked with `[new]`
+pub struct Terminal<W: Write>(pub W);
+
+impl<W: Write> OutputWriter for Terminal<W> {
+    fn write_results(&mut self, subdomains: &[String]) -> io::Result<()> {
+        Lines(&mut self.0).write_results(subdomains)
+    }
+
+    fn write_marked(&mut self, subdomains: &[String], new: &[bool]) -> io::Result<()> {
+        for (sub, new) in subdomains.iter().zip(new) {
+            if *new {
+                writeln!(self.0, "{sub} [new]")?;
+            } else {
+                writeln!(self.0, "{sub}")?;
+            }
+        }
+        self.0.flush()
+    }
+
+    fn write_metadata(&mut self, metadata: &Metadata) -> io::Result<()> {
+        Lines(&mut self.0).write_metadata(metadata)
+    }
+}
+
+impl<T: OutputWriter + ?Sized> OutputWriter for Box<T> {
+    fn write_results(&mut self, subdomains: &[String]) -> io::Result<()> {
+        (**self).write_results(subdomains)
+    }
+
+    fn write_marked(&mut self, subdomains: &[String], new: &[bool]) -> io::Result<()> {
+        (**self).write_marked(subdomains, new)
+    }
+
+    fn write_metadata(&mut self, metadata: &Metadata) -> io::Result<()> {
+        (**self).write_metadata(metadata)
+    }
+}
+
+/// Writes one subdomain per line, gzip compressed
+///
+/// Every batch is a complete gzip member, so a file appended to by several runs
+/// still decompresses to all of their results.
+pub struct Gzip<W: Write>(pub W);
+
+impl<W: Write> OutputWriter for Gzip<W> {
+    fn write_results(&mut self, subdomains: &[String]) -> io::Result<()> {
+        let mut encoder = GzEncoder::new(&mut self.0, Compression::default());
+        Lines(&mut encoder).write_results(subdomains)?;
+        encoder.finish()?.flush()
+    }
+
+    fn write_metadata(&mut self, metadata: &Metadata) -> io::Result<()> {
+        let mut encoder = GzEncoder::new(&mut self.0, Compression::default());
+        Lines(&mut encoder).write_metadata(metadata)?;
+        encoder.finish()?.flush()
+    }
+}
+
+/// Drops results, for runs that only want the output file or the exit code
+pub struct Discard;
+
+impl OutputWriter for Discard {
+    fn write_results(&mut self, _: &[String]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// How results are printed to the terminal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// One subdomain per line
+    #[default]
+    Text,
+    /// Nothing, the output file is still written
+    None,
+    /// One `app.example.com [A] 192.0.2.1` line per resolved address, as `dnsx` prints them
+    Dnsx,
+    /// A JSON object with the domain, its subdomains and their count, one line per target
+    Json,
+    /// A `name,ips,status,new` header, then one row per subdomain of every target
+    Csv,
+}
+
+impl Format {
+    /// Whether the results are for other tools to parse, so nothing else goes to stdout
+    #[must_use]
+    pub fn is_structured(self) -> bool {
+        matches!(self, Format::Dnsx | Format::Json | Format::Csv)
+    }
+}
+
+/// Writes the same results to two sinks
+pub struct Tee<A, B>(pub A, pub B);
+
+impl<A: OutputWriter, B: OutputWriter> OutputWriter for Tee<A, B> {
+    fn write_results(&mut self, subdomains: &[String]) -> io::Result<()> {
+        self.0.write_results(subdomains)?;
+        self.1.write_results(subdomains)
+    }
+
+    fn write_marked(&mut self, subdomains: &[String], new: &[bool]) -> io::Result<()> {
+        self.0.write_marked(subdomains, new)?;
+        self.1.write_marked(subdomains, new)
+    }
+
+    /// Only the first sink gets the metadata, it describes the saved file, not the terminal
+    fn write_metadata(&mut self, metadata: &Metadata) -> io::Result<()> {
+        self.0.write_metadata(metadata)
+    }
+}
+
+/// Cut `.{domain}` off the end of `name`, e.g. `v2.api` for `v2.api.example.com`
+///
+/// The apex itself becomes `@`, the way zone files write it, and names outside of `domain`
+/// are kept whole.
+#[must_use]
+pub fn strip_domain<'a>(name: &'a str, domain: &str) -> &'a str {
+    let domain = domain.trim_end_matches('.');
+    if name.trim_end_matches('.').eq_ignore_ascii_case(domain) {
+        return "@";
+    }
+
+    let Some(at) = name.len().checked_sub(domain.len() + 1) else {
+        return name;
+    };
+    match name.get(at..) {
+        Some(rest) if rest.starts_with('.') && rest[1..].eq_ignore_ascii_case(domain) => {
+            &name[..at]
+        }
+        _ => name,
+    }
+}
+
+/// Lines `dnsx` would print for a resolved subdomain, e.g. `app.example.com [A] 192.0.2.1`
+///
+/// One line per address, with its record type, none if the subdomain wasn't resolved.
+pub fn dnsx(subdomain: &ReportEntry) -> impl Iterator<Item = String> + '_ {
+    subdomain.ips.iter().map(|ip| {
+        let kind = if ip.contains(':') { "AAAA" } else { "A" };
+        format!("{} [{kind}] {ip}", subdomain.name)
+    })
+}
+
+/// The results as a single line of JSON, e.g.
+/// `{"domain":"example.com","subdomains":["api.example.com"],"count":1,"discovered_ms":{"api.example.com":840}}`
+///
+/// Subdomains are sorted by name whatever the report's order, so the same results always
+/// give the same JSON. `discovered_ms` has how far into the run each was first reported.
+/// A run over several targets prints one line each, as JSON Lines.
+///
+/// # Errors
+///
+/// If serializing fails
+pub fn json(report: &Report) -> serde_json::Result<String> {
+    #[derive(Serialize)]
+    struct Results<'a> {
+        domain: &'a str,
+        subdomains: Vec<&'a str>,
+        count: usize,
+        discovered_ms: BTreeMap<&'a str, u64>,
+    }
+
+    let mut subdomains: Vec<&str> = report.subdomains.iter().map(|s| s.name.as_str()).collect();
+    subdomains.sort_unstable();
+    let discovered_ms = report
+        .subdomains
+        .iter()
+        .filter_map(|s| Some((s.name.as_str(), s.discovered_ms?)))
+        .collect();
+    serde_json::to_string(&Results {
+        domain: report.domain,
+        subdomains,
+        count: report.count,
+        discovered_ms,
+    })
+}
+
+/// The results as CSV rows, under a `name,ips,status,new` header if `header`
+///
+/// IPs are separated by spaces, a missing status is left empty.
+#[must_use]
+pub fn csv(subdomains: &[ReportEntry], header: bool) -> Vec<String> {
+    let mut rows = Vec::with_capacity(subdomains.len() + 1);
+    if header {
+        rows.push("name,ips,status,new".to_owned());
+    }
+    rows.extend(subdomains.iter().map(|s| {
+        let status = s.status.map(|c| c.to_string()).unwrap_or_default();
+        format!(
+            "{},{},{status},{}",
+            csv_field(&s.name),
+            s.ips.join(" "),
+            s.new
+        )
+    }));
+    rows
+}
+
+/// Quote a field holding a separator or a quote, names kept as extracted (`--no-clean`) can
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Whether `path` stands for stdout rather than a file
+#[must_use]
+pub fn is_stdout(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// Whether results written to `path` are gzip compressed, either asked for or implied by `.gz`
+#[must_use]
+pub fn is_compressed(path: &Path, compress: bool) -> bool {
+    compress || path.extension().is_some_and(|ext| ext == "gz")
+}
+
+/// Build the writer for `--output`, stdout if there's no file
+///
+/// Results are appended to the file, so several runs can share one output file.
+/// The file is gzip compressed if [`is_compressed`] says so.
+/// With `also_stdout`, results are printed as well as written to the file.
+/// With [`Format::None`], nothing is printed but the file is still written.
+/// The other formats only change the lines written, see [`dnsx`], [`json`] and [`csv`].
+///
+/// # Errors
+///
+/// If the output file can't be opened
+pub fn writer(
+    output: Option<&Path>,
+    also_stdout: bool,
+    format: Format,
+    compress: bool,
+) -> io::Result<Box<dyn OutputWriter + Send>> {
+    let Some(path) = output.filter(|p| !is_stdout(p)) else {
+        return Ok(match format {
+            Format::None => Box::new(Discard),
+            _ => Box::new(Terminal(io::stdout())),
+        });
+    };
+
+    let file = BufWriter::new(open(path)?);
+    let file: Box<dyn OutputWriter + Send> = if is_compressed(path, compress) {
+        Box::new(Gzip(file))
+    } else {
+        Box::new(Lines(file))
+    };
+    if also_stdout && format != Format::None {
+        Ok(Box::new(Tee(file, Terminal(io::stdout()))))
+    } else {
+        Ok(Box::new(file))
+    }
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Appends subdomains to the output file as they're found, so a run that's killed
+/// still leaves every subdomain found so far, one complete line each
+///
+/// Post-processing can still drop some of them, so once the final results are in,
+/// [`Incremental::finish`] cuts the file back to where this run started.
+pub struct Incremental {
+    file: File,
+    /// Length of the file when the run started
+    start: u64,
+    seen: HashSet<String>,
+}
+
+impl Incremental {
+    /// Open `path` to append to, remembering where this run's writes start
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be opened
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = open(path)?;
+        let start = file.metadata()?.len();
+        Ok(Self {
+            file,
+            start,
+            seen: HashSet::new(),
+        })
+    }
+
+    /// Write `name` and flush it to disk, unless it was already written
+    ///
+    /// # Errors
+    ///
+    /// If writing or flushing fails
+    pub fn write(&mut self, name: String) -> io::Result<()> {
+        if self.seen.contains(&name) {
+            return Ok(());
+        }
+        // a single write per line, so a kill never leaves half a name behind
+        self.file.write_all(format!("{name}\n").as_bytes())?;
+        self.file.flush()?;
+        self.seen.insert(name);
+        Ok(())
+    }
+
+    /// Write every name received until all senders are gone, blocking the current thread
+    ///
+    /// # Errors
+    ///
+    /// If a write fails
+    pub fn consume(mut self, mut rx: UnboundedReceiver<String>) -> io::Result<Self> {
+        while let Some(name) = rx.blocking_recv() {
+            self.write(name)?;
+        }
+        Ok(self)
+    }
+
+    /// Write the names received every `period` instead of one by one, until all senders are gone
+    ///
+    /// Each write logs how many subdomains were found so far, for long runs.
+    ///
+    /// # Errors
+    ///
+    /// If a write fails
+    pub async fn consume_every(
+        mut self,
+        mut rx: UnboundedReceiver<String>,
+        period: Duration,
+    ) -> io::Result<Self> {
+        let mut ticker = tokio::time::interval_at(Instant::now() + period, period);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut pending = Vec::new();
+        loop {
+            tokio::select! {
+                name = rx.recv() => match name {
+                    Some(name) => pending.push(name),
+                    None => break,
+                },
+                _ = ticker.tick() => self.write_pending(&mut pending)?,
+            }
+        }
+        self.write_pending(&mut pending)?;
+        Ok(self)
+    }
+
+    fn write_pending(&mut self, pending: &mut Vec<String>) -> io::Result<()> {
+        if pending.is_empty() {
+            return Ok(());
+        }
+        for name in pending.drain(..) {
+            self.write(name)?;
+        }
+        info!(found = self.seen.len(), "wrote results found so far");
+        Ok(())
+    }
+
+    /// Remove what this run wrote, so the final results can take its place
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be cut back
+    pub fn finish(self) -> io::Result<()> {
+        self.file.set_len(self.start)
+    }
+}
+
+/// Everything a report template is rendered with, for `--output-template-file`
+#[derive(Debug, Serialize)]
+pub struct Report<'a> {
+    pub domain: &'a str,
+    pub subdomains: Vec<ReportEntry>,
+    /// Number of subdomains
+    pub count: usize,
+    /// Time the run took, in seconds
+    pub elapsed_secs: f64,
+}
+
+/// A subdomain as seen by a report template
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportEntry {
+    pub name: String,
+    /// Resolved IP addresses, empty without `--resolve`
+    pub ips: Vec<String>,
+    /// Probe status code, missing without `--probe`
+    pub status: Option<u16>,
+    /// Whether it was flagged by `--new-within`
+    pub new: bool,
+    /// Milliseconds into the run when it was first reported, missing if found after enumeration
+    pub discovered_ms: Option<u64>,
+}
+
+/// Check that `template` is a valid template, before spending time on enumeration
+///
+/// # Errors
+///
+/// The syntax error found in `template`
+pub fn check_template(template: &str) -> Result<(), minijinja::Error> {
+    Environment::new().template_from_str(template).map(|_| ())
+}
+
+/// Render a [`Report`] through a Jinja-style template, e.g. `{{ domain }}: {{ count }}`
+///
+/// # Errors
+///
+/// If rendering fails, e.g. the template uses a filter that doesn't exist
+pub fn render(template: &str, report: &Report) -> Result<String, minijinja::Error> {
+    Environment::new().render_str(template, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::single_label("api.example.com", "api")]
+    #[case::multi_level("v2.api.example.com", "v2.api")]
+    #[case::deep("a.b.c.example.com", "a.b.c")]
+    #[case::case_insensitive("WWW.Example.COM", "WWW")]
+    #[case::apex("example.com", "@")]
+    #[case::apex_trailing_dot("example.com.", "@")]
+    #[case::other_domain("api.example.org", "api.example.org")]
+    // only whole labels are stripped
+    #[case::label_suffix("myexample.com", "myexample.com")]
+    #[case::shorter("com", "com")]
+    fn test_strip_domain(#[case] name: &str, #[case] expected: &str) {
+        assert_eq!(strip_domain(name, "example.com"), expected);
+    }
+
+    fn results() -> Vec<String> {
+        vec!["api.example.com".to_owned(), "app.example.com".to_owned()]
+    }
+
+    fn report() -> Report<'static> {
+        Report {
+            domain: "example.com",
+            subdomains: vec![
+                ReportEntry {
+                    name: "api.example.com".to_owned(),
+                    ips: vec!["10.0.0.1".to_owned()],
+                    status: Some(200),
+                    new: false,
+                    discovered_ms: Some(120),
+                },
+                ReportEntry {
+                    name: "app.example.com".to_owned(),
+                    ips: vec![],
+                    status: None,
+                    new: true,
+                    discovered_ms: Some(45),
+                },
+            ],
+            count: 2,
+            elapsed_secs: 1.5,
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let report = report();
+        let template = "# {{ domain }} ({{ count }} in {{ elapsed_secs }}s)\n\
+            {% for s in subdomains %}- {{ s.name }}{% if s.new %} [new]{% endif %}\
+            {% if s.status %} {{ s.status }}{% endif %}\n{% endfor %}";
+
+        assert_eq!(
+            render(template, &report).unwrap(),
+            "# example.com (2 in 1.5s)\n- api.example.com 200\n- app.example.com [new]\n"
+        );
+    }
+
+    #[test]
+    fn test_check_template() {
+        assert!(check_template("{{ domain }}").is_ok());
+        assert!(check_template("{% for s in subdomains %}").is_err());
+    }
+
+    #[test]
+    fn test_incremental() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        std::fs::write(&path, "old.example.com\n").unwrap();
+
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        for name in ["api.example.com", "app.example.com", "api.example.com"] {
+            tx.send(name.to_owned()).unwrap();
+        }
+        drop(tx);
+        let journal = Incremental::open(&path).unwrap().consume(rx).unwrap();
+
+        // as if the process was killed here, every line written so far is complete
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "old.example.com\napi.example.com\napp.example.com\n"
+        );
+
+        journal.finish().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "old.example.com\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_incremental_consume_every() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        let read = || std::fs::read_to_string(&path).unwrap();
+
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let journal = Incremental::open(&path).unwrap();
+        let consumer = tokio::spawn(journal.consume_every(rx, Duration::from_secs(5)));
+
+        // a slow engine: one result now, the next one long after
+        tx.send("api.example.com".to_owned()).unwrap();
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(read(), "");
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert_eq!(read(), "api.example.com\n");
+
+        tx.send("app.example.com".to_owned()).unwrap();
+        drop(tx);
+        let journal = consumer.await.unwrap().unwrap();
+        assert_eq!(read(), "api.example.com\napp.example.com\n");
+
+        journal.finish().unwrap();
+        assert_eq!(read(), "");
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut buf = Vec::new();
+        Lines(&mut buf).write_results(&results()).unwrap();
+
+        assert_eq!(buf, b"api.example.com\napp.example.com\n");
+    }
+
+    #[test]
+    fn test_json() {
+        let expected = r#"{"domain":"example.com","subdomains":["api.example.com","app.example.com"],"count":2,"discovered_ms":{"api.example.com":120,"app.example.com":45}}"#;
+        let mut report = report();
+        assert_eq!(json(&report).unwrap(), expected);
+
+        // sorted by name, whatever the report was sorted by
+        report.subdomains.reverse();
+        assert_eq!(json(&report).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut report = report();
+        report.subdomains[1].name = "https://app.example.com/?a=1,b=2".to_owned();
+
+        assert_eq!(
+            csv(&report.subdomains, true),
+            [
+                "name,ips,status,new",
+                "api.example.com,10.0.0.1,200,false",
+                r#""https://app.example.com/?a=1,b=2",,,true"#,
+            ]
+        );
+        // the rows of later targets continue the first one's
+        assert_eq!(csv(&report.subdomains, false).len(), 2);
+    }
+
+    #[test]
+    fn test_dnsx() {
+        let subdomains = [
+            ReportEntry {
+                name: "app.example.com".to_owned(),
+                ips: vec!["192.0.2.1".to_owned(), "2001:db8::1".to_owned()],
+                status: None,
+                new: false,
+                discovered_ms: None,
+            },
+            ReportEntry {
+                name: "unresolved.example.com".to_owned(),
+                ips: Vec::new(),
+                status: None,
+                new: false,
+                discovered_ms: None,
+            },
+        ];
+
+        let lines: Vec<_> = subdomains.iter().flat_map(dnsx).collect();
+        let mut buf = Vec::new();
+        Lines(&mut buf).write_results(&lines).unwrap();
+
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "app.example.com [A] 192.0.2.1\napp.example.com [AAAA] 2001:db8::1\n"
+        );
+    }
+
+    #[test]
+    fn test_new_only_marked_on_terminal() {
+        let (mut file, mut terminal) = (Vec::new(), Vec::new());
+        Tee(Lines(&mut file), Terminal(&mut terminal))
+            .write_marked(&results(), &[false, true])
+            .unwrap();
+
+        assert_eq!(file, b"api.example.com\napp.example.com\n");
+        assert_eq!(terminal, b"api.example.com\napp.example.com [new]\n");
+    }
+
+    #[test]
+    fn test_tee() {
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        Tee(Lines(&mut a), Lines(&mut b))
+            .write_results(&results())
+            .unwrap();
+
+        assert_eq!(a, b"api.example.com\napp.example.com\n");
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_metadata_header() {
+        let metadata = Metadata {
+            domain: "example.com",
+            timestamp: 1_714_564_800,
+            engines: 8,
+            found: 2,
+        };
+        let (mut file, mut stdout) = (Vec::new(), Vec::new());
+        let mut out = Tee(Lines(&mut file), Lines(&mut stdout));
+        out.write_metadata(&metadata).unwrap();
+        out.write_results(&results()).unwrap();
+
+        let file = String::from_utf8(file).unwrap();
+        let mut lines = file.lines();
+        let header = regex::Regex::new(
+            r"^# s7r (\S+) (\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z) engines=(\d+) found=(\d+)$",
+        )
+        .unwrap();
+        let caps = header.captures(lines.next().unwrap()).unwrap();
+        assert_eq!(&caps[1], "example.com");
+        assert_eq!(&caps[2], "2024-05-01T12:00:00Z");
+        assert_eq!(caps[3].parse::<usize>().unwrap(), 8);
+        assert_eq!(caps[4].parse::<usize>().unwrap(), 2);
+        assert_eq!(lines.collect::<Vec<_>>(), results());
+
+        // the terminal only gets the results
+        assert_eq!(stdout, b"api.example.com\napp.example.com\n");
+    }
+
+    #[test]
+    fn test_writer_appends_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+
+        writer(Some(&path), false, Format::Text, false)
+            .unwrap()
+            .write_results(&results()[..1])
+            .unwrap();
+        writer(Some(&path), true, Format::Text, false)
+            .unwrap()
+            .write_results(&results()[1..])
+            .unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "api.example.com\napp.example.com\n");
+    }
+
+    #[test]
+    fn test_writer_compresses_gz_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt.gz");
+
+        // appended runs add gzip members, which decompress as one stream
+        for batch in [&results()[..1], &results()[1..]] {
+            writer(Some(&path), false, Format::Text, false)
+                .unwrap()
+                .write_results(batch)
+                .unwrap();
+        }
+
+        let compressed = std::fs::read(&path).unwrap();
+        assert_eq!(compressed[..2], [0x1f, 0x8b]);
+        let mut content = String::new();
+        flate2::read::MultiGzDecoder::new(&compressed[..])
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "api.example.com\napp.example.com\n");
+    }
+
+    #[rstest]
+    #[case("out.txt.gz", false, true)]
+    #[case("out.txt", true, true)]
+    #[case("out.txt", false, false)]
+    #[case("gz", false, false)]
+    fn test_is_compressed(#[case] path: &str, #[case] compress: bool, #[case] expected: bool) {
+        assert_eq!(is_compressed(Path::new(path), compress), expected);
+    }
+
+    #[test]
+    fn test_is_stdout() {
+        assert!(is_stdout(Path::new("-")));
+        assert!(!is_stdout(Path::new("out.txt")));
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashSet;
+use std::net::IpAddr;
+
+use clap::ValueEnum;
+use reqwest::Client;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::enumerate::rapiddns::RapidDNS;
+use crate::normalize;
+
+/// Which hosts discovered by pivoting are kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PivotScope {
+    /// Only subdomains of the target domain
+    #[default]
+    Domain,
+    /// Every host sharing an IP with a discovered subdomain
+    All,
+}
+
+impl PivotScope {
+    fn is_relevant(self, host: &str, domain: &str) -> bool {
+        match self {
+            PivotScope::Domain => normalize::is_subdomain_of(host, domain),
+            PivotScope::All => true,
+        }
+    }
+}
+
+/// Resolve discovered subdomains and query RapidDNS for other hosts on the same IPs
+#[tracing::instrument(skip_all)]
+pub async fn sameip(
+    client: Client,
+    domain: &str,
+    subdomains: &HashSet<String>,
+    scope: PivotScope,
+) -> HashSet<String> {
+    let mut ips = HashSet::<IpAddr>::new();
+    for sub in subdomains {
+        match tokio::net::lookup_host((sub.as_str(), 0)).await {
+            Ok(addrs) => ips.extend(addrs.map(|a| a.ip())),
+            Err(e) => info!(sub, err = ?e, "failed to resolve"),
+        }
+    }
+
+    let mut found = HashSet::new();
+    for ip in ips {
+        match RapidDNS::sameip(client.clone(), ip).await {
+            Ok(hosts) => found.extend(hosts),
+            Err(e) => warn!(%ip, err = ?e, "failed to pivot"),
+        }
+    }
+
+    filter(found, domain, scope)
+}
+
+fn filter(hosts: HashSet<String>, domain: &str, scope: PivotScope) -> HashSet<String> {
+    hosts
+        .into_iter()
+        .filter(|h| scope.is_relevant(h, domain))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::domain(PivotScope::Domain, vec!["app.example.com", "a.b.example.com"])]
+    #[case::all(
+        PivotScope::All,
+        vec!["app.example.com", "a.b.example.com", "example.org", "notexample.com"]
+    )]
+    fn test_filter(#[case] scope: PivotScope, #[case] expected: Vec<&str>) {
+        let hosts = [
+            "app.example.com",
+            "a.b.example.com",
+            "example.org",
+            "notexample.com",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+        let expected: HashSet<String> = expected.into_iter().map(String::from).collect();
+        assert_eq!(filter(hosts, "example.com", scope), expected);
+    }
+}
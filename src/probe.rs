@@ -0,0 +1,257 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::header::RANGE;
+use reqwest::{Client, Response, StatusCode, Url};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, info};
+
+/// Maximum number of probes in flight at once
+const CONCURRENCY: usize = 32;
+
+/// Give up on a host that doesn't answer within this time
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Coarse classification of a probed host by its final status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liveness {
+    /// 2xx, or 3xx left unfollowed
+    Live,
+    /// 4xx, the server is up but refuses the request
+    ClientError,
+    /// 5xx, the server is up but failing
+    ServerError,
+}
+
+impl From<StatusCode> for Liveness {
+    fn from(status: StatusCode) -> Self {
+        if status.is_server_error() {
+            Liveness::ServerError
+        } else if status.is_client_error() {
+            Liveness::ClientError
+        } else {
+            Liveness::Live
+        }
+    }
+}
+
+/// Outcome of probing a single host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Probe {
+    /// Status of the final response, after following redirects
+    pub status: StatusCode,
+    pub liveness: Liveness,
+    /// Host the probe was redirected to, if it isn't the probed host
+    pub redirect: Option<String>,
+    /// Whether the host answered over HTTPS, rather than only over HTTP
+    pub https: bool,
+}
+
+impl Probe {
+    fn new(url: &Url, resp: &Response) -> Self {
+        let status = resp.status();
+        let redirect = resp
+            .url()
+            .host_str()
+            .filter(|host| Some(*host) != url.host_str())
+            .map(String::from);
+
+        Self {
+            status,
+            liveness: status.into(),
+            redirect,
+            https: url.scheme() == "https",
+        }
+    }
+}
+
+/// Probe a URL without downloading its body
+///
+/// Sends a `HEAD` request, falling back to a `GET` for the first byte only
+/// if the server doesn't allow `HEAD`.
+pub async fn probe_url(client: &Client, url: Url) -> reqwest::Result<Probe> {
+    let resp = client.head(url.clone()).timeout(TIMEOUT).send().await?;
+
+    let resp = match resp.status() {
+        StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED => {
+            debug!(%url, status = %resp.status(), "HEAD rejected, falling back to a ranged GET");
+            client
+                .get(url.clone())
+                .header(RANGE, "bytes=0-0")
+                .timeout(TIMEOUT)
+                .send()
+                .await?
+        }
+        _ => resp,
+    };
+
+    Ok(Probe::new(&url, &resp))
+}
+
+/// Probe a host over HTTPS, then over HTTP, `None` if neither answers
+pub async fn probe(client: &Client, host: &str) -> Option<Probe> {
+    for scheme in ["https", "http"] {
+        let Ok(url) = Url::parse(&format!("{scheme}://{host}/")) else {
+            return None;
+        };
+
+        match probe_url(client, url).await {
+            Ok(p) => return Some(p),
+            Err(e) => debug!(host, scheme, err = %e, "failed to probe"),
+        }
+    }
+
+    None
+}
+
+/// Drop hosts that only answered over HTTP
+pub fn retain_https(probes: &mut HashMap<String, Probe>) {
+    probes.retain(|_, p| p.https);
+}
+
+/// Probe every host concurrently, dropping those that don't answer
+pub async fn probe_all(client: Client, hosts: HashSet<String>) -> HashMap<String, Probe> {
+    let permits = Arc::new(Semaphore::new(CONCURRENCY));
+    let mut join_set = JoinSet::new();
+
+    for host in hosts {
+        let c = client.clone();
+        let p = permits.clone();
+        join_set.spawn(async move {
+            let _permit = p.acquire_owned().await.expect("semaphore is never closed");
+            let probed = probe(&c, &host).await;
+            (host, probed)
+        });
+    }
+
+    join_set
+        .join_all()
+        .await
+        .into_iter()
+        .filter_map(|(host, probed)| {
+            let probed = probed?;
+            info!(
+                host,
+                status = %probed.status,
+                liveness = ?probed.liveness,
+                redirect = ?probed.redirect,
+                "probed"
+            );
+            Some((host, probed))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_probe_head() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let url = Url::parse(&server.uri()).unwrap();
+        let probed = probe_url(&Client::new(), url).await.unwrap();
+
+        assert_eq!(probed.status, StatusCode::NO_CONTENT);
+        assert_eq!(probed.liveness, Liveness::Live);
+        assert_eq!(probed.redirect, None);
+    }
+
+    #[tokio::test]
+    async fn test_probe_head_rejected() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(405))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(header("range", "bytes=0-0"))
+            .respond_with(ResponseTemplate::new(206).set_body_string("<"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let url = Url::parse(&server.uri()).unwrap();
+        let probed = probe_url(&Client::new(), url).await.unwrap();
+
+        assert_eq!(probed.status, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(probed.liveness, Liveness::Live);
+    }
+
+    #[tokio::test]
+    async fn test_probe_redirect_to_other_host() {
+        let target = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(403))
+            .mount(&target)
+            .await;
+        let location = target.uri().replace("127.0.0.1", "localhost");
+
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(301).insert_header("location", location))
+            .mount(&server)
+            .await;
+
+        let url = Url::parse(&server.uri()).unwrap();
+        let probed = probe_url(&Client::new(), url).await.unwrap();
+
+        assert_eq!(probed.status, StatusCode::FORBIDDEN);
+        assert_eq!(probed.liveness, Liveness::ClientError);
+        assert_eq!(probed.redirect.as_deref(), Some("localhost"));
+    }
+
+    #[tokio::test]
+    async fn test_probe_falls_back_to_http() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        // the mock server doesn't speak TLS, so only the HTTP probe gets an answer
+        let host = server.address().to_string();
+        let probed = probe(&Client::new(), &host).await.unwrap();
+
+        assert_eq!(probed.status, StatusCode::OK);
+        assert!(!probed.https);
+    }
+
+    #[test]
+    fn test_retain_https() {
+        let live = |https| Probe {
+            status: StatusCode::OK,
+            liveness: Liveness::Live,
+            redirect: None,
+            https,
+        };
+        let mut probes = HashMap::from([
+            ("app.example.com".to_owned(), live(true)),
+            ("staging.example.com".to_owned(), live(false)),
+            ("api.example.com".to_owned(), live(true)),
+        ]);
+
+        retain_https(&mut probes);
+
+        let mut hosts: Vec<_> = probes.into_keys().collect();
+        hosts.sort_unstable();
+        assert_eq!(hosts, ["api.example.com", "app.example.com"]);
+    }
+}
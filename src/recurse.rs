@@ -0,0 +1,179 @@
+use std::collections::{HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::enumerate::normalize_domain;
+use crate::normalize;
+
+/// Most subdomains a run enumerates recursively, on top of the targets it started with
+pub const MAX_RECURSIVE_TARGETS: usize = 256;
+
+/// Domains left to enumerate, for `--max-depth`
+///
+/// Subdomains found for a target are queued one level deeper than it, up to `max_depth`.
+/// Every name is targeted at most once, so results pointing back at an earlier target,
+/// e.g. through a CNAME chain, can't make the recursion loop.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Frontier {
+    queue: VecDeque<(String, usize)>,
+    targeted: HashSet<String>,
+    max_depth: usize,
+    /// Targets queued below the first level, capped at `max_recursive`
+    recursive: usize,
+    max_recursive: usize,
+    /// Names left out once the cap was reached, reported once at the end of the run
+    #[serde(default)]
+    skipped: usize,
+}
+
+impl Frontier {
+    #[must_use]
+    pub fn new(max_depth: usize, max_recursive: usize) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            targeted: HashSet::new(),
+            max_depth,
+            recursive: 0,
+            max_recursive,
+            skipped: 0,
+        }
+    }
+
+    /// Queue `name` to be enumerated at `depth`, returning whether it was queued
+    ///
+    /// Names deeper than `max_depth`, already targeted, or past the recursion cap are skipped.
+    pub fn push(&mut self, name: &str, depth: usize) -> bool {
+        if depth > self.max_depth {
+            return false;
+        }
+        let Ok(name) = normalize_domain(name) else {
+            debug!(name, "not a valid domain, not enumerating it");
+            return false;
+        };
+        if self.targeted.contains(&name) {
+            debug!(name, "already targeted");
+            return false;
+        }
+        if depth > 0 {
+            if self.recursive == self.max_recursive {
+                debug!(name, "past the recursion cap, not enumerating it");
+                // counted once however many targets find it
+                self.targeted.insert(name);
+                self.skipped += 1;
+                return false;
+            }
+            self.recursive += 1;
+        }
+
+        self.targeted.insert(name.clone());
+        self.queue.push_back((name, depth));
+        true
+    }
+
+    /// Queue the subdomains found for `target` at `depth` one level deeper,
+    /// names outside of `target` aren't enumerated further
+    pub fn push_found(&mut self, target: &str, depth: usize, found: &[String]) {
+        for name in found
+            .iter()
+            // wildcard markers aren't hosts to enumerate
+            .filter(|n| !n.starts_with("*.") && normalize::is_subdomain_of(n, target))
+        {
+            self.push(name, depth + 1);
+        }
+    }
+
+    /// Subdomains that weren't enumerated because the recursion cap was reached
+    #[must_use]
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Next domain to enumerate and its depth, shallowest first
+    pub fn pop(&mut self) -> Option<(String, usize)> {
+        self.queue.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    /// Enumerate everything queued, with `results` standing in for the engines
+    fn recurse(results: &HashMap<&str, Vec<&str>>, frontier: &mut Frontier) -> Vec<String> {
+        let mut enumerated = Vec::new();
+        while let Some((target, depth)) = frontier.pop() {
+            for name in results.get(target.as_str()).into_iter().flatten() {
+                frontier.push(name, depth + 1);
+            }
+            enumerated.push(target);
+        }
+        enumerated
+    }
+
+    #[test]
+    fn test_cycle_terminates() {
+        // every name's results lead back to names already targeted
+        let results = HashMap::from([
+            ("example.com", vec!["a.example.com", "b.example.com"]),
+            ("a.example.com", vec!["b.example.com", "A.example.com."]),
+            ("b.example.com", vec!["a.example.com", "example.com"]),
+        ]);
+        let mut frontier = Frontier::new(usize::MAX, MAX_RECURSIVE_TARGETS);
+        frontier.push("example.com", 0);
+
+        let enumerated = recurse(&results, &mut frontier);
+
+        assert_eq!(
+            enumerated,
+            ["example.com", "a.example.com", "b.example.com"]
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        // each level finds one level further down, forever
+        let names = [
+            "example.com",
+            "a.example.com",
+            "a.a.example.com",
+            "a.a.a.example.com",
+        ];
+        let results = names.windows(2).map(|w| (w[0], vec![w[1]])).collect();
+        let mut frontier = Frontier::new(2, MAX_RECURSIVE_TARGETS);
+        frontier.push("example.com", 0);
+
+        assert_eq!(recurse(&results, &mut frontier), names[..3]);
+    }
+
+    #[test]
+    fn test_max_recursive_targets() {
+        let results = HashMap::from([
+            (
+                "example.com",
+                vec!["a.example.com", "b.example.com", "c.example.com"],
+            ),
+            ("a.example.com", vec!["c.example.com", "d.example.com"]),
+        ]);
+        let mut frontier = Frontier::new(2, 2);
+        frontier.push("example.com", 0);
+
+        assert_eq!(
+            recurse(&results, &mut frontier),
+            ["example.com", "a.example.com", "b.example.com"]
+        );
+        // c.example.com is found twice but skipped once
+        assert_eq!(frontier.skipped(), 2);
+    }
+
+    #[test]
+    fn test_push_skips_invalid() {
+        let mut frontier = Frontier::new(1, MAX_RECURSIVE_TARGETS);
+
+        assert!(!frontier.push("https://app.example.com/", 0));
+        assert!(frontier.push("app.example.com", 0));
+        assert!(!frontier.push("APP.example.com", 1));
+    }
+}
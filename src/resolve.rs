@@ -0,0 +1,300 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use hickory_resolver::TokioResolver;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::debug;
+
+use crate::normalize;
+
+/// Maximum number of lookups in flight at once
+const CONCURRENCY: usize = 64;
+/// How long a single lookup may take by default, slower ones count as not resolving
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Resolved IP addresses of each subdomain
+pub type Resolved = HashMap<String, Vec<IpAddr>>;
+
+/// Looks up DNS records for discovered subdomains
+pub trait Resolver: Send + Sync + 'static {
+    /// IP addresses of `name`, empty if it doesn't resolve
+    fn lookup_ip(&self, name: &str) -> impl Future<Output = Vec<IpAddr>> + Send;
+
+    /// Hostnames from the PTR records of `ip`, empty if there are none
+    fn reverse(&self, ip: IpAddr) -> impl Future<Output = Vec<String>> + Send;
+}
+
+/// A resolver using the system DNS configuration
+pub struct SystemResolver(TokioResolver);
+
+impl SystemResolver {
+    pub fn new() -> anyhow::Result<Self> {
+        let resolver = TokioResolver::builder_tokio()?.build();
+        Ok(Self(resolver))
+    }
+}
+
+impl Resolver for SystemResolver {
+    async fn lookup_ip(&self, name: &str) -> Vec<IpAddr> {
+        match self.0.lookup_ip(name).await {
+            Ok(ips) => ips.iter().collect(),
+            Err(e) => {
+                debug!(name, err = %e, "failed to resolve");
+                Vec::new()
+            }
+        }
+    }
+
+    async fn reverse(&self, ip: IpAddr) -> Vec<String> {
+        match self.0.reverse_lookup(ip).await {
+            Ok(names) => names
+                .iter()
+                .map(|n| n.to_string().trim_end_matches('.').to_ascii_lowercase())
+                .collect(),
+            Err(e) => {
+                debug!(%ip, err = %e, "failed to reverse resolve");
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Whether `ip` is in a private, loopback, link-local or otherwise non-routable range
+pub fn is_private(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_private_v4(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_private_v4(v4),
+            None => is_private_v6(v6),
+        },
+    }
+}
+
+fn is_private_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_private() // 10/8, 172.16/12, 192.168/16
+        || ip.is_loopback() // 127/8
+        || ip.is_link_local() // 169.254/16
+        || ip.is_unspecified() // 0.0.0.0
+        || (a == 100 && (b & 0b1100_0000) == 64) // 100.64/10, carrier-grade NAT
+}
+
+fn is_private_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback() // ::1
+        || ip.is_unspecified() // ::
+        || (first & 0xfe00) == 0xfc00 // fc00::/7, unique local
+        || (first & 0xffc0) == 0xfe80 // fe80::/10, link-local
+}
+
+/// Drop subdomains that only resolve to private addresses,
+/// these are usually internal hosts leaked into public data
+pub fn retain_public(resolved: &mut Resolved) {
+    resolved.retain(|_, ips| ips.iter().any(|ip| !is_private(*ip)));
+}
+
+/// Resolve every name concurrently, dropping those without any address
+///
+/// A lookup that takes longer than `timeout` is given up on, as if the name didn't resolve.
+pub async fn resolve<R, I>(resolver: Arc<R>, names: I, timeout: Duration) -> Resolved
+where
+    R: Resolver,
+    I: IntoIterator<Item = String>,
+{
+    let permits = Arc::new(Semaphore::new(CONCURRENCY));
+    let mut join_set = JoinSet::new();
+
+    for name in names {
+        let r = resolver.clone();
+        let p = permits.clone();
+        join_set.spawn(async move {
+            let _permit = p.acquire_owned().await.expect("semaphore is never closed");
+            let ips = tokio::time::timeout(timeout, r.lookup_ip(&name))
+                .await
+                .unwrap_or_else(|_| {
+                    debug!(name, ?timeout, "timed out resolving");
+                    Vec::new()
+                });
+            (name, ips)
+        });
+    }
+
+    join_set
+        .join_all()
+        .await
+        .into_iter()
+        .filter(|(_, ips)| !ips.is_empty())
+        .collect()
+}
+
+/// Reverse resolve every address in `known` and return the in-scope hostnames
+/// that aren't already known, lookups taking longer than `timeout` find nothing
+pub async fn reverse<R: Resolver>(
+    resolver: Arc<R>,
+    domain: &str,
+    known: &Resolved,
+    timeout: Duration,
+) -> HashSet<String> {
+    let ips: HashSet<IpAddr> = known.values().flatten().copied().collect();
+
+    let permits = Arc::new(Semaphore::new(CONCURRENCY));
+    let mut join_set = JoinSet::new();
+
+    for ip in ips {
+        let r = resolver.clone();
+        let p = permits.clone();
+        join_set.spawn(async move {
+            let _permit = p.acquire_owned().await.expect("semaphore is never closed");
+            tokio::time::timeout(timeout, r.reverse(ip))
+                .await
+                .unwrap_or_else(|_| {
+                    debug!(%ip, ?timeout, "timed out reverse resolving");
+                    Vec::new()
+                })
+        });
+    }
+
+    join_set
+        .join_all()
+        .await
+        .into_iter()
+        .flatten()
+        .filter(|n| normalize::is_subdomain_of(n, domain) && !known.contains_key(n))
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    /// A resolver serving records from in-memory tables
+    #[derive(Default)]
+    pub struct StubResolver {
+        pub forward: HashMap<String, Vec<IpAddr>>,
+        pub ptr: HashMap<IpAddr, Vec<String>>,
+        /// How long looking up each name takes, instant if missing
+        pub latency: HashMap<String, Duration>,
+    }
+
+    impl Resolver for StubResolver {
+        async fn lookup_ip(&self, name: &str) -> Vec<IpAddr> {
+            if let Some(latency) = self.latency.get(name) {
+                tokio::time::sleep(*latency).await;
+            }
+            self.forward.get(name).cloned().unwrap_or_default()
+        }
+
+        async fn reverse(&self, ip: IpAddr) -> Vec<String> {
+            self.ptr.get(&ip).cloned().unwrap_or_default()
+        }
+    }
+
+    const APP: IpAddr = IpAddr::V4(Ipv4Addr::new(93, 184, 216, 34));
+    const MAIL: IpAddr = IpAddr::V4(Ipv4Addr::new(93, 184, 216, 35));
+
+    fn stub() -> StubResolver {
+        StubResolver {
+            forward: HashMap::from([
+                ("app.example.com".to_owned(), vec![APP]),
+                ("mail.example.com".to_owned(), vec![MAIL]),
+                ("edge-1.example.com".to_owned(), vec![APP]),
+            ]),
+            ptr: HashMap::from([
+                (
+                    APP,
+                    vec![
+                        "edge-1.example.com".to_owned(),
+                        "app.example.com".to_owned(),
+                    ],
+                ),
+                (MAIL, vec!["mx.hosting-provider.net".to_owned()]),
+            ]),
+            latency: HashMap::new(),
+        }
+    }
+
+    #[rstest]
+    #[case::private_10("10.1.2.3", true)]
+    #[case::private_172("172.16.0.1", true)]
+    #[case::public_172("172.32.0.1", false)]
+    #[case::private_192("192.168.1.1", true)]
+    #[case::loopback("127.0.0.1", true)]
+    #[case::link_local("169.254.169.254", true)]
+    #[case::unspecified("0.0.0.0", true)]
+    #[case::cgnat("100.64.0.1", true)]
+    #[case::public_100("100.128.0.1", false)]
+    #[case::public("93.184.216.34", false)]
+    #[case::v6_loopback("::1", true)]
+    #[case::v6_unique_local("fd12:3456:789a::1", true)]
+    #[case::v6_link_local("fe80::1", true)]
+    #[case::v6_mapped_private("::ffff:192.168.1.1", true)]
+    #[case::v6_public("2606:2800:220:1:248:1893:25c8:1946", false)]
+    fn test_is_private(#[case] ip: IpAddr, #[case] expected: bool) {
+        assert_eq!(is_private(ip), expected);
+    }
+
+    #[test]
+    fn test_retain_public() {
+        let private: IpAddr = "10.0.0.1".parse().unwrap();
+        let mut resolved = Resolved::from([
+            ("app.example.com".to_owned(), vec![APP]),
+            ("intranet.example.com".to_owned(), vec![private]),
+            ("mixed.example.com".to_owned(), vec![private, MAIL]),
+        ]);
+
+        retain_public(&mut resolved);
+
+        let mut kept: Vec<_> = resolved.into_keys().collect();
+        kept.sort_unstable();
+        assert_eq!(kept, vec!["app.example.com", "mixed.example.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_resolve() {
+        let names = ["app.example.com", "mail.example.com", "gone.example.com"].map(String::from);
+
+        let resolved = resolve(Arc::new(stub()), names, DEFAULT_TIMEOUT).await;
+
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved["app.example.com"], vec![APP]);
+        assert_eq!(resolved["mail.example.com"], vec![MAIL]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resolve_timeout() {
+        let slow = StubResolver {
+            latency: HashMap::from([
+                ("mail.example.com".to_owned(), Duration::from_secs(30)),
+                ("app.example.com".to_owned(), Duration::from_millis(500)),
+            ]),
+            ..stub()
+        };
+        let names = ["app.example.com", "mail.example.com"].map(String::from);
+
+        let resolved = resolve(Arc::new(slow), names, Duration::from_secs(1)).await;
+
+        // the hanging lookup is dropped, the slow but timely one is kept
+        assert_eq!(
+            resolved,
+            Resolved::from([("app.example.com".to_owned(), vec![APP])])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reverse() {
+        let dns = Arc::new(stub());
+        let names = ["app.example.com", "mail.example.com"].map(String::from);
+        let resolved = resolve(dns.clone(), names, DEFAULT_TIMEOUT).await;
+
+        let found = reverse(dns, "example.com", &resolved, DEFAULT_TIMEOUT).await;
+
+        // `app.example.com` is already known and the hosting provider's name is out of scope
+        assert_eq!(found, HashSet::from(["edge-1.example.com".to_owned()]));
+    }
+}
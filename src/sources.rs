@@ -0,0 +1,230 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use tokio::time::Instant;
+use tracing::debug;
+
+use crate::enumerate::EngineChoice;
+
+/// Where a subdomain was reported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Source {
+    Engine(EngineChoice),
+    /// Known subdomains given with `--input-subdomains`
+    Input,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Engine(engine) => engine.fmt(f),
+            Source::Input => f.write_str("Input"),
+        }
+    }
+}
+
+/// Engines that reported each subdomain
+pub type Sources = HashMap<String, HashSet<Source>>;
+
+/// When each subdomain was first recorded, whichever source reported it
+pub type Discovered = HashMap<String, Instant>;
+
+/// Record that `engine` found each of `subdomains`
+pub fn record<I>(sources: &mut Sources, engine: Source, subdomains: I)
+where
+    I: IntoIterator<Item = String>,
+{
+    for sub in subdomains {
+        sources.entry(sub).or_default().insert(engine);
+    }
+}
+
+/// Lowercase `name` and drop any trailing dot
+pub fn canonical(name: &str) -> String {
+    name.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Record that `engine` found each of `subdomains`, under their canonical form
+///
+/// Names are lowercased and lose any trailing dot, so `App.example.com.` and `app.example.com`
+/// from two engines count as one subdomain. Each name in another form is logged, to spot
+/// engines that return noncanonical names. Returns how many there were.
+pub fn record_canonical<I>(sources: &mut Sources, engine: Source, subdomains: I) -> usize
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut noncanonical = 0;
+    for sub in subdomains {
+        let canonical = canonical(&sub);
+        if canonical != sub {
+            noncanonical += 1;
+            let others: Vec<_> = sources
+                .get(&canonical)
+                .into_iter()
+                .flatten()
+                .filter(|e| **e != engine)
+                .collect();
+            debug!(%engine, reported = %sub, %canonical, ?others, "name reported in a noncanonical form");
+        }
+        sources.entry(canonical).or_default().insert(engine);
+    }
+    noncanonical
+}
+
+/// Keep only subdomains reported by at least `n` distinct engines
+pub fn retain_min(sources: &mut Sources, n: usize) {
+    sources.retain(|_, engines| engines.len() >= n);
+}
+
+/// Keep only subdomains that `engine` reported, whoever else did too
+pub fn retain_found_by(sources: &mut Sources, engine: EngineChoice) {
+    sources.retain(|_, engines| engines.contains(&Source::Engine(engine)));
+}
+
+/// Number of subdomains an engine found, and how many of them no other engine found
+#[derive(Debug, PartialEq, Eq)]
+pub struct Contribution {
+    pub engine: Source,
+    pub unique: usize,
+    pub total: usize,
+}
+
+/// Each engine's contribution, most unique results first
+pub fn contributions(sources: &Sources) -> Vec<Contribution> {
+    let mut counts: HashMap<Source, (usize, usize)> = HashMap::new();
+    for engines in sources.values() {
+        for &engine in engines {
+            let (unique, total) = counts.entry(engine).or_default();
+            *total += 1;
+            if engines.len() == 1 {
+                *unique += 1;
+            }
+        }
+    }
+
+    let mut report: Vec<_> = counts
+        .into_iter()
+        .map(|(engine, (unique, total))| Contribution {
+            engine,
+            unique,
+            total,
+        })
+        .collect();
+    report.sort_unstable_by(|a, b| b.unique.cmp(&a.unique).then(a.engine.cmp(&b.engine)));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const BING: Source = Source::Engine(EngineChoice::Bing);
+    const CRTSH: Source = Source::Engine(EngineChoice::CrtSh);
+    const VIRUSTOTAL: Source = Source::Engine(EngineChoice::VirusTotal);
+
+    fn sources() -> Sources {
+        let mut sources = Sources::new();
+        record(
+            &mut sources,
+            CRTSH,
+            ["app.example.com", "api.example.com", "mail.example.com"].map(String::from),
+        );
+        record(
+            &mut sources,
+            VIRUSTOTAL,
+            ["app.example.com", "api.example.com"].map(String::from),
+        );
+        record(
+            &mut sources,
+            BING,
+            ["app.example.com", "noise.example.com"].map(String::from),
+        );
+        sources
+    }
+
+    #[test]
+    fn test_record() {
+        let sources = sources();
+
+        assert_eq!(sources.len(), 4);
+        assert_eq!(
+            sources["app.example.com"],
+            HashSet::from([CRTSH, VIRUSTOTAL, BING])
+        );
+        assert_eq!(sources["noise.example.com"], HashSet::from([BING]));
+    }
+
+    #[test]
+    fn test_record_canonical() {
+        let mut sources = Sources::new();
+        let n = record_canonical(
+            &mut sources,
+            CRTSH,
+            ["app.example.com", "api.example.com"].map(String::from),
+        );
+        assert_eq!(n, 0);
+
+        let n = record_canonical(
+            &mut sources,
+            BING,
+            ["App.Example.com", "api.example.com.", "www.example.com"].map(String::from),
+        );
+        assert_eq!(n, 2);
+
+        let mut names: Vec<_> = sources.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        assert_eq!(
+            names,
+            ["api.example.com", "app.example.com", "www.example.com"]
+        );
+        assert_eq!(sources["app.example.com"], HashSet::from([CRTSH, BING]));
+    }
+
+    #[test]
+    fn test_contributions() {
+        let report = contributions(&sources());
+
+        let contribution = |engine, unique, total| Contribution {
+            engine,
+            unique,
+            total,
+        };
+        assert_eq!(
+            report,
+            vec![
+                contribution(BING, 1, 2),
+                contribution(CRTSH, 1, 3),
+                contribution(VIRUSTOTAL, 0, 2),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::one(1, vec!["api.example.com", "app.example.com", "mail.example.com", "noise.example.com"])]
+    #[case::two(2, vec!["api.example.com", "app.example.com"])]
+    #[case::three(3, vec!["app.example.com"])]
+    #[case::four(4, vec![])]
+    fn test_retain_min(#[case] n: usize, #[case] expected: Vec<&str>) {
+        let mut sources = sources();
+        retain_min(&mut sources, n);
+
+        let mut kept: Vec<_> = sources.into_keys().collect();
+        kept.sort_unstable();
+        assert_eq!(kept, expected);
+    }
+
+    #[rstest]
+    #[case::bing(EngineChoice::Bing, vec!["app.example.com", "noise.example.com"])]
+    #[case::crtsh(EngineChoice::CrtSh, vec!["api.example.com", "app.example.com", "mail.example.com"])]
+    #[case::none(EngineChoice::Yahoo, vec![])]
+    fn test_retain_found_by(#[case] engine: EngineChoice, #[case] expected: Vec<&str>) {
+        let mut sources = sources();
+        retain_found_by(&mut sources, engine);
+
+        let mut kept: Vec<_> = sources.into_keys().collect();
+        kept.sort_unstable();
+        assert_eq!(kept, expected);
+    }
+}
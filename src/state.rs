@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use strum::VariantArray;
+
+use crate::enumerate::EngineChoice;
+
+/// How many subdomains each engine found in the last run, kept between runs
+///
+/// ```json
+/// {"engines": {"crtsh": 42, "google": 0}}
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LastRun {
+    pub engines: HashMap<EngineChoice, usize>,
+}
+
+impl LastRun {
+    /// Default location of the state file, `$XDG_STATE_HOME/s7r/last-run.json`,
+    /// falling back to `~/.local/state/s7r/last-run.json`
+    pub fn default_path() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local").join("state"))
+            })?;
+
+        Some(base.join("s7r").join("last-run.json"))
+    }
+
+    /// Load the state saved at `path`
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read or doesn't hold a saved state
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read state file {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("failed to parse state file {}", path.display()))
+    }
+
+    /// Save the state to `path`, creating its directory if needed
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be written
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write state file {}", path.display()))
+    }
+
+    /// Engines that found at least one subdomain, in the order they're listed in
+    #[must_use]
+    pub fn successful(&self) -> Vec<EngineChoice> {
+        EngineChoice::VARIANTS
+            .iter()
+            .filter(|e| self.engines.get(e).is_some_and(|&n| n > 0))
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_run() -> LastRun {
+        LastRun {
+            engines: HashMap::from([
+                (EngineChoice::VirusTotal, 12),
+                (EngineChoice::Google, 0),
+                (EngineChoice::CrtSh, 42),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("s7r").join("last-run.json");
+
+        last_run().save(&path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains(r#""crtsh": 42"#));
+        assert_eq!(LastRun::load(&path).unwrap(), last_run());
+    }
+
+    #[test]
+    fn test_successful() {
+        assert_eq!(
+            last_run().successful(),
+            [EngineChoice::CrtSh, EngineChoice::VirusTotal]
+        );
+        assert!(LastRun::default().successful().is_empty());
+    }
+}
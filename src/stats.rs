@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::enumerate::EngineChoice;
+
+/// What a run found for one domain
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunStats {
+    pub domain: String,
+    /// Subdomains found, once post-processed
+    pub subdomains: Vec<String>,
+    /// How many subdomains each engine found, before post-processing
+    pub engines: BTreeMap<EngineChoice, usize>,
+    pub elapsed: Duration,
+}
+
+/// Render runs in the Prometheus text exposition format, one sample per domain
+#[must_use]
+pub fn to_prometheus(runs: &[RunStats]) -> String {
+    let mut out = String::new();
+
+    metric(
+        &mut out,
+        "s7r_subdomains_found",
+        "Subdomains found for a domain",
+    );
+    for run in runs {
+        let domain = escape(&run.domain);
+        let _ = writeln!(
+            out,
+            "s7r_subdomains_found{{domain=\"{domain}\"}} {}",
+            run.subdomains.len()
+        );
+    }
+
+    metric(
+        &mut out,
+        "s7r_engine_subdomains_found",
+        "Subdomains an engine found for a domain, before post-processing",
+    );
+    for run in runs {
+        let domain = escape(&run.domain);
+        for (engine, count) in &run.engines {
+            let engine = escape(&engine.to_string());
+            let _ = writeln!(
+                out,
+                "s7r_engine_subdomains_found{{domain=\"{domain}\",engine=\"{engine}\"}} {count}"
+            );
+        }
+    }
+
+    metric(
+        &mut out,
+        "s7r_run_duration_seconds",
+        "Time enumerating a domain took",
+    );
+    for run in runs {
+        let domain = escape(&run.domain);
+        let _ = writeln!(
+            out,
+            "s7r_run_duration_seconds{{domain=\"{domain}\"}} {}",
+            run.elapsed.as_secs_f64()
+        );
+    }
+
+    out
+}
+
+/// Write [`to_prometheus`] to `path`, for `node_exporter`'s textfile collector
+///
+/// The metrics go to a temporary file next to `path` which then replaces it,
+/// so the collector never reads a partially written file.
+///
+/// # Errors
+///
+/// If the file can't be written or moved into place
+pub fn write_prometheus(path: &Path, runs: &[RunStats]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(to_prometheus(runs).as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)
+}
+
+fn metric(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+}
+
+/// Escape a label value, backslashes, double quotes and newlines can't appear as is
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use super::*;
+
+    fn runs() -> Vec<RunStats> {
+        vec![
+            RunStats {
+                domain: "example.com".to_owned(),
+                subdomains: vec!["api.example.com".to_owned(), "app.example.com".to_owned()],
+                engines: BTreeMap::from([(EngineChoice::Bing, 1), (EngineChoice::CrtSh, 2)]),
+                elapsed: Duration::from_millis(1500),
+            },
+            RunStats {
+                domain: "example.net".to_owned(),
+                ..RunStats::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_to_prometheus() {
+        let text = to_prometheus(&runs());
+
+        assert!(text.contains("s7r_subdomains_found{domain=\"example.com\"} 2\n"));
+        assert!(text.contains("s7r_subdomains_found{domain=\"example.net\"} 0\n"));
+        assert!(
+            text.contains(
+                "s7r_engine_subdomains_found{domain=\"example.com\",engine=\"CrtSh\"} 2\n"
+            )
+        );
+        assert!(text.contains("s7r_run_duration_seconds{domain=\"example.com\"} 1.5\n"));
+    }
+
+    #[test]
+    fn test_to_prometheus_is_valid() {
+        let sample = Regex::new(
+            r#"^([a-zA-Z_:][a-zA-Z0-9_:]*)\{([a-z]+="(?:[^"\\]|\\.)*")(,[a-z]+="(?:[^"\\]|\\.)*")*\} [0-9]+(\.[0-9]+)?$"#,
+        )
+        .unwrap();
+        let mut declared = Vec::new();
+
+        for line in to_prometheus(&runs()).lines() {
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let (name, kind) = rest.split_once(' ').unwrap();
+                assert_eq!(kind, "gauge");
+                declared.push(name.to_owned());
+            } else if !line.starts_with("# HELP ") {
+                let caps = sample.captures(line).unwrap_or_else(|| panic!("{line}"));
+                // every sample follows the declaration of its metric
+                assert_eq!(declared.last().map(String::as_str), Some(&caps[1]));
+            }
+        }
+        assert_eq!(declared.len(), 3);
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape(r#"a"b\c"#), r#"a\"b\\c"#);
+        assert_eq!(escape("a\nb"), r"a\nb");
+    }
+
+    #[test]
+    fn test_write_prometheus() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("s7r.prom");
+        std::fs::write(&path, "stale").unwrap();
+
+        write_prometheus(&path, &runs()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            to_prometheus(&runs())
+        );
+        // only the final file is left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
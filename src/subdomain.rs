@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use clap::ValueEnum;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use tokio::time::Instant;
+
+use crate::apex;
+use crate::enumerate::FirstSeen;
+use crate::probe::Probe;
+use crate::resolve::Resolved;
+use crate::sources::Discovered;
+
+/// A discovered subdomain with whatever was learned about it after enumeration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subdomain {
+    pub name: String,
+    /// Resolved IP addresses, empty if not resolved
+    pub ips: Vec<IpAddr>,
+    /// Final status of the HTTP probe, `None` if not probed or no answer
+    pub status: Option<StatusCode>,
+    /// Earliest certificate for this subdomain, in seconds since the Unix epoch, if known
+    pub first_seen: Option<i64>,
+    /// When an engine, or the input, first reported it, `None` if found after enumeration
+    pub discovered: Option<Instant>,
+}
+
+impl Subdomain {
+    /// Build a subdomain from the resolve and probe results, either of which may be missing
+    pub(crate) fn enriched(
+        name: String,
+        resolved: Option<&Resolved>,
+        probes: Option<&HashMap<String, Probe>>,
+        first_seen: &FirstSeen,
+        discovered: &Discovered,
+    ) -> Self {
+        let ips = resolved
+            .and_then(|r| r.get(&name))
+            .cloned()
+            .unwrap_or_default();
+        let status = probes.and_then(|p| p.get(&name)).map(|p| p.status);
+        let first_seen = first_seen.get(&name).copied();
+        let discovered = discovered.get(&name).copied();
+        Self {
+            name,
+            ips,
+            status,
+            first_seen,
+            discovered,
+        }
+    }
+
+    /// Whether the first certificate for this subdomain is less than `window` older than `now`,
+    /// a hint that it was only recently provisioned
+    #[must_use]
+    pub fn is_new(&self, now: i64, window: Duration) -> bool {
+        let window = i64::try_from(window.as_secs()).unwrap_or(i64::MAX);
+        self.first_seen
+            .is_some_and(|t| now.saturating_sub(t) < window)
+    }
+
+    /// Lowest resolved address, the one sorted on
+    fn ip(&self) -> Option<IpAddr> {
+        self.ips.iter().min().copied()
+    }
+}
+
+/// Field the output is sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    /// Alphabetically by name
+    #[default]
+    Name,
+    /// By lowest resolved IP address, unresolved last
+    Ip,
+    /// By probe status code, unprobed last
+    Status,
+}
+
+/// Sort subdomains by `by`, ties and missing values ordered by name
+///
+/// Names are grouped by registrable domain first, so hosts of other domains (e.g. pivoted to
+/// with `--pivot-scope all`) stay together, see [`apex::group`].
+pub fn sort(subdomains: &mut [Subdomain], by: SortBy) {
+    let order: HashMap<String, usize> = apex::group(subdomains.iter().map(|s| s.name.clone()))
+        .into_values()
+        .flatten()
+        .enumerate()
+        .map(|(i, name)| (name, i))
+        .collect();
+    subdomains.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    // stable, names without a registrable domain go last in name order
+    subdomains.sort_by_key(|s| order.get(&s.name).copied().unwrap_or(usize::MAX));
+
+    // stable, so subdomains with the same key stay in name order
+    match by {
+        SortBy::Name => {}
+        SortBy::Ip => subdomains.sort_by_key(|s| missing_last(s.ip())),
+        SortBy::Status => subdomains.sort_by_key(|s| missing_last(s.status.map(|c| c.as_u16()))),
+    }
+}
+
+/// Sort key putting `None` after every `Some`
+fn missing_last<T: Ord>(value: Option<T>) -> (bool, Option<T>) {
+    (value.is_none(), value)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn sub(name: &str, ips: &[&str], status: Option<u16>) -> Subdomain {
+        Subdomain {
+            name: name.to_owned(),
+            ips: ips.iter().map(|ip| ip.parse().unwrap()).collect(),
+            status: status.map(|s| StatusCode::from_u16(s).unwrap()),
+            first_seen: None,
+            discovered: None,
+        }
+    }
+
+    fn dataset() -> Vec<Subdomain> {
+        vec![
+            sub("www.example.com", &["93.184.216.34"], Some(200)),
+            sub("dev.example.com", &[], None),
+            sub("api.example.com", &["10.0.0.2", "10.0.0.1"], Some(403)),
+            sub("mail.example.com", &["2001:db8::1"], None),
+            sub("app.example.com", &["10.0.0.1"], Some(200)),
+            sub("old.example.com", &[], Some(502)),
+        ]
+    }
+
+    #[rstest]
+    #[case::name(
+        SortBy::Name,
+        &["api", "app", "dev", "mail", "old", "www"]
+    )]
+    // api and app share 10.0.0.1, IPv6 after IPv4, unresolved last
+    #[case::ip(
+        SortBy::Ip,
+        &["api", "app", "www", "mail", "dev", "old"]
+    )]
+    #[case::status(
+        SortBy::Status,
+        &["app", "www", "api", "old", "dev", "mail"]
+    )]
+    fn test_sort(#[case] by: SortBy, #[case] expected: &[&str]) {
+        let mut subdomains = dataset();
+        sort(&mut subdomains, by);
+
+        let names: Vec<_> = subdomains
+            .iter()
+            .map(|s| s.name.trim_end_matches(".example.com"))
+            .collect();
+        assert_eq!(names, expected);
+    }
+
+    #[test]
+    fn test_sort_groups_by_apex() {
+        let mut subdomains = [
+            "www.example.co.uk",
+            "app.example.com",
+            "localhost",
+            "api.example.co.uk",
+            "mail.example.com",
+        ]
+        .map(|name| sub(name, &[], None));
+        sort(&mut subdomains, SortBy::Name);
+
+        let names: Vec<_> = subdomains.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "api.example.co.uk",
+                "www.example.co.uk",
+                "app.example.com",
+                "mail.example.com",
+                "localhost"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_enriched() {
+        let resolved = Resolved::from([(
+            "app.example.com".to_owned(),
+            vec!["10.0.0.1".parse().unwrap()],
+        )]);
+
+        let first_seen = FirstSeen::from([("dev.example.com".to_owned(), 1_700_000_000)]);
+
+        let s = Subdomain::enriched(
+            "app.example.com".to_owned(),
+            Some(&resolved),
+            None,
+            &first_seen,
+            &Discovered::new(),
+        );
+        assert_eq!(s, sub("app.example.com", &["10.0.0.1"], None));
+
+        let s = Subdomain::enriched(
+            "dev.example.com".to_owned(),
+            Some(&resolved),
+            None,
+            &first_seen,
+            &Discovered::new(),
+        );
+        assert_eq!(
+            s,
+            Subdomain {
+                first_seen: Some(1_700_000_000),
+                ..sub("dev.example.com", &[], None)
+            }
+        );
+    }
+
+    #[rstest]
+    #[case::yesterday(Some(NOW - DAY), true)]
+    #[case::issued_ahead(Some(NOW + DAY), true)]
+    #[case::last_month(Some(NOW - 30 * DAY), false)]
+    #[case::unknown(None, false)]
+    fn test_is_new(#[case] first_seen: Option<i64>, #[case] expected: bool) {
+        const WEEK: Duration = Duration::from_secs(7 * DAY as u64);
+        let s = Subdomain {
+            first_seen,
+            ..sub("app.example.com", &[], None)
+        };
+        assert_eq!(s.is_new(NOW, WEEK), expected);
+    }
+
+    const NOW: i64 = 1_700_000_000;
+    const DAY: i64 = 86_400;
+}
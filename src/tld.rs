@@ -0,0 +1,84 @@
+use std::path::Path;
+
+use anyhow::Context;
+
+/// TLDs tried by `--tld-expand` when no `--tld-list` is given
+pub const COMMON_TLDS: [&str; 20] = [
+    "com", "net", "org", "io", "co", "dev", "app", "ai", "info", "biz", "us", "uk", "de", "fr",
+    "eu", "ca", "au", "in", "jp", "xyz",
+];
+
+/// Combine a brand with every TLD, e.g. `example` becomes `example.com`, `example.net`, ...
+///
+/// TLDs are lowercased and may be written with or without a leading dot, duplicates are dropped.
+pub fn expand<S: AsRef<str>>(brand: &str, tlds: &[S]) -> Vec<String> {
+    let mut domains: Vec<String> = Vec::with_capacity(tlds.len());
+
+    for tld in tlds {
+        let tld = tld.as_ref().trim().trim_start_matches('.');
+        if tld.is_empty() {
+            continue;
+        }
+
+        let domain = format!("{brand}.{}", tld.to_ascii_lowercase());
+        if !domains.contains(&domain) {
+            domains.push(domain);
+        }
+    }
+
+    domains
+}
+
+/// Read a TLD list, one per line
+///
+/// Blank lines and `#` comments are skipped.
+///
+/// # Errors
+///
+/// If the file can't be read
+pub fn load(path: &Path) -> anyhow::Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    #[test]
+    fn test_expand_common() {
+        let domains = expand("example", &COMMON_TLDS);
+
+        assert_eq!(domains.len(), COMMON_TLDS.len());
+        assert_eq!(domains[..3], ["example.com", "example.net", "example.org"]);
+    }
+
+    #[test]
+    fn test_expand_custom() {
+        let tlds = ["COM", ".net", "co.uk", "", "com"];
+        let domains = expand("example", &tlds);
+
+        assert_eq!(domains, ["example.com", "example.net", "example.co.uk"]);
+    }
+
+    #[test]
+    fn test_load() {
+        let mut f = NamedTempFile::new().unwrap();
+        f.write_all(b"# registered\ncom\n\n  .io\n").unwrap();
+
+        let tlds = load(f.path()).unwrap();
+        assert_eq!(tlds, ["com", ".io"]);
+        assert_eq!(expand("example", &tlds), ["example.com", "example.io"]);
+    }
+}
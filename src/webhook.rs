@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use reqwest::{Client, StatusCode, Url};
+use serde::Serialize;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tracing::{debug, warn};
+
+/// Attempts at delivering a payload before giving up on it
+const ATTEMPTS: u32 = 3;
+/// Wait before the first retry, doubled before each next one
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Give up on a delivery the webhook doesn't answer within this time
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A subdomain sent to the webhook as soon as an engine finds it, with `--webhook-stream`
+#[derive(Debug, Serialize)]
+pub struct Discovery<'a> {
+    pub domain: &'a str,
+    pub subdomain: &'a str,
+}
+
+/// POST `payload` as JSON to `url`, retrying transient failures
+///
+/// A webhook that can't be reached never fails the run, it's only logged.
+/// Returns whether the payload was delivered.
+pub async fn post<T: Serialize>(client: &Client, url: &Url, payload: &T) -> bool {
+    let mut delay = RETRY_DELAY;
+    for attempt in 1..=ATTEMPTS {
+        let res = client
+            .post(url.clone())
+            .timeout(TIMEOUT)
+            .json(payload)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+        match res {
+            Ok(_) => return true,
+            Err(e) if attempt < ATTEMPTS && is_transient(&e) => {
+                debug!(attempt, err = %e, "webhook delivery failed, retrying");
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Err(e) => {
+                warn!(%url, err = %e, "failed to deliver results to the webhook");
+                return false;
+            }
+        }
+    }
+    false
+}
+
+/// POST every name received to `url` until all senders are gone
+///
+/// Streaming stops at the first name that can't be delivered, so an unreachable webhook
+/// doesn't hold up the run with retries for every later name.
+pub async fn stream(client: Client, url: Url, domain: String, mut rx: UnboundedReceiver<String>) {
+    while let Some(subdomain) = rx.recv().await {
+        let discovery = Discovery {
+            domain: &domain,
+            subdomain: &subdomain,
+        };
+        if !post(&client, &url, &discovery).await {
+            warn!(%url, "not streaming further subdomains to the webhook");
+            rx.close();
+            return;
+        }
+    }
+}
+
+/// Whether retrying could help: the webhook couldn't be reached, or is overloaded or failing
+fn is_transient(e: &reqwest::Error) -> bool {
+    match e.status() {
+        Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+        None => e.is_connect() || e.is_timeout(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{body_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    // real time: a paused clock would let the request timeout fire while waiting on the server
+    #[tokio::test]
+    async fn test_post_retries_transient_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_json(
+                serde_json::json!({"domain": "example.com", "subdomain": "app.example.com"}),
+            ))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&server)
+            .await;
+
+        let url = Url::parse(&server.uri()).unwrap();
+        let discovery = Discovery {
+            domain: "example.com",
+            subdomain: "app.example.com",
+        };
+
+        assert!(post(&Client::new(), &url, &discovery).await);
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_post_gives_up_on_client_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let url = Url::parse(&server.uri()).unwrap();
+
+        assert!(!post(&Client::new(), &url, &"payload").await);
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,321 @@
+use std::io::ErrorKind;
+use std::net::TcpListener;
+use std::process::Command;
+
+/// The binary, keeping whatever state it saves out of the developer's home directory
+fn s7r_command() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_s7r"));
+    command.env("XDG_STATE_HOME", env!("CARGO_TARGET_TMPDIR"));
+    command
+}
+
+fn s7r(args: &[&str]) -> Option<i32> {
+    s7r_command()
+        .args(args)
+        .output()
+        .expect("s7r should run")
+        .status
+        .code()
+}
+
+#[test]
+fn test_completion_succeeds() {
+    assert_eq!(s7r(&["--completion", "bash"]), Some(0));
+}
+
+#[test]
+fn test_ip_address_is_invalid_domain() {
+    assert_eq!(s7r(&["-d", "127.0.0.1"]), Some(2));
+}
+
+#[test]
+fn test_unparsable_domain_is_invalid() {
+    assert_eq!(s7r(&["-d", "not a domain"]), Some(2));
+}
+
+#[test]
+fn test_unknown_flag_is_invalid() {
+    assert_eq!(s7r(&["-d", "example.com", "--no-such-flag"]), Some(2));
+}
+
+#[test]
+fn test_dry_run_prints_one_line_per_engine() {
+    // no mock server: any request would fail or hang, the dry run must not send one
+    let output = s7r_command()
+        .args([
+            "-d",
+            "example.com",
+            "-e",
+            "crtsh,bing,hackertarget",
+            "--dry-run",
+        ])
+        .env("HTTPS_PROXY", "http://127.0.0.1:9")
+        .env("HTTP_PROXY", "http://127.0.0.1:9")
+        .output()
+        .expect("s7r should run");
+
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.lines().count(), 3);
+    assert!(stdout.contains("crt.sh"));
+}
+
+#[test]
+fn test_engine_without_credentials_is_skipped() {
+    let run = s7r_command()
+        .args(["-d", "example.com", "-e", "fofa", "--no-banner"])
+        .env_remove("FOFA_EMAIL")
+        .env_remove("FOFA_KEY")
+        .env("HTTPS_PROXY", "http://127.0.0.1:9")
+        .env("HTTP_PROXY", "http://127.0.0.1:9")
+        .output()
+        .expect("s7r should run");
+
+    // nothing found, rather than every engine failing
+    assert_eq!(run.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&run.stdout);
+    assert!(stdout.contains("FOFA_EMAIL, FOFA_KEY"), "{stdout}");
+}
+
+#[test]
+fn test_format_none_prints_no_results() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("known.txt");
+    let output = dir.path().join("out.txt");
+    std::fs::write(&input, "app.example.com\n").unwrap();
+
+    // no engines: only the known subdomains are post-processed, nothing goes over the network
+    let run = s7r_command()
+        .args([
+            "-d",
+            "example.com",
+            "--format",
+            "none",
+            "--input-subdomains",
+        ])
+        .arg(&input)
+        .arg("-o")
+        .arg(&output)
+        .arg("--also-stdout")
+        .output()
+        .expect("s7r should run");
+
+    assert_eq!(run.status.code(), Some(0));
+    assert!(!String::from_utf8_lossy(&run.stdout).contains("app.example.com"));
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        "app.example.com\n"
+    );
+}
+
+#[test]
+fn test_format_json_prints_only_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("known.txt");
+    std::fs::write(&input, "www.example.com\napp.example.com\n").unwrap();
+
+    // no engines: only the known subdomains are post-processed, nothing goes over the network
+    let run = s7r_command()
+        .args(["-d", "example.com", "-f", "json", "--input-subdomains"])
+        .arg(&input)
+        .output()
+        .expect("s7r should run");
+
+    assert_eq!(run.status.code(), Some(0));
+    // no banner, warnings or progress around the results
+    let results: serde_json::Value = serde_json::from_slice(&run.stdout).unwrap();
+    assert_eq!(results["domain"], "example.com");
+    assert_eq!(
+        results["subdomains"],
+        serde_json::json!(["app.example.com", "www.example.com"])
+    );
+    assert_eq!(results["count"], 2);
+    assert!(results["discovered_ms"]["app.example.com"].is_u64());
+}
+
+#[test]
+fn test_format_json_keeps_sources_report_off_stdout() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("known.txt");
+    std::fs::write(&input, "app.example.com\n").unwrap();
+
+    let run = s7r_command()
+        .args(["-d", "example.com", "-f", "json", "--sources-report"])
+        .arg("--input-subdomains")
+        .arg(&input)
+        .output()
+        .expect("s7r should run");
+
+    assert_eq!(run.status.code(), Some(0));
+    let results: serde_json::Value = serde_json::from_slice(&run.stdout).unwrap();
+    assert_eq!(results["count"], 1);
+    assert!(String::from_utf8_lossy(&run.stderr).contains("1 unique / 1 total"));
+}
+
+#[test]
+fn test_resume_skips_finished_engines() {
+    let dir = tempfile::tempdir().unwrap();
+    let checkpoint = dir.path().join("checkpoint.json");
+    // interrupted once crt.sh was done with the only target
+    std::fs::write(
+        &checkpoint,
+        r#"{
+            "frontier": {"queue": [], "targeted": ["example.com"], "max_depth": 0, "recursive": 0, "max_recursive": 256},
+            "runs": [],
+            "current": {"domain": "example.com", "depth": 0, "engines": {"crtsh": ["app.example.com"]}}
+        }"#,
+    )
+    .unwrap();
+
+    // any request would fail, crt.sh's results come from the checkpoint
+    let run = s7r_command()
+        .arg("--resume")
+        .arg(&checkpoint)
+        .arg("--checkpoint")
+        .arg(&checkpoint)
+        .args(["-e", "crtsh", "--no-banner"])
+        .env("HTTPS_PROXY", "http://127.0.0.1:9")
+        .env("HTTP_PROXY", "http://127.0.0.1:9")
+        .output()
+        .expect("s7r should run");
+
+    assert_eq!(run.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&run.stdout).contains("app.example.com"));
+    let saved = std::fs::read_to_string(&checkpoint).unwrap();
+    assert!(saved.contains(r#""current":null"#), "{saved}");
+}
+
+#[test]
+fn test_color_never_prints_no_escapes() {
+    let dry_run = |color: &str| {
+        let output = s7r_command()
+            .args([
+                "-d",
+                "example.com",
+                "-e",
+                "crtsh",
+                "--dry-run",
+                "--color",
+                color,
+            ])
+            // would turn colors on despite stdout being a pipe
+            .env("FORCE_COLOR", "1")
+            .env_remove("NO_COLOR")
+            .output()
+            .expect("s7r should run");
+        assert_eq!(output.status.code(), Some(0));
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    assert!(dry_run("always").contains('\x1b'));
+    assert!(!dry_run("never").contains('\x1b'));
+}
+
+#[test]
+fn test_no_banner_keeps_warnings() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("known.txt");
+    std::fs::write(&input, "app.example.com\n").unwrap();
+
+    // no engines: only the known subdomains are post-processed, nothing goes over the network
+    let run = |args: &[&str]| {
+        let output = s7r_command()
+            .args(["-d", "example.com", "--input-subdomains"])
+            .arg(&input)
+            .args(args)
+            .output()
+            .expect("s7r should run");
+        assert_eq!(output.status.code(), Some(0));
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+
+    let stdout = run(&[]);
+    assert!(stdout.contains("@nt54hamnghi"));
+    assert!(stdout.contains("[!] Users are responsible for their actions."));
+
+    let stdout = run(&["--no-banner"]);
+    assert!(!stdout.contains("@nt54hamnghi"));
+    assert!(stdout.contains("[!] Users are responsible for their actions."));
+}
+
+#[test]
+fn test_only_extract_parses_saved_page() {
+    let dir = tempfile::tempdir().unwrap();
+    let page = dir.path().join("page.html");
+    std::fs::write(
+        &page,
+        r#"<html><body><ol id="b_results">
+        <li class="b_algo"><cite>https://app.example.com › login</cite></li>
+        <li class="b_algo"><cite>https://api.example.com</cite></li>
+        <li class="b_algo"><cite>https://www.evil.net/app.example.com</cite></li>
+        </ol></body></html>"#,
+    )
+    .unwrap();
+
+    // every request would go through this proxy, it must never see a connection
+    let proxy = TcpListener::bind("127.0.0.1:0").unwrap();
+    proxy.set_nonblocking(true).unwrap();
+    let proxy_url = format!("http://{}", proxy.local_addr().unwrap());
+
+    let output = s7r_command()
+        .args([
+            "--only-extract",
+            "--engines",
+            "bing",
+            "-d",
+            "example.com",
+            "--input-file",
+        ])
+        .arg(&page)
+        .env("HTTPS_PROXY", &proxy_url)
+        .env("HTTP_PROXY", &proxy_url)
+        .output()
+        .expect("s7r should run");
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "api.example.com\napp.example.com\n"
+    );
+    let err = proxy.accept().unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::WouldBlock);
+}
+
+#[test]
+fn test_only_extract_takes_a_single_engine() {
+    let code = s7r(&[
+        "--only-extract",
+        "-e",
+        "bing,google",
+        "-d",
+        "example.com",
+        "--input-file",
+        "page.html",
+    ]);
+    assert_eq!(code, Some(2));
+}
+
+#[test]
+fn test_last_run_only_saved_when_asked() {
+    let state = tempfile::tempdir().unwrap();
+    let path = state.path().join("s7r").join("last-run.json");
+    let run = |save: bool| {
+        let mut command = s7r_command();
+        // every search fails at once against the closed proxy port
+        command
+            .args(["-d", "example.com", "-e", "crtsh", "--circuit-breaker", "1"])
+            .env("XDG_STATE_HOME", state.path())
+            .env("HTTPS_PROXY", "http://127.0.0.1:9")
+            .env("HTTP_PROXY", "http://127.0.0.1:9");
+        if save {
+            command.arg("--save-last-run");
+        }
+        command.output().expect("s7r should run").status.code()
+    };
+
+    assert_eq!(run(false), Some(3));
+    assert!(!path.exists());
+
+    assert_eq!(run(true), Some(3));
+    assert!(path.exists());
+}